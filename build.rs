@@ -1,7 +1,148 @@
 //! A connector that fetches data from the HTS service and dumps it into `InfluxDB`.
 
+/// Settings that have defaults in the crate and are only forwarded when set.
+const OPTIONAL_VARS: &[&str] = &[
+    "DRY_RUN_OUTPUT",
+    "EXPORT_DIR",
+    "EXPORT_PREFIX",
+    "EXPORT_MAX_BYTES",
+    "EXPORT_GZIP",
+    "EXPORT_FLUSH_INTERVAL_MS",
+    "GRPC_ADDR",
+    "GRPC_CAPACITY",
+    "KAFKA_BROKERS",
+    "KAFKA_CANDLE_TOPIC",
+    "KAFKA_INDICATOR_TOPIC",
+    "KAFKA_FORMAT",
+    "KAFKA_DELIVERY_TIMEOUT_MS",
+    "MQTT_HOST",
+    "MQTT_PORT",
+    "MQTT_CLIENT_ID",
+    "MQTT_USERNAME",
+    "MQTT_PASSWORD",
+    "MQTT_TOPIC",
+    "MQTT_QOS",
+    "MQTT_RETAIN",
+    "MQTT_CAPACITY",
+    "MQTT_RECONNECT_BACKOFF_MS",
+    "NORMALIZED_DIR",
+    "NORMALIZED_PREFIX",
+    "NORMALIZED_FORMAT",
+    "PARQUET_DIR",
+    "PARQUET_BATCH_ROWS",
+    "PARQUET_MAX_BYTES",
+    "PARQUET_MAX_FILE_AGE_MS",
+    "POSTGRES_URL",
+    "POSTGRES_TIMESCALE",
+    "POSTGRES_BATCH_SIZE",
+    "POSTGRES_FLUSH_INTERVAL_MS",
+    "POSTGRES_RECONNECT_BACKOFF_MS",
+    "POSTGRES_MAX_BUFFERED_ROWS",
+    "QUESTDB_ADDR",
+    "QUESTDB_BUFFER_BYTES",
+    "QUESTDB_FLUSH_INTERVAL_MS",
+    "QUESTDB_RECONNECT_BACKOFF_MS",
+    "QUESTDB_MAX_BUFFER_BYTES",
+    "SQLITE_PATH",
+    "TEXT_FILE_ENCODING",
+    "WEBHOOK_URL",
+    "WEBHOOK_SECRET",
+    "WEBHOOK_TEMPLATE",
+    "WEBHOOK_BATCH_SIZE",
+    "WEBHOOK_FLUSH_INTERVAL_MS",
+    "WEBHOOK_TIMEOUT_MS",
+    "WEBHOOK_MAX_RETRIES",
+    "WEBHOOK_RETRY_BACKOFF_MS",
+    "WEBHOOK_MAX_BACKOFF_MS",
+    "WEBHOOK_CAPACITY",
+    "WEBSOCKET_ADDR",
+    "WEBSOCKET_CAPACITY",
+    "INFLUXDB_API_VERSION",
+    "INFLUXDB_DATABASE",
+    "INFLUXDB_RETENTION_POLICY",
+    "INFLUXDB_USERNAME",
+    "INFLUXDB_PASSWORD",
+    "INFLUXDB_PRECISION",
+    "INFLUXDB_GZIP",
+    "INFLUXDB_GZIP_LEVEL",
+    "INFLUXDB_TIMEOUT_MS",
+    "INFLUXDB_QUERY_LANGUAGE",
+    "INFLUXDB_MAX_RETRIES",
+    "INFLUXDB_RETRY_BACKOFF_MS",
+    "INFLUXDB_MAX_RETRY_AFTER_SECONDS",
+    "INFLUXDB_DEAD_LETTER_FILE",
+    "INFLUXDB_TARGETS",
+    "INFLUXDB_BATCH_SIZE",
+    "INFLUXDB_FLUSH_INTERVAL_MS",
+    "INFLUXDB_SPOOL_DIR",
+    "INFLUXDB_QUEUE_CAPACITY",
+    "INFLUXDB_MAX_SPOOLED_POINTS",
+    "INFLUXDB_RESUME",
+    "INFLUXDB_BOOTSTRAP",
+    "INFLUXDB_BUCKET_RETENTION_SECONDS",
+    "INFLUXDB_HEALTH_INTERVAL_SECONDS",
+    "INFLUXDB_HEALTH_REBUILD_AFTER",
+    "INFLUXDB_TLS_CA_FILE",
+    "INFLUXDB_TLS_CERT_FILE",
+    "INFLUXDB_TLS_KEY_FILE",
+    "INFLUXDB_TLS_SERVER_NAME",
+    "INFLUXDB_TLS_INSECURE",
+    "INFLUXDB_CANDLE_MEASUREMENT",
+    "INFLUXDB_INDICATOR_MEASUREMENT",
+    "INFLUXDB_EVENT_TAG",
+    "INFLUXDB_PROPERTY_TAG",
+    "INFLUXDB_STATIC_TAGS",
+    "INFLUXDB_OPEN_FIELD",
+    "INFLUXDB_HIGH_FIELD",
+    "INFLUXDB_LOW_FIELD",
+    "INFLUXDB_CLOSE_FIELD",
+    "INFLUXDB_VALUE_FIELD",
+];
+
+/// Generates the server and client of the `hts.Feed` gRPC service described
+/// in `proto/hts/feed.proto`, whose messages are in `src/grpc/proto.rs`.
+#[cfg(feature = "grpc")]
+fn compile_feed_service() {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    let method = |name: &str, route_name: &str, input_type: &str, output_type: &str| {
+        Method::builder()
+            .name(name)
+            .route_name(route_name)
+            .input_type(format!("crate::grpc::proto::{input_type}"))
+            .output_type(format!("crate::grpc::proto::{output_type}"))
+            .codec_path("tonic_prost::ProstCodec")
+    };
+    let service = Service::builder()
+        .name("Feed")
+        .package("hts")
+        .method(
+            method("stream_candles", "StreamCandles", "StreamRequest", "Candle")
+                .server_streaming()
+                .build(),
+        )
+        .method(
+            method(
+                "stream_indicators",
+                "StreamIndicators",
+                "StreamRequest",
+                "Indicator",
+            )
+            .server_streaming()
+            .build(),
+        )
+        .method(method("get_latest", "GetLatest", "LatestRequest", "LatestResponse").build())
+        .build();
+
+    Builder::new().compile(&[service]);
+}
+
 fn main() {
     use std::env;
+
+    #[cfg(feature = "grpc")]
+    compile_feed_service();
+
     #[cfg(not(debug_assertions))]
     {
         dotenv::dotenv().expect("Failed to load .env file");
@@ -41,4 +182,10 @@ fn main() {
     println!("cargo:rustc-env=INFLUXDB_TOKEN={}", influxdb_token.unwrap_or_default());
     println!("cargo:rustc-env=INFLUXDB_ORG={}", influxdb_org.unwrap_or_default());
     println!("cargo:rustc-env=TEXT_FILE_PATH={}", text_file_path.unwrap_or_default());
+
+    for key in OPTIONAL_VARS {
+        if let Ok(value) = env::var(key) {
+            println!("cargo:rustc-env={key}={value}");
+        }
+    }
 }
@@ -0,0 +1,28 @@
+//! Helpers shared by the configs of every module.
+
+use std::error::Error;
+use std::str::FromStr;
+
+/// Parses `value` if it is set, falling back to `default` otherwise.
+pub fn parse_or<T>(value: Option<impl AsRef<str>>, default: T) -> Result<T, Box<dyn Error>>
+where
+    T: FromStr,
+    T::Err: Into<Box<dyn Error>>,
+{
+    value.map_or(Ok(default), |value| {
+        value.as_ref().parse().map_err(Into::into)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_or() {
+        // Act & Assert
+        assert_eq!(parse_or(None::<&str>, 7).ok(), Some(7));
+        assert_eq!(parse_or(Some("42"), 7).ok(), Some(42));
+        assert!(parse_or(Some("forty-two"), 7).is_err());
+    }
+}
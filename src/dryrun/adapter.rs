@@ -0,0 +1,129 @@
+use crate::dryrun::config::Config;
+use crate::influx::line::Point;
+use crate::influx::precision::Precision;
+use crate::influx::schema::Schema;
+use crate::model::{candle::Candle, indicator::Indicator};
+use crate::text::reader::Handler;
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+/// A [`Handler`] that renders records into the line protocol the `InfluxDB`
+/// client would send, and writes it out instead of sending it.
+pub struct DryRunHandler {
+    writer: Mutex<Box<dyn Write + Send>>,
+    schema: Schema,
+    precision: Precision,
+}
+
+impl DryRunHandler {
+    pub fn new(config: &Config, schema: Schema, precision: Precision) -> Result<Self, io::Error> {
+        let writer: Box<dyn Write + Send> = if config.is_stdout() {
+            Box::new(io::stdout())
+        } else {
+            Box::new(
+                OpenOptions::new()
+                    .append(true)
+                    .create(true)
+                    .open(&config.output)?,
+            )
+        };
+
+        Ok(Self::with_writer(writer, schema, precision))
+    }
+
+    #[must_use]
+    pub fn with_writer(
+        writer: Box<dyn Write + Send>,
+        schema: Schema,
+        precision: Precision,
+    ) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+            schema,
+            precision,
+        }
+    }
+
+    fn write_point(&self, point: &Point) -> Result<(), io::Error> {
+        let mut writer = self
+            .writer
+            .lock()
+            .map_err(|e| io::Error::other(format!("Failed to lock writer: {e}")))?;
+        writeln!(writer, "{}", point.render(self.precision))?;
+        writer.flush()
+    }
+}
+
+impl Handler for DryRunHandler {
+    fn handle_candle(&self, candle: Candle) -> Result<(), io::Error> {
+        self.write_point(&self.schema.candle_point(&candle))
+    }
+
+    fn handle_indicator(&self, indicator: Indicator) -> Result<(), io::Error> {
+        self.write_point(&self.schema.indicator_point(&indicator))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().expect("Failed to lock buffer").write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_dry_run_writes_line_protocol() {
+        // Arrange
+        let buffer = SharedBuffer::default();
+        let handler = DryRunHandler::with_writer(
+            Box::new(buffer.clone()),
+            Schema::default(),
+            Precision::Seconds,
+        );
+
+        let candle = Candle {
+            timestamp: 1_714_450_860_000_000_000,
+            event: "테스트".to_string(),
+            open: 368.85,
+            high: 368.9,
+            low: 368.7,
+            close: 368.75,
+        };
+        let indicator = Indicator {
+            timestamp: 1_714_615_200_000_000_000,
+            event: "옵션".to_string(),
+            property: "풋외국인".to_string(),
+            value: -13,
+        };
+
+        // Act
+        let candle_result = handler.handle_candle(candle);
+        let indicator_result = handler.handle_indicator(indicator);
+
+        // Assert
+        assert!(candle_result.is_ok());
+        assert!(indicator_result.is_ok());
+
+        let written = buffer.0.lock().expect("Failed to lock buffer").clone();
+        assert_eq!(
+            String::from_utf8(written).expect("Failed to decode output"),
+            "candle,event=테스트 open=368.85,high=368.9,low=368.7,close=368.75 1714450860\n\
+             indicator,event=옵션,property=풋외국인 value=-13i 1714615200\n"
+        );
+    }
+}
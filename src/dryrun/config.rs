@@ -0,0 +1,74 @@
+use envconfig::Envconfig;
+use serde::Deserialize;
+
+use std::env;
+
+/// Output target used when `DRY_RUN_OUTPUT` is not set.
+pub const STDOUT: &str = "-";
+
+#[derive(Debug, Deserialize, Envconfig)]
+pub struct Config {
+    /// File the rendered line protocol is appended to, or `-` for stdout.
+    pub output: String,
+}
+
+impl Config {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            output: env::var("DRY_RUN_OUTPUT").unwrap_or_else(|_| STDOUT.to_string()),
+        }
+    }
+
+    #[must_use]
+    pub fn init() -> Self {
+        Self {
+            output: option_env!("DRY_RUN_OUTPUT").unwrap_or(STDOUT).to_string(),
+        }
+    }
+
+    #[must_use]
+    pub fn is_stdout(&self) -> bool {
+        self.output == STDOUT
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[ignore]
+    #[test]
+    fn test_config_default_stdout() {
+        // Arrange
+        env::remove_var("DRY_RUN_OUTPUT");
+
+        // Act
+        let config = Config::new();
+
+        // Assert
+        assert!(config.is_stdout());
+    }
+
+    #[ignore]
+    #[test]
+    fn test_config_success() {
+        // Arrange
+        const DRY_RUN_OUTPUT: &str = "./dry-run.lp";
+        env::set_var("DRY_RUN_OUTPUT", DRY_RUN_OUTPUT);
+
+        // Act
+        let config = Config::new();
+
+        // Assert
+        assert_eq!(config.output, DRY_RUN_OUTPUT);
+        assert!(!config.is_stdout());
+    }
+}
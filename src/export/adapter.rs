@@ -0,0 +1,397 @@
+use crate::export::config::Config;
+use crate::influx::line::Point;
+use crate::influx::precision::Precision;
+use crate::influx::schema::Schema;
+use crate::model::{candle::Candle, indicator::Indicator};
+use crate::text::reader::Handler;
+use chrono::{DateTime, NaiveDate};
+use chrono_tz::Asia::Seoul;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::mem;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+enum Output {
+    Plain(BufWriter<File>),
+    /// Lines are compressed into a gzip member in memory, appended to the
+    /// file once complete.
+    Gzip {
+        file: BufWriter<File>,
+        member: GzEncoder<Vec<u8>>,
+        /// Set while the member holds lines not yet in the file.
+        pending: bool,
+    },
+}
+
+impl Output {
+    /// Writes `line`, flushing plain files so every line is on disk. Gzip
+    /// lines reach the file on the next flush.
+    fn write_line(&mut self, line: &[u8]) -> Result<(), io::Error> {
+        match self {
+            Self::Plain(writer) => {
+                writer.write_all(line)?;
+                writer.flush()
+            }
+            Self::Gzip {
+                member, pending, ..
+            } => {
+                *pending = true;
+                member.write_all(line)
+            }
+        }
+    }
+
+    /// Completes the pending gzip member and appends it to the file. Gzip
+    /// readers decompress concatenated members as one stream, so the file
+    /// is readable after every flush.
+    fn flush(&mut self) -> Result<(), io::Error> {
+        match self {
+            Self::Plain(writer) => writer.flush(),
+            Self::Gzip {
+                file,
+                member,
+                pending,
+            } => {
+                if !*pending {
+                    return Ok(());
+                }
+                let complete =
+                    mem::replace(member, GzEncoder::new(Vec::new(), Compression::default()));
+                file.write_all(&complete.finish()?)?;
+                *pending = false;
+                file.flush()
+            }
+        }
+    }
+}
+
+struct Current {
+    date: NaiveDate,
+    bytes: u64,
+    output: Output,
+}
+
+/// A [`Handler`] writing the line protocol the `InfluxDB` client would send
+/// into local files, for loading with `influx write` where there is no
+/// network path to the server.
+///
+/// A new file is started for every Seoul trading day of the records and
+/// whenever the configured size is reached. Existing files are never
+/// appended to, so a restart starts the next file of the day. Gzip files
+/// are flushed on a flusher thread every flush interval.
+pub struct ExportHandler {
+    config: Config,
+    schema: Schema,
+    precision: Precision,
+    current: Arc<Mutex<Option<Current>>>,
+    /// Dropped to stop the flusher.
+    stop: Option<Sender<()>>,
+    flusher: Option<JoinHandle<()>>,
+}
+
+impl ExportHandler {
+    pub fn new(config: Config, schema: Schema, precision: Precision) -> Result<Self, io::Error> {
+        fs::create_dir_all(&config.dir)?;
+        let current = Arc::new(Mutex::new(None));
+        let (stop, flusher) = if config.gzip {
+            let (stop, stopped) = mpsc::channel();
+            let current = Arc::clone(&current);
+            let interval = config.flush_interval();
+            let flusher = thread::spawn(move || Self::flush_every(&current, &stopped, interval));
+            (Some(stop), Some(flusher))
+        } else {
+            (None, None)
+        };
+
+        Ok(Self {
+            config,
+            schema,
+            precision,
+            current,
+            stop,
+            flusher,
+        })
+    }
+
+    fn flush_every(
+        current: &Mutex<Option<Current>>,
+        stopped: &mpsc::Receiver<()>,
+        interval: Duration,
+    ) {
+        while stopped.recv_timeout(interval) == Err(RecvTimeoutError::Timeout) {
+            let mut guard = current.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(current) = guard.as_mut() {
+                if let Err(e) = current.output.flush() {
+                    println!("Failed to flush export file: {e}");
+                }
+            }
+        }
+    }
+
+    fn date_of(timestamp: u128) -> Result<NaiveDate, io::Error> {
+        let nanos = i64::try_from(timestamp)
+            .map_err(|_| io::Error::other(format!("Timestamp out of range: {timestamp}")))?;
+        Ok(DateTime::from_timestamp_nanos(nanos)
+            .with_timezone(&Seoul)
+            .date_naive())
+    }
+
+    fn path(&self, date: NaiveDate, sequence: u32) -> PathBuf {
+        let extension = if self.config.gzip { "lp.gz" } else { "lp" };
+        PathBuf::from(&self.config.dir).join(format!(
+            "{}-{date}-{sequence:03}.{extension}",
+            self.config.prefix
+        ))
+    }
+
+    fn open(&self, date: NaiveDate) -> Result<Current, io::Error> {
+        let path = (0..)
+            .map(|sequence| self.path(date, sequence))
+            .find(|path| !path.exists())
+            .ok_or_else(|| io::Error::other("No free file name"))?;
+        let file = BufWriter::new(File::create(path)?);
+
+        let output = if self.config.gzip {
+            Output::Gzip {
+                file,
+                member: GzEncoder::new(Vec::new(), Compression::default()),
+                pending: false,
+            }
+        } else {
+            Output::Plain(file)
+        };
+
+        Ok(Current {
+            date,
+            bytes: 0,
+            output,
+        })
+    }
+
+    fn write_point(&self, point: &Point, timestamp: u128) -> Result<(), io::Error> {
+        let line = format!("{}\n", point.render(self.precision));
+        let length = line.len() as u64;
+        let date = Self::date_of(timestamp)?;
+
+        let mut guard = self
+            .current
+            .lock()
+            .map_err(|e| io::Error::other(format!("Failed to lock export file: {e}")))?;
+
+        let rotate = guard.as_ref().is_none_or(|current| {
+            current.date != date
+                || (self.config.max_bytes > 0
+                    && current.bytes > 0
+                    && current.bytes + length > self.config.max_bytes)
+        });
+        if rotate {
+            if let Some(mut previous) = guard.take() {
+                previous.output.flush()?;
+            }
+            *guard = Some(self.open(date)?);
+        }
+
+        let Some(current) = guard.as_mut() else {
+            return Err(io::Error::other("No export file open"));
+        };
+        current.output.write_line(line.as_bytes())?;
+        current.bytes += length;
+        drop(guard);
+
+        Ok(())
+    }
+}
+
+impl Handler for ExportHandler {
+    fn handle_candle(&self, candle: Candle) -> Result<(), io::Error> {
+        self.write_point(&self.schema.candle_point(&candle), candle.timestamp)
+    }
+
+    fn handle_indicator(&self, indicator: Indicator) -> Result<(), io::Error> {
+        self.write_point(
+            &self.schema.indicator_point(&indicator),
+            indicator.timestamp,
+        )
+    }
+}
+
+impl Drop for ExportHandler {
+    /// Stops the flusher and flushes the current file.
+    fn drop(&mut self) {
+        self.stop = None;
+        if let Some(flusher) = self.flusher.take() {
+            if flusher.join().is_err() {
+                println!("Export flusher panicked");
+            }
+        }
+        let current = self
+            .current
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(mut current) = current {
+            if let Err(e) = current.output.flush() {
+                println!("Failed to flush export file: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+    use crate::model::candle::tests::candle;
+    use flate2::read::MultiGzDecoder;
+    use std::env;
+    use std::io::Read;
+    use std::time::Instant;
+
+    fn config(name: &str, max_bytes: u64, gzip: bool) -> Config {
+        let dir = env::temp_dir().join(format!("hts-connector-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Config {
+            dir: dir.to_string_lossy().to_string(),
+            prefix: "hts".to_string(),
+            max_bytes,
+            gzip,
+            flush_interval_ms: 10_000,
+        }
+    }
+
+    fn files(dir: &str) -> Vec<String> {
+        let mut files: Vec<String> = fs::read_dir(dir)
+            .expect("Failed to read directory")
+            .map(|entry| {
+                entry
+                    .expect("Failed to read entry")
+                    .file_name()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_export_rotates_by_day_and_size() {
+        // Arrange
+        let config = config("export-rotate", 100, false);
+        let dir = config.dir.clone();
+        let handler = ExportHandler::new(config, Schema::default(), Precision::Seconds)
+            .expect("Failed to create handler");
+
+        // Act
+        // 2024-04-30 13:21 and 13:22 in Seoul, then 2024-05-01 09:00.
+        handler
+            .handle_candle(candle("테스트", 1_714_450_860_000_000_000, 368.75))
+            .expect("Failed to handle candle");
+        handler
+            .handle_candle(candle("테스트", 1_714_450_920_000_000_000, 368.75))
+            .expect("Failed to handle candle");
+        handler
+            .handle_candle(candle("테스트", 1_714_521_600_000_000_000, 368.75))
+            .expect("Failed to handle candle");
+        drop(handler);
+
+        // Assert
+        assert_eq!(
+            files(&dir),
+            vec![
+                "hts-2024-04-30-000.lp",
+                "hts-2024-04-30-001.lp",
+                "hts-2024-05-01-000.lp",
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(PathBuf::from(&dir).join("hts-2024-04-30-001.lp"))
+                .expect("Failed to read file"),
+            "candle,event=테스트 open=100,high=200,low=50,close=368.75 1714450920\n"
+        );
+
+        fs::remove_dir_all(&dir).expect("Failed to remove directory");
+    }
+
+    #[test]
+    fn test_export_gzip() {
+        // Arrange
+        let config = config("export-gzip", 0, true);
+        let dir = config.dir.clone();
+        let handler = ExportHandler::new(config, Schema::default(), Precision::Seconds)
+            .expect("Failed to create handler");
+
+        // Act
+        handler
+            .handle_candle(candle("테스트", 1_714_450_860_000_000_000, 368.75))
+            .expect("Failed to handle candle");
+        handler
+            .handle_candle(candle("테스트", 1_714_450_920_000_000_000, 368.75))
+            .expect("Failed to handle candle");
+        drop(handler);
+
+        // Assert
+        assert_eq!(files(&dir), vec!["hts-2024-04-30-000.lp.gz"]);
+
+        let file = File::open(PathBuf::from(&dir).join("hts-2024-04-30-000.lp.gz"))
+            .expect("Failed to open file");
+        let mut content = String::new();
+        MultiGzDecoder::new(file)
+            .read_to_string(&mut content)
+            .expect("Failed to decompress file");
+        assert_eq!(content.lines().count(), 2);
+
+        fs::remove_dir_all(&dir).expect("Failed to remove directory");
+    }
+
+    fn decompress(path: &PathBuf) -> Result<String, io::Error> {
+        let mut content = String::new();
+        MultiGzDecoder::new(File::open(path)?).read_to_string(&mut content)?;
+        Ok(content)
+    }
+
+    #[test]
+    fn test_export_gzip_is_readable_after_flush() {
+        // Arrange
+        let mut config = config("export-gzip-flush", 0, true);
+        config.flush_interval_ms = 20;
+        let dir = config.dir.clone();
+        let path = PathBuf::from(&dir).join("hts-2024-04-30-000.lp.gz");
+        let handler = ExportHandler::new(config, Schema::default(), Precision::Seconds)
+            .expect("Failed to create handler");
+        let readable = |lines: usize| {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while Instant::now() < deadline {
+                if decompress(&path).is_ok_and(|content| content.lines().count() == lines) {
+                    return true;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            false
+        };
+
+        // Act
+        handler
+            .handle_candle(candle("테스트", 1_714_450_860_000_000_000, 368.75))
+            .expect("Failed to handle candle");
+        let first = readable(1);
+        handler
+            .handle_candle(candle("테스트", 1_714_450_920_000_000_000, 368.75))
+            .expect("Failed to handle candle");
+        let second = readable(2);
+
+        // Assert
+        assert!(first);
+        assert!(second);
+
+        drop(handler);
+        fs::remove_dir_all(&dir).expect("Failed to remove directory");
+    }
+}
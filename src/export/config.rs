@@ -0,0 +1,62 @@
+use crate::config::parse_or;
+use envconfig::Envconfig;
+use serde::Deserialize;
+
+use std::env;
+use std::error::Error;
+use std::time::Duration;
+
+/// Directory used when `EXPORT_DIR` is not set.
+pub const DEFAULT_DIR: &str = "export";
+
+#[derive(Debug, Deserialize, Envconfig)]
+pub struct Config {
+    /// Directory the line-protocol files are written to.
+    pub dir: String,
+    /// File names are `<prefix>-<date>-<sequence>.lp`.
+    pub prefix: String,
+    /// Uncompressed bytes after which a new file is started, `0` for one file
+    /// per day.
+    pub max_bytes: u64,
+    /// Compress files with gzip, adding `.gz` to their names.
+    pub gzip: bool,
+    /// Time after which the lines compressed so far are appended to a gzip
+    /// file as a complete member, so it can be read while being written.
+    pub flush_interval_ms: u64,
+}
+
+impl Config {
+    fn validate(self) -> Result<Self, Box<dyn Error>> {
+        if self.flush_interval_ms == 0 {
+            return Err("Expected a flush interval above 0 ms".into());
+        }
+        Ok(self)
+    }
+
+    #[must_use]
+    pub const fn flush_interval(&self) -> Duration {
+        Duration::from_millis(self.flush_interval_ms)
+    }
+
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Self {
+            dir: env::var("EXPORT_DIR").unwrap_or_else(|_| DEFAULT_DIR.to_string()),
+            prefix: env::var("EXPORT_PREFIX").unwrap_or_else(|_| "hts".to_string()),
+            max_bytes: parse_or(env::var("EXPORT_MAX_BYTES").ok(), 0)?,
+            gzip: parse_or(env::var("EXPORT_GZIP").ok(), false)?,
+            flush_interval_ms: parse_or(env::var("EXPORT_FLUSH_INTERVAL_MS").ok(), 10_000)?,
+        }
+        .validate()
+    }
+
+    pub fn init() -> Result<Self, Box<dyn Error>> {
+        Self {
+            dir: option_env!("EXPORT_DIR").unwrap_or(DEFAULT_DIR).to_string(),
+            prefix: option_env!("EXPORT_PREFIX").unwrap_or("hts").to_string(),
+            max_bytes: parse_or(option_env!("EXPORT_MAX_BYTES"), 0)?,
+            gzip: parse_or(option_env!("EXPORT_GZIP"), false)?,
+            flush_interval_ms: parse_or(option_env!("EXPORT_FLUSH_INTERVAL_MS"), 10_000)?,
+        }
+        .validate()
+    }
+}
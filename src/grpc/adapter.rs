@@ -0,0 +1,156 @@
+use crate::grpc::config::Config;
+use crate::grpc::proto::feed_server::FeedServer;
+use crate::grpc::service::FeedService;
+use crate::model::{candle::Candle, indicator::Indicator};
+use crate::text::reader::Handler;
+use tokio::net::TcpListener;
+use tokio::runtime::{Builder, Runtime};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Server;
+
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// A [`Handler`] streaming every record to the subscribers of the `hts.Feed`
+/// gRPC service, as described in `proto/hts/feed.proto`.
+///
+/// A subscriber that falls behind by more than the configured capacity has
+/// its stream ended with `RESOURCE_EXHAUSTED` instead of slowing the reader
+/// down.
+pub struct GrpcHandler {
+    service: Arc<FeedService>,
+    local_addr: SocketAddr,
+    /// Runs the server until the handler is dropped.
+    _runtime: Runtime,
+}
+
+impl GrpcHandler {
+    /// Starts listening on the configured address.
+    pub fn new(config: &Config) -> Result<Self, io::Error> {
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("grpc")
+            .enable_all()
+            .build()?;
+        let listener = runtime.block_on(TcpListener::bind(&config.addr))?;
+        let local_addr = listener.local_addr()?;
+        println!("Serving gRPC subscribers on {local_addr}");
+
+        let service = Arc::new(FeedService::new(config.capacity));
+        let server = Server::builder()
+            .add_service(FeedServer::from_arc(Arc::clone(&service)))
+            .serve_with_incoming(TcpListenerStream::new(listener));
+        runtime.spawn(async move {
+            if let Err(e) = server.await {
+                println!("gRPC server failed: {e}");
+            }
+        });
+
+        Ok(Self {
+            service,
+            local_addr,
+            _runtime: runtime,
+        })
+    }
+
+    /// The address the server listens on, with the port picked if the
+    /// configured one was `0`.
+    #[must_use]
+    pub const fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl Handler for GrpcHandler {
+    fn handle_candle(&self, candle: Candle) -> Result<(), io::Error> {
+        self.service.publish_candle(candle.try_into()?);
+        Ok(())
+    }
+
+    fn handle_indicator(&self, indicator: Indicator) -> Result<(), io::Error> {
+        self.service.publish_indicator(indicator.try_into()?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpc::proto::feed_client::FeedClient;
+    use crate::grpc::proto::{self, LatestRequest, StreamRequest};
+    use crate::model::candle::tests::candle;
+    use std::time::Duration;
+    use tokio::time::timeout;
+    use tokio_stream::StreamExt;
+
+    const TIMESTAMP: u128 = 1_714_450_980_000_000_000;
+
+    #[test]
+    fn test_subscriber_receives_latest_and_filtered_updates() {
+        // Arrange
+        let handler = GrpcHandler::new(&Config {
+            addr: "127.0.0.1:0".to_string(),
+            capacity: 16,
+        })
+        .expect("Failed to start server");
+        handler
+            .handle_candle(candle("A", TIMESTAMP, 150.0))
+            .expect("Failed to handle candle");
+        handler
+            .handle_candle(candle("B", TIMESTAMP, 150.0))
+            .expect("Failed to handle candle");
+        handler
+            .handle_indicator(Indicator {
+                timestamp: 1_714_450_980_000_000_000,
+                event: "A".to_string(),
+                property: "rsi".to_string(),
+                value: 70,
+            })
+            .expect("Failed to handle indicator");
+        let url = format!("http://{}", handler.local_addr());
+        let runtime = Runtime::new().expect("Failed to create runtime");
+
+        // Act
+        let (latest, update) = runtime.block_on(async {
+            let mut client = FeedClient::connect(url).await.expect("Failed to connect");
+            let latest = client
+                .get_latest(LatestRequest {
+                    events: vec!["A".to_string()],
+                })
+                .await
+                .expect("Failed to get latest")
+                .into_inner();
+            let mut stream = client
+                .stream_candles(StreamRequest {
+                    events: vec!["A".to_string()],
+                })
+                .await
+                .expect("Failed to subscribe")
+                .into_inner();
+            handler
+                .handle_candle(candle("B", TIMESTAMP, 175.0))
+                .expect("Failed to handle candle");
+            handler
+                .handle_candle(candle("A", TIMESTAMP, 175.0))
+                .expect("Failed to handle candle");
+            let update = timeout(Duration::from_secs(5), stream.next())
+                .await
+                .expect("No update received")
+                .expect("Stream ended")
+                .expect("Failed to receive");
+            (latest, update)
+        });
+
+        // Assert
+        assert_eq!(
+            latest.candles,
+            vec![proto::Candle::try_from(candle("A", TIMESTAMP, 150.0)).expect("Out of range")]
+        );
+        assert_eq!(latest.indicators.len(), 1);
+        assert_eq!(
+            update,
+            proto::Candle::try_from(candle("A", TIMESTAMP, 175.0)).expect("Out of range")
+        );
+    }
+}
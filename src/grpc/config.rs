@@ -0,0 +1,33 @@
+use crate::config::parse_or;
+use envconfig::Envconfig;
+use serde::Deserialize;
+
+use std::env;
+use std::error::Error;
+
+/// Address used when `GRPC_ADDR` is not set.
+pub const DEFAULT_ADDR: &str = "127.0.0.1:50051";
+
+#[derive(Debug, Clone, Deserialize, Envconfig)]
+pub struct Config {
+    /// `host:port` the server listens on.
+    pub addr: String,
+    /// Records a subscriber may fall behind by before its stream is ended.
+    pub capacity: usize,
+}
+
+impl Config {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            addr: env::var("GRPC_ADDR").unwrap_or_else(|_| DEFAULT_ADDR.to_string()),
+            capacity: parse_or(env::var("GRPC_CAPACITY").ok(), 1024)?,
+        })
+    }
+
+    pub fn init() -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            addr: option_env!("GRPC_ADDR").unwrap_or(DEFAULT_ADDR).to_string(),
+            capacity: parse_or(option_env!("GRPC_CAPACITY"), 1024)?,
+        })
+    }
+}
@@ -0,0 +1,4 @@
+pub mod adapter;
+pub mod config;
+pub mod proto;
+pub mod service;
@@ -0,0 +1,34 @@
+//! Messages of the `hts.Feed` service in `proto/hts/feed.proto`, and its
+//! server and client generated by the build script.
+
+pub use crate::model::proto::{Candle, Indicator};
+use prost::Message;
+
+#[derive(Clone, PartialEq, Eq, Message)]
+pub struct StreamRequest {
+    /// Events to receive; empty for all of them.
+    #[prost(string, repeated, tag = "1")]
+    pub events: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, Eq, Message)]
+pub struct LatestRequest {
+    /// Events to return; empty for all of them.
+    #[prost(string, repeated, tag = "1")]
+    pub events: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct LatestResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub candles: Vec<Candle>,
+    #[prost(message, repeated, tag = "2")]
+    pub indicators: Vec<Indicator>,
+}
+
+#[allow(clippy::all, clippy::nursery, clippy::unwrap_used)]
+mod generated {
+    include!(concat!(env!("OUT_DIR"), "/hts.Feed.rs"));
+}
+
+pub use generated::{feed_client, feed_server};
@@ -0,0 +1,204 @@
+use crate::grpc::proto::feed_server::Feed;
+use crate::grpc::proto::{Candle, Indicator, LatestRequest, LatestResponse, StreamRequest};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Records buffered for a subscriber before its stream reads from the
+/// broadcast channel again.
+const BUFFER: usize = 16;
+
+/// The latest record of every candle and indicator series.
+#[derive(Default)]
+struct Latest {
+    candles: BTreeMap<String, Candle>,
+    indicators: BTreeMap<(String, String), Indicator>,
+}
+
+/// The events of a request; empty for all of them.
+struct Events(BTreeSet<String>);
+
+impl Events {
+    fn new(events: Vec<String>) -> Self {
+        Self(events.into_iter().collect())
+    }
+
+    fn matches(&self, event: &str) -> bool {
+        self.0.is_empty() || self.0.contains(event)
+    }
+}
+
+/// Forwards the records of `events` from `updates` to a subscriber's
+/// stream, until the subscriber goes away.
+///
+/// A subscriber that falls behind by more than the broadcast capacity gets
+/// `RESOURCE_EXHAUSTED` and its stream ends, so that it can resubscribe
+/// knowing it missed records instead of silently skipping them.
+fn forward<T>(
+    mut updates: broadcast::Receiver<T>,
+    events: Events,
+    event: fn(&T) -> &str,
+) -> ReceiverStream<Result<T, Status>>
+where
+    T: Clone + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel(BUFFER);
+    tokio::spawn(async move {
+        loop {
+            let update = tokio::select! {
+                update = updates.recv() => update,
+                () = sender.closed() => return,
+            };
+            let update = match update {
+                Ok(update) if events.matches(event(&update)) => Ok(update),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(missed)) => Err(Status::resource_exhausted(format!(
+                    "Subscriber fell behind by {missed} records"
+                ))),
+                Err(RecvError::Closed) => return,
+            };
+            let last = update.is_err();
+            if sender.send(update).await.is_err() || last {
+                return;
+            }
+        }
+    });
+    ReceiverStream::new(receiver)
+}
+
+/// The `hts.Feed` service, streaming the records it is given to every
+/// subscriber.
+pub struct FeedService {
+    candles: broadcast::Sender<Candle>,
+    indicators: broadcast::Sender<Indicator>,
+    latest: Mutex<Latest>,
+}
+
+impl FeedService {
+    /// A service letting subscribers fall behind by `capacity` records.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            candles: broadcast::channel(capacity.max(1)).0,
+            indicators: broadcast::channel(capacity.max(1)).0,
+            latest: Mutex::new(Latest::default()),
+        }
+    }
+
+    pub fn publish_candle(&self, candle: Candle) {
+        self.latest()
+            .candles
+            .insert(candle.event.clone(), candle.clone());
+        // Fails only without subscribers.
+        let _ = self.candles.send(candle);
+    }
+
+    pub fn publish_indicator(&self, indicator: Indicator) {
+        self.latest().indicators.insert(
+            (indicator.event.clone(), indicator.property.clone()),
+            indicator.clone(),
+        );
+        // Fails only without subscribers.
+        let _ = self.indicators.send(indicator);
+    }
+
+    fn latest(&self) -> MutexGuard<'_, Latest> {
+        self.latest.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[tonic::async_trait]
+impl Feed for FeedService {
+    type StreamCandlesStream = ReceiverStream<Result<Candle, Status>>;
+    type StreamIndicatorsStream = ReceiverStream<Result<Indicator, Status>>;
+
+    async fn stream_candles(
+        &self,
+        request: Request<StreamRequest>,
+    ) -> Result<Response<Self::StreamCandlesStream>, Status> {
+        let events = Events::new(request.into_inner().events);
+        Ok(Response::new(forward(
+            self.candles.subscribe(),
+            events,
+            |candle| &candle.event,
+        )))
+    }
+
+    async fn stream_indicators(
+        &self,
+        request: Request<StreamRequest>,
+    ) -> Result<Response<Self::StreamIndicatorsStream>, Status> {
+        let events = Events::new(request.into_inner().events);
+        Ok(Response::new(forward(
+            self.indicators.subscribe(),
+            events,
+            |indicator| &indicator.event,
+        )))
+    }
+
+    async fn get_latest(
+        &self,
+        request: Request<LatestRequest>,
+    ) -> Result<Response<LatestResponse>, Status> {
+        let events = Events::new(request.into_inner().events);
+        let latest = self.latest();
+        let response = LatestResponse {
+            candles: latest
+                .candles
+                .values()
+                .filter(|candle| events.matches(&candle.event))
+                .cloned()
+                .collect(),
+            indicators: latest
+                .indicators
+                .values()
+                .filter(|indicator| events.matches(&indicator.event))
+                .cloned()
+                .collect(),
+        };
+        drop(latest);
+        Ok(Response::new(response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::candle::tests::candle;
+    use tokio_stream::StreamExt;
+
+    #[tokio::test]
+    async fn test_lagging_subscriber_stream_ends() {
+        // Arrange
+        let service = FeedService::new(2);
+        let mut stream = service
+            .stream_candles(Request::new(StreamRequest::default()))
+            .await
+            .expect("Failed to subscribe")
+            .into_inner();
+
+        // Act
+        for close in 0..8 {
+            service.publish_candle(
+                candle("A", 1_714_450_980_000_000_000, f64::from(close))
+                    .try_into()
+                    .expect("Out of range"),
+            );
+        }
+        let mut received = Vec::new();
+        while let Some(item) = stream.next().await {
+            received.push(item);
+        }
+
+        // Assert
+        let status = received
+            .last()
+            .and_then(|item| item.as_ref().err())
+            .expect("Stream did not end with an error");
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+    }
+}
@@ -2,33 +2,34 @@ use crate::influx::client::Client as InfluxClient;
 use crate::model::candle::Candle;
 use crate::text::reader::Handler;
 use std::io;
+use std::sync::Arc;
 use tokio::runtime::Runtime;
 
 pub struct InfluxHandler {
-    client: InfluxClient,
+    client: Arc<InfluxClient>,
 }
 
 impl InfluxHandler {
     #[must_use]
-    pub const fn new(client: InfluxClient) -> Self {
+    pub fn new(client: InfluxClient) -> Self {
+        Self::shared(Arc::new(client))
+    }
+
+    /// Writes through a client that is also used elsewhere, such as by the
+    /// health monitor.
+    #[must_use]
+    pub const fn shared(client: Arc<InfluxClient>) -> Self {
         Self { client }
     }
 }
 
 impl Handler for InfluxHandler {
     fn handle_candle(&self, candle: Candle) -> Result<(), io::Error> {
-        let runtime = Runtime::new().map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                format!("Failed to create runtime: {e}"),
-            )
-        })?;
+        let runtime = Runtime::new()
+            .map_err(|e| io::Error::other(format!("Failed to create runtime: {e}")))?;
         match runtime.block_on(self.client.insert_candle(candle)) {
             Ok(()) => Ok(()),
-            Err(e) => Err(io::Error::new(
-                io::ErrorKind::Other,
-                format!("Failed to insert data: {e}"),
-            )),
+            Err(e) => Err(io::Error::other(format!("Failed to insert data: {e}"))),
         }
     }
 
@@ -36,18 +37,75 @@ impl Handler for InfluxHandler {
         &self,
         indicator: crate::model::indicator::Indicator,
     ) -> Result<(), io::Error> {
-        let runtime = Runtime::new().map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::Other,
-                format!("Failed to create runtime: {e}"),
-            )
-        })?;
+        let runtime = Runtime::new()
+            .map_err(|e| io::Error::other(format!("Failed to create runtime: {e}")))?;
         match runtime.block_on(self.client.insert_indicator(indicator)) {
             Ok(()) => Ok(()),
-            Err(e) => Err(io::Error::new(
-                io::ErrorKind::Other,
-                format!("Failed to insert data: {e}"),
-            )),
+            Err(e) => Err(io::Error::other(format!("Failed to insert data: {e}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(target_os = "windows"))]
+mod tests {
+    use super::*;
+    use crate::influx::api::ApiVersion;
+    use crate::influx::fake::{config, FakeServer};
+    use crate::text::config::Config as TextConfig;
+    use crate::text::encoding::SourceEncoding;
+    use crate::text::reader::Reader;
+    use rinfluxdb_lineprotocol::FieldValue;
+    use scopeguard::defer;
+    use std::env;
+    use std::fs::File;
+    use std::io::Write;
+    use std::time::Duration;
+
+    #[test]
+    fn test_reader_writes_to_influx() {
+        // Arrange
+        let path = env::temp_dir().join(format!("hts-connector-e2e-{}.txt", std::process::id()));
+        let mut file = File::create(&path).expect("Failed to create file");
+        writeln!(
+            file,
+            "2024-04-30 13:21:00  테스트 368.850000 368.900000 368.750000 368.700000"
+        )
+        .expect("Failed to write to file");
+        writeln!(file, "2024-05-02 11:00:00  옵션 풋외국인 -13.000000")
+            .expect("Failed to write to file");
+
+        defer! {
+            std::fs::remove_file(&path).expect("Failed to remove file");
         }
+
+        let server = FakeServer::start().expect("Failed to start server");
+        let client = Runtime::new()
+            .expect("Failed to create runtime")
+            .block_on(InfluxClient::new(config(server.url(), ApiVersion::V2)))
+            .expect("Failed to create client");
+
+        let config = TextConfig {
+            path: path.to_string_lossy().to_string(),
+            encoding: SourceEncoding::default(),
+        };
+        let reader = Reader::new(config, Box::new(InfluxHandler::new(client)))
+            .expect("Failed to create reader");
+
+        // Act
+        let result = reader.read_and_follow(Duration::ZERO);
+
+        // Assert
+        assert!(result.is_ok());
+
+        let points = server.points();
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].measurement, "candle");
+        assert_eq!(points[0].tag("event"), Some("테스트"));
+        assert_eq!(points[0].field("open"), Some(&FieldValue::Float(368.85)));
+        assert_eq!(points[0].timestamp, 1_714_450_860_000_000_000);
+        assert_eq!(points[1].measurement, "indicator");
+        assert_eq!(points[1].tag("property"), Some("풋외국인"));
+        assert_eq!(points[1].field("value"), Some(&FieldValue::Integer(-13)));
     }
 }
@@ -0,0 +1,101 @@
+use crate::influx::precision::Precision;
+use serde::Deserialize;
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// The `InfluxDB` HTTP API the client talks to.
+///
+/// - `V1` writes to `/write` with a database, an optional retention policy
+///   and optional username/password authentication.
+/// - `V2` writes to `/api/v2/write` with an org and bucket, authenticated
+///   with `Authorization: Token`.
+/// - `V3` writes to `/api/v3/write_lp` with a database, authenticated with
+///   `Authorization: Bearer`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Deserialize)]
+pub enum ApiVersion {
+    V1,
+    #[default]
+    V2,
+    V3,
+}
+
+impl ApiVersion {
+    #[must_use]
+    pub const fn write_path(self) -> &'static str {
+        match self {
+            Self::V1 => "/write",
+            Self::V2 => "/api/v2/write",
+            Self::V3 => "/api/v3/write_lp",
+        }
+    }
+
+    /// The `precision` query parameter for the write endpoint, which is
+    /// spelled differently by every version.
+    #[must_use]
+    pub const fn precision(self, precision: Precision) -> &'static str {
+        match self {
+            Self::V1 => match precision {
+                Precision::Microseconds => "u",
+                _ => precision.as_str(),
+            },
+            Self::V2 => precision.as_str(),
+            Self::V3 => match precision {
+                Precision::Seconds => "second",
+                Precision::Milliseconds => "millisecond",
+                Precision::Microseconds => "microsecond",
+                Precision::Nanoseconds => "nanosecond",
+            },
+        }
+    }
+}
+
+impl FromStr for ApiVersion {
+    type Err = Box<dyn Error>;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "1" | "v1" => Ok(Self::V1),
+            "2" | "v2" => Ok(Self::V2),
+            "3" | "v3" => Ok(Self::V3),
+            _ => Err(format!("Expected one of v1, v2 or v3, found {input}").into()),
+        }
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::V1 => write!(f, "v1"),
+            Self::V2 => write!(f, "v2"),
+            Self::V3 => write!(f, "v3"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        // Act & Assert
+        assert_eq!("v1".parse::<ApiVersion>().ok(), Some(ApiVersion::V1));
+        assert_eq!("2".parse::<ApiVersion>().ok(), Some(ApiVersion::V2));
+        assert_eq!("v3".parse::<ApiVersion>().ok(), Some(ApiVersion::V3));
+        assert!("v4".parse::<ApiVersion>().is_err());
+    }
+
+    #[test]
+    fn test_precision() {
+        // Act & Assert
+        assert_eq!(ApiVersion::V1.precision(Precision::Microseconds), "u");
+        assert_eq!(ApiVersion::V2.precision(Precision::Microseconds), "us");
+        assert_eq!(
+            ApiVersion::V3.precision(Precision::Microseconds),
+            "microsecond"
+        );
+        assert_eq!(ApiVersion::V3.precision(Precision::Seconds), "second");
+    }
+}
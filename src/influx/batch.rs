@@ -0,0 +1,71 @@
+use envconfig::Envconfig;
+use serde::Deserialize;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::time::Duration;
+
+/// Batching and spooling of writes when replicating to several targets.
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Envconfig)]
+pub struct Batch {
+    /// Points sent in one write request at most.
+    #[envconfig(from = "INFLUXDB_BATCH_SIZE", default = "500")]
+    pub size: usize,
+    /// Milliseconds a point waits at most before its batch is sent.
+    #[envconfig(from = "INFLUXDB_FLUSH_INTERVAL_MS", default = "1000")]
+    pub flush_interval_ms: u64,
+    /// Directory keeping points that could not be written, one file per
+    /// target. Points are kept in memory if unset.
+    #[envconfig(from = "INFLUXDB_SPOOL_DIR")]
+    pub spool_dir: Option<String>,
+    /// Points queued for a target at most, after which points are dropped
+    /// for it until it catches up.
+    #[envconfig(from = "INFLUXDB_QUEUE_CAPACITY", default = "10000")]
+    pub queue_capacity: usize,
+    /// Points kept in memory at most when no spool directory is set, after
+    /// which points that cannot be written are dropped.
+    #[envconfig(from = "INFLUXDB_MAX_SPOOLED_POINTS", default = "100000")]
+    pub max_spooled_points: usize,
+}
+
+impl Default for Batch {
+    fn default() -> Self {
+        Self::init_from_hashmap(&HashMap::new()).expect("Defaults are valid")
+    }
+}
+
+impl Batch {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(Self::init_from_env()?)
+    }
+
+    pub fn init() -> Result<Self, Box<dyn Error>> {
+        let compiled = [
+            ("INFLUXDB_BATCH_SIZE", option_env!("INFLUXDB_BATCH_SIZE")),
+            (
+                "INFLUXDB_FLUSH_INTERVAL_MS",
+                option_env!("INFLUXDB_FLUSH_INTERVAL_MS"),
+            ),
+            ("INFLUXDB_SPOOL_DIR", option_env!("INFLUXDB_SPOOL_DIR")),
+            (
+                "INFLUXDB_QUEUE_CAPACITY",
+                option_env!("INFLUXDB_QUEUE_CAPACITY"),
+            ),
+            (
+                "INFLUXDB_MAX_SPOOLED_POINTS",
+                option_env!("INFLUXDB_MAX_SPOOLED_POINTS"),
+            ),
+        ];
+        let vars: HashMap<String, String> = compiled
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| (key.to_string(), value.to_string())))
+            .collect();
+
+        Ok(Self::init_from_hashmap(&vars)?)
+    }
+
+    #[must_use]
+    pub const fn flush_interval(&self) -> Duration {
+        Duration::from_millis(self.flush_interval_ms)
+    }
+}
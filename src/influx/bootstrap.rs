@@ -0,0 +1,373 @@
+use crate::influx::api::ApiVersion;
+use crate::influx::client::Client;
+use crate::influx::query::influxql::{self, identifier};
+use envconfig::Envconfig;
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::error::Error;
+
+/// Settings of the optional startup step that provisions the bucket.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Deserialize, Envconfig)]
+pub struct Bootstrap {
+    /// Check the org and bucket at startup, creating the bucket if missing.
+    #[envconfig(from = "INFLUXDB_BOOTSTRAP", default = "false")]
+    pub enabled: bool,
+    /// Retention period of a created bucket in seconds, `0` keeps data forever.
+    #[envconfig(from = "INFLUXDB_BUCKET_RETENTION_SECONDS", default = "0")]
+    pub retention_seconds: u64,
+}
+
+impl Bootstrap {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(Self::init_from_env()?)
+    }
+
+    pub fn init() -> Result<Self, Box<dyn Error>> {
+        let compiled = [
+            ("INFLUXDB_BOOTSTRAP", option_env!("INFLUXDB_BOOTSTRAP")),
+            (
+                "INFLUXDB_BUCKET_RETENTION_SECONDS",
+                option_env!("INFLUXDB_BUCKET_RETENTION_SECONDS"),
+            ),
+        ];
+        let vars: HashMap<String, String> = compiled
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| (key.to_string(), value.to_string())))
+            .collect();
+
+        Ok(Self::init_from_hashmap(&vars)?)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Organization {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Organizations {
+    #[serde(default)]
+    orgs: Vec<Organization>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Buckets {
+    #[serde(default)]
+    buckets: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RetentionRule {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    every_seconds: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NewBucket<'a> {
+    #[serde(rename = "orgID")]
+    org_id: &'a str,
+    name: &'a str,
+    retention_rules: Vec<RetentionRule>,
+}
+
+fn decode<T: for<'de> Deserialize<'de>>(body: &str) -> Result<T, influxdb::Error> {
+    serde_json::from_str(body).map_err(|e| influxdb::Error::DeserializationError {
+        error: format!("{e}: {body}"),
+    })
+}
+
+fn context(message: String) -> impl FnOnce(influxdb::Error) -> influxdb::Error {
+    move |e| match e {
+        influxdb::Error::AuthenticationError | influxdb::Error::AuthorizationError => e,
+        e => influxdb::Error::DatabaseError {
+            error: format!("{message}: {e}"),
+        },
+    }
+}
+
+/// Makes sure the configured org and bucket exist, creating the bucket with
+/// the configured retention if missing, and that the token may write to it.
+pub async fn bootstrap(client: &Client) -> Result<(), influxdb::Error> {
+    let config = client.config();
+    let bootstrap = &config.bootstrap;
+    match config.api_version {
+        ApiVersion::V1 => {
+            let mut query = format!("CREATE DATABASE {}", identifier(config.database()));
+            if bootstrap.retention_seconds > 0 {
+                query.push_str(&format!(" WITH DURATION {}s", bootstrap.retention_seconds));
+            }
+            let request = client
+                .request(reqwest::Method::POST, "/query")
+                .query(&[("q", query)]);
+            let message = format!("Failed to create database {}", config.database());
+            let body = Client::fetch(request)
+                .await
+                .map_err(context(message.clone()))?;
+            // InfluxQL reports failed statements in the body of a 200.
+            influxql::parse(&body).map_err(|e| influxdb::Error::DatabaseError {
+                error: format!("{message}: {e}"),
+            })?;
+        }
+        ApiVersion::V2 => {
+            let request = client
+                .request(reqwest::Method::GET, "/api/v2/orgs")
+                .query(&[("org", &config.org)]);
+            let orgs: Organizations = decode(&Client::fetch(request).await.map_err(context(
+                format!("Failed to look up organization {}", config.org),
+            ))?)?;
+            let org = orgs
+                .orgs
+                .first()
+                .ok_or_else(|| influxdb::Error::DatabaseError {
+                    error: format!("Organization {} not found", config.org),
+                })?;
+
+            let request = client
+                .request(reqwest::Method::GET, "/api/v2/buckets")
+                .query(&[("orgID", &org.id), ("name", &config.bucket)]);
+            let buckets: Buckets = decode(&Client::fetch(request).await.map_err(context(
+                format!("Failed to look up bucket {}", config.bucket),
+            ))?)?;
+
+            if buckets.buckets.is_empty() {
+                let retention_rules = if bootstrap.retention_seconds > 0 {
+                    vec![RetentionRule {
+                        kind: "expire",
+                        every_seconds: bootstrap.retention_seconds,
+                    }]
+                } else {
+                    Vec::new()
+                };
+                let body = serde_json::to_string(&NewBucket {
+                    org_id: &org.id,
+                    name: &config.bucket,
+                    retention_rules,
+                })
+                .map_err(|e| influxdb::Error::InvalidQueryError {
+                    error: e.to_string(),
+                })?;
+                let request = client
+                    .request(reqwest::Method::POST, "/api/v2/buckets")
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .body(body);
+                Client::fetch(request).await.map_err(context(format!(
+                    "Failed to create bucket {}",
+                    config.bucket
+                )))?;
+            }
+        }
+        ApiVersion::V3 => {
+            return Err(influxdb::Error::InvalidQueryError {
+                error: "Bootstrapping is not supported for the v3 API".to_string(),
+            })
+        }
+    }
+
+    client.check_write_permission().await
+}
+
+#[cfg(test)]
+#[cfg(not(target_os = "windows"))]
+mod tests {
+    use super::*;
+    use crate::influx::fake::{config, FakeServer};
+
+    const ORGS: &str = r#"{"orgs":[{"id":"0123","name":"goboolean"}]}"#;
+    const BUCKETS: &str = r#"{"buckets":[{"id":"4567","name":"sample-bucket"}]}"#;
+
+    async fn bootstrapped_client(
+        server: &FakeServer,
+        retention_seconds: u64,
+    ) -> Result<Client, influxdb::Error> {
+        let mut config = config(server.url(), ApiVersion::V2);
+        config.bootstrap = Bootstrap {
+            enabled: true,
+            retention_seconds,
+        };
+        Client::new(config).await
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_creates_missing_bucket() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        server.respond_to("GET", "/api/v2/orgs", 200, ORGS);
+        server.respond_to("GET", "/api/v2/buckets", 200, r#"{"buckets":[]}"#);
+        server.respond_to("POST", "/api/v2/buckets", 201, "{}");
+
+        // Act
+        let result = bootstrapped_client(&server, 3600).await;
+
+        // Assert
+        assert!(result.is_ok());
+
+        let requests = server.requests();
+        let create = requests
+            .iter()
+            .find(|request| request.method == "POST" && request.path == "/api/v2/buckets")
+            .expect("Bucket was not created");
+        assert_eq!(
+            String::from_utf8_lossy(&create.body),
+            r#"{"orgID":"0123","name":"sample-bucket","retentionRules":[{"type":"expire","everySeconds":3600}]}"#
+        );
+        assert!(requests
+            .last()
+            .is_some_and(|request| request.path == "/api/v2/write"));
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_keeps_existing_bucket() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        server.respond_to("GET", "/api/v2/orgs", 200, ORGS);
+        server.respond_to("GET", "/api/v2/buckets", 200, BUCKETS);
+
+        // Act
+        let result = bootstrapped_client(&server, 0).await;
+
+        // Assert
+        assert!(result.is_ok());
+        assert!(!server
+            .requests()
+            .iter()
+            .any(|request| request.method == "POST" && request.path == "/api/v2/buckets"));
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_fail_if_org_is_missing() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        server.respond_to("GET", "/api/v2/orgs", 200, r#"{"orgs":[]}"#);
+
+        // Act
+        let result = bootstrapped_client(&server, 0).await;
+
+        // Assert
+        assert_eq!(
+            result.err(),
+            Some(influxdb::Error::DatabaseError {
+                error: "Organization goboolean not found".to_string(),
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_fail_if_token_cannot_write() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        server.respond_to("GET", "/api/v2/orgs", 200, ORGS);
+        server.respond_to("GET", "/api/v2/buckets", 200, BUCKETS);
+        server.respond_to("POST", "/api/v2/write", 403, r#"{"code":"forbidden"}"#);
+
+        // Act
+        let result = bootstrapped_client(&server, 0).await;
+
+        // Assert
+        assert_eq!(result.err(), Some(influxdb::Error::AuthorizationError));
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_accepts_empty_write_rejection() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        server.respond_to("GET", "/api/v2/orgs", 200, ORGS);
+        server.respond_to("GET", "/api/v2/buckets", 200, BUCKETS);
+        server.respond_to(
+            "POST",
+            "/api/v2/write",
+            400,
+            r#"{"code":"invalid","message":"writing requires points"}"#,
+        );
+
+        // Act
+        let result = bootstrapped_client(&server, 0).await;
+
+        // Assert
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_fail_if_write_fails() {
+        for (status, body) in [
+            (404, r#"{"code":"not found","message":"bucket not found"}"#),
+            (500, r#"{"code":"internal error"}"#),
+        ] {
+            // Arrange
+            let server = FakeServer::start().expect("Failed to start server");
+            server.respond_to("GET", "/api/v2/orgs", 200, ORGS);
+            server.respond_to("GET", "/api/v2/buckets", 200, BUCKETS);
+            server.respond_to("POST", "/api/v2/write", status, body);
+
+            // Act
+            let result = bootstrapped_client(&server, 0).await;
+
+            // Assert
+            assert!(
+                matches!(result, Err(influxdb::Error::DatabaseError { .. })),
+                "{status} was accepted"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_fail_if_create_database_statement_fails() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        server.respond_to(
+            "POST",
+            "/query",
+            200,
+            r#"{"results":[{"statement_id":0,"error":"retention policy duration must be at least 1h0m0s"}]}"#,
+        );
+        let mut config = config(server.url(), ApiVersion::V1);
+        config.bootstrap = Bootstrap {
+            enabled: true,
+            retention_seconds: 60,
+        };
+
+        // Act
+        let result = Client::new(config).await;
+
+        // Assert
+        assert!(matches!(
+            result,
+            Err(influxdb::Error::DatabaseError { error }) if error.contains("at least 1h0m0s")
+        ));
+        assert!(!server
+            .requests()
+            .iter()
+            .any(|request| request.path == "/write"));
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_escapes_database_name() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        server.respond_to("POST", "/query", 200, r#"{"results":[{"statement_id":0}]}"#);
+        let mut config = config(server.url(), ApiVersion::V1);
+        config.database = Some(r#"sample"db\x"#.to_string());
+        config.bootstrap = Bootstrap {
+            enabled: true,
+            retention_seconds: 3600,
+        };
+
+        // Act
+        let result = Client::new(config).await;
+
+        // Assert
+        assert!(result.is_ok());
+        let requests = server.requests();
+        let create = requests
+            .iter()
+            .find(|request| request.path == "/query")
+            .expect("Database was not created");
+        assert_eq!(
+            create.query("q"),
+            Some(r#"CREATE DATABASE "sample\"db\\x" WITH DURATION 3600s"#)
+        );
+    }
+}
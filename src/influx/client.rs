@@ -1,69 +1,540 @@
+use crate::influx::api::ApiVersion;
+use crate::influx::bootstrap::bootstrap;
 use crate::influx::config::Config;
+use crate::influx::deadletter::DeadLetter;
+use crate::influx::health::Health;
+use crate::influx::line::Point;
+use crate::influx::query::{flux, influxql, QueryLanguage, Row};
+use crate::influx::retry::WriteError;
 use crate::model::{candle::Candle, indicator::Indicator};
-use influxdb::Client as InfluxClient;
-use influxdb::WriteQuery;
+use crate::text::watermark::Watermarks;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use reqwest::header::{ACCEPT, AUTHORIZATION, CONTENT_ENCODING, CONTENT_TYPE};
+use reqwest::{Method, RequestBuilder, StatusCode};
+
+use std::future::Future;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// The HTTP client and the URL it connects to, rebuilt on reconnect.
+struct Transport {
+    http: reqwest::Client,
+    url: String,
+    /// Addresses the URL host resolved to when the transport was built.
+    addrs: Vec<SocketAddr>,
+}
+
+/// Messages servers answer an empty write with, across API versions.
+const EMPTY_BODY_ERRORS: [&str; 4] = ["requires points", "no data", "empty", "unable to parse"];
+
+/// Whether a `400 Bad Request` body says the write was empty or unparseable,
+/// rather than invalid for another reason.
+fn rejects_empty_body(body: &str) -> bool {
+    let body = body.to_lowercase();
+    EMPTY_BODY_ERRORS.iter().any(|error| body.contains(error))
+}
 
 pub struct Client {
-    client: InfluxClient,
+    transport: RwLock<Transport>,
+    config: Config,
+    health: Arc<Health>,
+    dead_letter: Option<DeadLetter>,
 }
 
 impl Client {
     pub async fn new(config: Config) -> Result<Self, influxdb::Error> {
-        let influx_client =
-            InfluxClient::new(&config.url, &config.bucket).with_token(&config.token);
+        let transport = Self::connect(&config).await?;
+        let dead_letter = config
+            .retry
+            .dead_letter_file
+            .as_deref()
+            .map(DeadLetter::open)
+            .transpose()
+            .map_err(|e| influxdb::Error::InvalidQueryError {
+                error: format!("Failed to open dead-letter file: {e}"),
+            })?;
         let client = Self {
-            client: influx_client,
+            transport: RwLock::new(transport),
+            config,
+            health: Arc::new(Health::default()),
+            dead_letter,
         };
 
         client.ping().await?;
+        if client.config.bootstrap.enabled {
+            bootstrap(&client).await?;
+        }
 
         Ok(client)
     }
 
+    async fn connect(config: &Config) -> Result<Transport, influxdb::Error> {
+        let tls_error = |e: Box<dyn std::error::Error>| influxdb::Error::ConnectionError {
+            error: format!("Failed to configure TLS: {e}"),
+        };
+
+        let builder = config
+            .tls
+            .configure(reqwest::Client::builder())
+            .map_err(tls_error)?;
+        let (builder, url) = config
+            .tls
+            .override_server_name(builder, &config.url)
+            .await
+            .map_err(tls_error)?;
+        let http = builder
+            .timeout(config.timeout())
+            .connect_timeout(config.timeout())
+            .build()
+            .map_err(|e| influxdb::Error::ConnectionError {
+                error: e.to_string(),
+            })?;
+        let addrs = Self::resolve(&config.url).await.unwrap_or_default();
+
+        Ok(Transport { http, url, addrs })
+    }
+
+    async fn resolve(url: &str) -> Result<Vec<SocketAddr>, Box<dyn std::error::Error>> {
+        let url = reqwest::Url::parse(url)?;
+        let host = url.host_str().ok_or("URL has no host")?;
+        let port = url.port_or_known_default().ok_or("URL has no port")?;
+        let mut addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port)).await?.collect();
+        addrs.sort_unstable();
+        Ok(addrs)
+    }
+
+    /// Replaces the HTTP client with a freshly built one, dropping pooled
+    /// connections and resolving the host again.
+    pub async fn rebuild(&self) -> Result<(), influxdb::Error> {
+        let transport = Self::connect(&self.config).await?;
+        *self
+            .transport
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = transport;
+        Ok(())
+    }
+
+    /// Whether the host of the URL now resolves to other addresses than when
+    /// the HTTP client was built.
+    pub async fn dns_changed(&self) -> bool {
+        let Ok(addrs) = Self::resolve(&self.config.url).await else {
+            return false;
+        };
+        let transport = self
+            .transport
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        !addrs.is_empty() && transport.addrs != addrs
+    }
+
+    #[must_use]
+    pub const fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Up/down state of the connection, updated by pings and writes.
+    #[must_use]
+    pub fn health(&self) -> Arc<Health> {
+        Arc::clone(&self.health)
+    }
+
+    /// The connection state for the reader to pause on, if the health
+    /// monitor runs. Without it nothing brings a connection marked down
+    /// back up, so a reader waiting on it would wait forever.
+    #[must_use]
+    pub fn monitored_health(&self) -> Option<Arc<Health>> {
+        self.config.health.interval().map(|_| self.health())
+    }
+
+    /// An authorized request to `path` of the server.
+    pub(crate) fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let transport = self
+            .transport
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        let endpoint = format!("{}{path}", transport.url.trim_end_matches('/'));
+        self.authorize(transport.http.request(method, endpoint))
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        let token = &self.config.token;
+        match self.config.api_version {
+            ApiVersion::V1 => match &self.config.username {
+                Some(username) => request.basic_auth(username, self.config.password.as_ref()),
+                None if !token.is_empty() => {
+                    request.header(AUTHORIZATION, format!("Token {token}"))
+                }
+                None => request,
+            },
+            ApiVersion::V2 => request.header(AUTHORIZATION, format!("Token {token}")),
+            ApiVersion::V3 => request.header(AUTHORIZATION, format!("Bearer {token}")),
+        }
+    }
+
+    fn write_params(&self) -> Vec<(&'static str, &str)> {
+        let precision = self.config.api_version.precision(self.config.precision);
+        match self.config.api_version {
+            ApiVersion::V1 => {
+                let mut params = vec![("db", self.config.database()), ("precision", precision)];
+                if let Some(retention_policy) = &self.config.retention_policy {
+                    params.push(("rp", retention_policy));
+                }
+                params
+            }
+            ApiVersion::V2 => vec![
+                ("org", self.config.org.as_str()),
+                ("bucket", self.config.bucket.as_str()),
+                ("precision", precision),
+            ],
+            ApiVersion::V3 => vec![("db", self.config.database()), ("precision", precision)],
+        }
+    }
+
+    /// Sends `request`, returning the status and body of any response that
+    /// is not an authentication or authorization failure.
+    async fn respond(request: RequestBuilder) -> Result<(StatusCode, String), influxdb::Error> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| influxdb::Error::ConnectionError {
+                error: e.to_string(),
+            })?;
+
+        match response.status() {
+            StatusCode::UNAUTHORIZED => Err(influxdb::Error::AuthenticationError),
+            StatusCode::FORBIDDEN => Err(influxdb::Error::AuthorizationError),
+            status => Ok((status, response.text().await.unwrap_or_default())),
+        }
+    }
+
+    pub(crate) async fn fetch(request: RequestBuilder) -> Result<String, influxdb::Error> {
+        let (status, body) = Self::respond(request).await?;
+        if status.is_success() {
+            return Ok(body);
+        }
+        Err(influxdb::Error::DatabaseError {
+            error: format!("{status}: {body}"),
+        })
+    }
+
+    async fn send(request: RequestBuilder) -> Result<(), influxdb::Error> {
+        Self::fetch(request).await.map(|_| ())
+    }
+
     pub async fn ping(&self) -> Result<(), influxdb::Error> {
-        self.client.ping().await.map(|_| ())
+        let request = self.request(Method::GET, "/ping");
+        let result = Self::send(request).await;
+        match &result {
+            Ok(()) => self.health.record_success(),
+            Err(_) => {
+                self.health.record_failure();
+            }
+        }
+        result
     }
 
-    pub async fn insert_candle(&self, candle: Candle) -> Result<(), influxdb::Error> {
-        let point =
-            WriteQuery::new(influxdb::Timestamp::Seconds(candle.timestamp), candle.event)
-                .add_field("open", candle.open)
-                .add_field("high", candle.high)
-                .add_field("low", candle.low)
-                .add_field("close", candle.close);
+    /// Sends an empty write, which the server authorizes without storing
+    /// anything. Servers answering `400 Bad Request` because the body is
+    /// empty or unparseable still accepted the token; any other failure,
+    /// such as a missing bucket or a server error, is returned.
+    pub async fn check_write_permission(&self) -> Result<(), influxdb::Error> {
+        let request = self
+            .request(Method::POST, self.config.api_version.write_path())
+            .query(&self.write_params())
+            .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body("");
+        match Self::respond(request).await? {
+            (status, _) if status.is_success() => Ok(()),
+            (StatusCode::BAD_REQUEST, body) if rejects_empty_body(&body) => Ok(()),
+            (status, body) => Err(influxdb::Error::DatabaseError {
+                error: format!("{status}: {body}"),
+            }),
+        }
+    }
 
-        self.client.query(&point).await.map(|_| ())
+    fn compress(&self, body: &[u8]) -> Result<Vec<u8>, influxdb::Error> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::new(self.config.gzip_level));
+        encoder
+            .write_all(body)
+            .and_then(|()| encoder.finish())
+            .map_err(|e| influxdb::Error::InvalidQueryError {
+                error: format!("Failed to compress body: {e}"),
+            })
+    }
+
+    async fn send_write(&self, lines: &[String]) -> Result<(), WriteError> {
+        let request = self
+            .request(Method::POST, self.config.api_version.write_path())
+            .query(&self.write_params())
+            .header(CONTENT_TYPE, "text/plain; charset=utf-8");
+
+        let body = lines.join("\n");
+        let request = if self.config.gzip {
+            let body = self
+                .compress(body.as_bytes())
+                .map_err(|e| WriteError::Failed {
+                    reason: e.to_string(),
+                })?;
+            request.header(CONTENT_ENCODING, "gzip").body(body)
+        } else {
+            request.body(body)
+        };
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                self.health.record_failure();
+                return Err(WriteError::Transient {
+                    reason: e.to_string(),
+                    retry_after: None,
+                });
+            }
+        };
+        // A server answering 5xx is as unusable as one not answering, while
+        // a 4xx only says the request was wrong.
+        if response.status().is_server_error() {
+            self.health.record_failure();
+        } else {
+            self.health.record_success();
+        }
+
+        if response.status().is_success() {
+            return Ok(());
+        }
+        Err(WriteError::from_response(response).await)
+    }
+
+    /// Writes `lines`, retrying transient failures with backoff. Batches that
+    /// are too large or partly rejected are split in halves until the
+    /// offending lines are isolated, which then go to the dead-letter file.
+    pub(crate) fn write_lines<'a>(
+        &'a self,
+        lines: &'a [String],
+    ) -> Pin<Box<dyn Future<Output = Result<(), influxdb::Error>> + Send + 'a>> {
+        Box::pin(async move {
+            let retry = &self.config.retry;
+            let mut attempt = 0;
+            loop {
+                match self.send_write(lines).await {
+                    Ok(()) => return Ok(()),
+                    Err(WriteError::Transient {
+                        reason,
+                        retry_after,
+                    }) if attempt < retry.max_retries => {
+                        let delay = retry.delay(attempt, retry_after);
+                        println!("Retrying write in {delay:?}: {reason}");
+                        attempt += 1;
+                        tokio::time::sleep(delay).await;
+                    }
+                    Err(WriteError::TooLarge { .. } | WriteError::Rejected { .. })
+                        if lines.len() > 1 =>
+                    {
+                        let (first, second) = lines.split_at(lines.len() / 2);
+                        let first = self.write_lines(first).await;
+                        let second = self.write_lines(second).await;
+                        return first.and(second);
+                    }
+                    Err(WriteError::TooLarge { reason } | WriteError::Rejected { reason }) => {
+                        let Some(dead_letter) = &self.dead_letter else {
+                            return Err(influxdb::Error::DatabaseError { error: reason });
+                        };
+                        println!("Dead-lettering point: {reason}");
+                        return dead_letter.record(&lines[0], &reason).map_err(|e| {
+                            influxdb::Error::DatabaseError {
+                                error: format!("Failed to dead-letter point ({reason}): {e}"),
+                            }
+                        });
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        })
+    }
+
+    async fn write(&self, points: Vec<Point>) -> Result<(), influxdb::Error> {
+        let lines: Vec<String> = points
+            .iter()
+            .map(|point| point.render(self.config.precision))
+            .collect();
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        self.write_lines(&lines).await
+    }
+
+    pub async fn insert_candle(&self, candle: Candle) -> Result<(), influxdb::Error> {
+        self.write(vec![self.config.schema.candle_point(&candle)])
+            .await
     }
 
     pub async fn insert_candles(&self, candles: Vec<Candle>) -> Result<(), influxdb::Error> {
-        let points: Vec<WriteQuery> = candles.into_iter().map(|candle| {
-            WriteQuery::new(influxdb::Timestamp::Seconds(candle.timestamp), candle.event)
-                .add_field("open", candle.open)
-                .add_field("high", candle.high)
-                .add_field("low", candle.low)
-                .add_field("close", candle.close)
-            }).collect();
-    
-        self.client.query(&points).await.map(|_| ())
+        let points = candles
+            .iter()
+            .map(|candle| self.config.schema.candle_point(candle))
+            .collect();
+        self.write(points).await
     }
 
     pub async fn insert_indicator(&self, indicator: Indicator) -> Result<(), influxdb::Error> {
-        let point = WriteQuery::new(
-            influxdb::Timestamp::Seconds(indicator.timestamp),
-            indicator.event,
-        )
-        .add_field(indicator.property, indicator.value);
+        self.write(vec![self.config.schema.indicator_point(&indicator)])
+            .await
+    }
+
+    pub async fn insert_indicators(
+        &self,
+        indicators: Vec<Indicator>,
+    ) -> Result<(), influxdb::Error> {
+        let points = indicators
+            .iter()
+            .map(|indicator| self.config.schema.indicator_point(indicator))
+            .collect();
+        self.write(points).await
+    }
+
+    async fn query_influxql(&self, query: String) -> Result<Vec<Row>, influxdb::Error> {
+        let mut params = vec![("db", self.config.database()), ("q", query.as_str())];
+        if let Some(retention_policy) = &self.config.retention_policy {
+            params.push(("rp", retention_policy));
+        }
 
-        self.client.query(&point).await.map(|_| ())
+        let request = self.request(Method::GET, "/query").query(&params);
+        let body = Self::fetch(request).await?;
+
+        influxql::parse(&body).map_err(|e| influxdb::Error::DeserializationError {
+            error: e.to_string(),
+        })
+    }
+
+    async fn query_flux(&self, query: String) -> Result<Vec<Row>, influxdb::Error> {
+        let request = self
+            .request(Method::POST, "/api/v2/query")
+            .query(&[("org", self.config.org.as_str())])
+            .header(CONTENT_TYPE, "application/vnd.flux")
+            .header(ACCEPT, "application/csv")
+            .body(query);
+        let body = Self::fetch(request).await?;
+
+        flux::parse(&body).map_err(|e| influxdb::Error::DeserializationError {
+            error: e.to_string(),
+        })
+    }
+
+    fn to_candles(&self, rows: &[Row]) -> Result<Vec<Candle>, influxdb::Error> {
+        rows.iter()
+            .map(|row| self.config.schema.candle_from_row(row))
+            .collect::<Result<_, _>>()
+            .map_err(|e| influxdb::Error::DeserializationError {
+                error: e.to_string(),
+            })
+    }
+
+    fn to_indicators(&self, rows: &[Row]) -> Result<Vec<Indicator>, influxdb::Error> {
+        rows.iter()
+            .map(|row| self.config.schema.indicator_from_row(row))
+            .collect::<Result<_, _>>()
+            .map_err(|e| influxdb::Error::DeserializationError {
+                error: e.to_string(),
+            })
+    }
+
+    /// Candles of `event` with `start <= timestamp < stop`, in nanoseconds,
+    /// ordered by time.
+    pub async fn query_candles(
+        &self,
+        event: &str,
+        start: u128,
+        stop: u128,
+    ) -> Result<Vec<Candle>, influxdb::Error> {
+        let schema = &self.config.schema;
+        let rows = match self.config.query_language {
+            QueryLanguage::InfluxQl => {
+                self.query_influxql(influxql::candles(schema, event, start, stop))
+                    .await?
+            }
+            QueryLanguage::Flux => {
+                self.query_flux(flux::candles(
+                    schema,
+                    &self.config.bucket,
+                    event,
+                    start,
+                    stop,
+                ))
+                .await?
+            }
+        };
+        self.to_candles(&rows)
+    }
+
+    /// The most recent candle of every event, ordered by time.
+    pub async fn latest_candles(&self) -> Result<Vec<Candle>, influxdb::Error> {
+        let schema = &self.config.schema;
+        let rows = match self.config.query_language {
+            QueryLanguage::InfluxQl => {
+                self.query_influxql(influxql::latest_candles(schema))
+                    .await?
+            }
+            QueryLanguage::Flux => {
+                self.query_flux(flux::latest_candles(schema, &self.config.bucket))
+                    .await?
+            }
+        };
+        self.to_candles(&rows)
+    }
+
+    /// Values of the `property` indicator of `event` with
+    /// `start <= timestamp < stop`, in nanoseconds, ordered by time.
+    pub async fn query_indicators(
+        &self,
+        event: &str,
+        property: &str,
+        start: u128,
+        stop: u128,
+    ) -> Result<Vec<Indicator>, influxdb::Error> {
+        let schema = &self.config.schema;
+        let rows = match self.config.query_language {
+            QueryLanguage::InfluxQl => {
+                self.query_influxql(influxql::indicators(schema, event, property, start, stop))
+                    .await?
+            }
+            QueryLanguage::Flux => {
+                let query =
+                    flux::indicators(schema, &self.config.bucket, event, property, start, stop);
+                self.query_flux(query).await?
+            }
+        };
+        self.to_indicators(&rows)
+    }
+
+    /// The most recent value of every indicator series, ordered by time.
+    pub async fn latest_indicators(&self) -> Result<Vec<Indicator>, influxdb::Error> {
+        let schema = &self.config.schema;
+        let rows = match self.config.query_language {
+            QueryLanguage::InfluxQl => {
+                self.query_influxql(influxql::latest_indicators(schema))
+                    .await?
+            }
+            QueryLanguage::Flux => {
+                self.query_flux(flux::latest_indicators(schema, &self.config.bucket))
+                    .await?
+            }
+        };
+        self.to_indicators(&rows)
     }
 
-    pub async fn insert_indicators(&self, indicators: Vec<Indicator>) -> Result<(), influxdb::Error> {
-        let points: Vec<WriteQuery> = indicators.into_iter().map(|indicator| {
-            WriteQuery::new(influxdb::Timestamp::Seconds(indicator.timestamp), indicator.event)
-                .add_field(indicator.property, indicator.value)
-            }).collect();
-    
-        self.client.query(&points).await.map(|_| ())
+    /// The latest stored timestamp of every candle and indicator series.
+    pub async fn watermarks(&self) -> Result<Watermarks, influxdb::Error> {
+        let mut watermarks = Watermarks::new();
+        for candle in self.latest_candles().await? {
+            watermarks.observe_candle(&candle);
+        }
+        for indicator in self.latest_indicators().await? {
+            watermarks.observe_indicator(&indicator);
+        }
+        Ok(watermarks)
     }
 }
 
@@ -72,69 +543,465 @@ impl Client {
 #[allow(clippy::unreadable_literal)]
 mod tests {
     use super::*;
+    use crate::influx::fake::{config, FakeServer};
+    use crate::influx::precision::Precision;
+    use rinfluxdb_lineprotocol::FieldValue;
+    use std::io::Read;
 
-    #[tokio::test]
-    async fn test_insert_candle() {
-        // Arrange
-        let config = Config::new().expect("Failed to create config");        
-        let client = Client::new(config).await.expect("Failed to create client");
-
-        let candle = Candle {
-            timestamp: 1714450980,
+    fn sample_candle() -> Candle {
+        Candle {
+            timestamp: 1_714_450_980_000_000_000,
             event: "BTCUSDT".to_string(),
             open: 100.0,
             high: 200.0,
             low: 50.0,
             close: 150.0,
-        };
+        }
+    }
+
+    #[tokio::test]
+    async fn test_write_v2() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        let config = config(server.url(), ApiVersion::V2);
+        let client = Client::new(config).await.expect("Failed to create client");
 
         // Act
-        let result = client.insert_candle(candle).await;
+        let result = client.insert_candle(sample_candle()).await;
 
         // Assert
         assert!(result.is_ok());
+
+        let requests = server.requests();
+        let write = requests.last().expect("No request received");
+        assert_eq!(write.method, "POST");
+        assert_eq!(write.path, "/api/v2/write");
+        assert_eq!(write.query("org"), Some("goboolean"));
+        assert_eq!(write.query("bucket"), Some("sample-bucket"));
+        assert_eq!(write.query("precision"), Some("s"));
+        assert_eq!(write.header("Authorization"), Some("Token token"));
+        assert_eq!(
+            String::from_utf8_lossy(&write.body),
+            "candle,event=BTCUSDT open=100,high=200,low=50,close=150 1714450980"
+        );
     }
 
     #[tokio::test]
-    async fn test_insert_candles() {
+    async fn test_only_server_errors_mark_the_connection_down() {
+        for (status, up) in [(400, true), (503, false)] {
+            // Arrange
+            let server = FakeServer::start().expect("Failed to start server");
+            let mut config = config(server.url(), ApiVersion::V2);
+            config.retry.max_retries = 0;
+            let client = Client::new(config).await.expect("Failed to create client");
+            server.respond_to("POST", "/api/v2/write", status, r#"{"code":"error"}"#);
+
+            // Act
+            let result = client.insert_candle(sample_candle()).await;
+
+            // Assert
+            assert!(result.is_err());
+            assert_eq!(client.health().is_up(), up, "{status}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_fails_after_timeout() {
         // Arrange
-        let config = Config::new().expect("Failed to create config");
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("Failed to bind");
+        let address = listener.local_addr().expect("Failed to get address");
+        let mut config = config(format!("http://{address}"), ApiVersion::V2);
+        config.timeout_ms = 100;
+
+        // Act
+        let result =
+            tokio::time::timeout(std::time::Duration::from_secs(5), Client::new(config)).await;
+
+        // Assert
+        assert!(matches!(result, Ok(Err(_))));
+    }
+
+    #[tokio::test]
+    async fn test_write_v1() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        let mut config = config(server.url(), ApiVersion::V1);
+        config.database = Some("hts".to_string());
+        config.retention_policy = Some("autogen".to_string());
+        config.username = Some("user".to_string());
+        config.password = Some("pass".to_string());
+        config.precision = Precision::Microseconds;
+        let client = Client::new(config).await.expect("Failed to create client");
+
+        // Act
+        let result = client.insert_candle(sample_candle()).await;
+
+        // Assert
+        assert!(result.is_ok());
+
+        let requests = server.requests();
+        let write = requests.last().expect("No request received");
+        assert_eq!(write.path, "/write");
+        assert_eq!(write.query("db"), Some("hts"));
+        assert_eq!(write.query("rp"), Some("autogen"));
+        assert_eq!(write.query("precision"), Some("u"));
+        assert_eq!(write.header("Authorization"), Some("Basic dXNlcjpwYXNz"));
+    }
+
+    #[tokio::test]
+    async fn test_write_v3() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        let config = config(server.url(), ApiVersion::V3);
         let client = Client::new(config).await.expect("Failed to create client");
 
+        // Act
+        let result = client.insert_candle(sample_candle()).await;
+
+        // Assert
+        assert!(result.is_ok());
+
+        let requests = server.requests();
+        let write = requests.last().expect("No request received");
+        assert_eq!(write.path, "/api/v3/write_lp");
+        assert_eq!(write.query("db"), Some("sample-bucket"));
+        assert_eq!(write.query("precision"), Some("second"));
+        assert_eq!(write.header("Authorization"), Some("Bearer token"));
+    }
+
+    #[tokio::test]
+    async fn test_write_gzip() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        let plain_client = Client::new(config(server.url(), ApiVersion::V2))
+            .await
+            .expect("Failed to create client");
+        let mut config = config(server.url(), ApiVersion::V2);
+        config.gzip = true;
+        let gzip_client = Client::new(config).await.expect("Failed to create client");
+
+        let candles: Vec<Candle> = (0..1000)
+            .map(|i| Candle {
+                timestamp: 1_714_450_980_000_000_000 + i * 60_000_000_000,
+                ..sample_candle()
+            })
+            .collect();
+
+        // Act
+        let plain_result = plain_client.insert_candles(candles.clone()).await;
+        let gzip_result = gzip_client.insert_candles(candles).await;
+
+        // Assert
+        assert!(plain_result.is_ok());
+        assert!(gzip_result.is_ok());
+
+        let writes: Vec<_> = server
+            .requests()
+            .into_iter()
+            .filter(|request| request.method == "POST")
+            .collect();
+        let (plain, gzip) = (&writes[0], &writes[1]);
+        assert_eq!(plain.header("Content-Encoding"), None);
+        assert_eq!(gzip.header("Content-Encoding"), Some("gzip"));
+
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(gzip.body.as_slice())
+            .read_to_end(&mut decoded)
+            .expect("Failed to decompress body");
+        assert_eq!(decoded, plain.body);
+
+        let ratio = gzip.body.len() as f64 / plain.body.len() as f64;
+        assert!(
+            ratio < 0.1,
+            "gzip reduced {} bytes to {} bytes ({:.1}%)",
+            plain.body.len(),
+            gzip.body.len(),
+            ratio * 100.0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_error() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        let config = config(server.url(), ApiVersion::V2);
+        let client = Client::new(config).await.expect("Failed to create client");
+        server.respond_with(401, r#"{"code":"unauthorized"}"#);
+
+        // Act
+        let result = client.insert_candle(sample_candle()).await;
+
+        // Assert
+        assert_eq!(result, Err(influxdb::Error::AuthenticationError));
+    }
+
+    fn sample_candles(count: u128) -> Vec<Candle> {
+        (0..count)
+            .map(|i| Candle {
+                timestamp: sample_candle().timestamp + i * 60_000_000_000,
+                ..sample_candle()
+            })
+            .collect()
+    }
+
+    fn written_lines(server: &FakeServer) -> Vec<usize> {
+        server
+            .requests()
+            .iter()
+            .filter(|request| request.path == "/api/v2/write")
+            .map(|request| String::from_utf8_lossy(&request.body).lines().count())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_write_retries_transient_error() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        let client = Client::new(config(server.url(), ApiVersion::V2))
+            .await
+            .expect("Failed to create client");
+        server.enqueue(503, "");
+        server.enqueue(429, "");
+
+        // Act
+        let result = client.insert_candle(sample_candle()).await;
+
+        // Assert
+        assert_eq!(result, Ok(()));
+        assert_eq!(written_lines(&server), vec![1, 1, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_write_gives_up_after_max_retries() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        let client = Client::new(config(server.url(), ApiVersion::V2))
+            .await
+            .expect("Failed to create client");
+        server.respond_with(503, "");
+
+        // Act
+        let result = client.insert_candle(sample_candle()).await;
+
+        // Assert
+        assert!(matches!(
+            result,
+            Err(influxdb::Error::ConnectionError { .. })
+        ));
+        assert_eq!(written_lines(&server).len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_write_splits_too_large_batch() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        let client = Client::new(config(server.url(), ApiVersion::V2))
+            .await
+            .expect("Failed to create client");
+        server.enqueue(413, "");
+
+        // Act
+        let result = client.insert_candles(sample_candles(4)).await;
+
+        // Assert
+        assert_eq!(result, Ok(()));
+        assert_eq!(written_lines(&server), vec![4, 2, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_write_dead_letters_rejected_point() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        let path = std::env::temp_dir().join(format!(
+            "hts-connector-dead-letter-{}.txt",
+            std::process::id()
+        ));
+        let mut config = config(server.url(), ApiVersion::V2);
+        config.retry.dead_letter_file = Some(path.to_string_lossy().to_string());
+        let client = Client::new(config).await.expect("Failed to create client");
+
+        scopeguard::defer! {
+            let _ = std::fs::remove_file(&path);
+        }
+
+        let conflict = "partial write: field type conflict: input field \"close\" is type float";
+        server.enqueue(400, conflict);
+        server.enqueue(204, "");
+        server.enqueue(400, conflict);
+
+        // Act
+        let result = client.insert_candles(sample_candles(2)).await;
+
+        // Assert
+        assert_eq!(result, Ok(()));
+        assert_eq!(written_lines(&server), vec![2, 1, 1]);
+
+        let dead_letters = std::fs::read_to_string(&path).expect("Failed to read file");
+        assert_eq!(
+            dead_letters,
+            format!(
+                "# 400 Bad Request: {conflict}\n{}\n",
+                client
+                    .config
+                    .schema
+                    .candle_point(&sample_candles(2)[1])
+                    .render(Precision::Seconds)
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_rejected_without_dead_letter() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        let client = Client::new(config(server.url(), ApiVersion::V2))
+            .await
+            .expect("Failed to create client");
+        server.enqueue(400, "unable to parse");
+
+        // Act
+        let result = client.insert_candle(sample_candle()).await;
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(influxdb::Error::DatabaseError {
+                error: "400 Bad Request: unable to parse".to_string()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_candles_influxql() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        let config = config(server.url(), ApiVersion::V1);
+        let client = Client::new(config).await.expect("Failed to create client");
+        server.respond_with(
+            200,
+            r#"{"results":[{"statement_id":0,"series":[{"name":"candle",
+            "tags":{"event":"BTCUSDT"},"columns":["time","open","high","low","close"],
+            "values":[["2024-04-30T04:23:00Z",100,200,50,150]]}]}]}"#,
+        );
+
+        // Act
+        let result = client
+            .query_candles("BTCUSDT", 0, 1_714_451_000_000_000_000)
+            .await;
+
+        // Assert
+        assert_eq!(result, Ok(vec![sample_candle()]));
+
+        let requests = server.requests();
+        let query = requests.last().expect("No request received");
+        assert_eq!(query.method, "GET");
+        assert_eq!(query.path, "/query");
+        assert_eq!(query.query("db"), Some("sample-bucket"));
+        assert!(query
+            .query("q")
+            .is_some_and(|q| q.contains("\"event\" = 'BTCUSDT'")));
+    }
+
+    #[tokio::test]
+    async fn test_query_indicators_flux() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        let mut config = config(server.url(), ApiVersion::V2);
+        config.query_language = QueryLanguage::Flux;
+        let client = Client::new(config).await.expect("Failed to create client");
+        server.respond_with(
+            200,
+            ",result,table,_time,event,property,value\r\n\
+             ,_result,0,2024-05-02T02:00:00Z,옵션,풋외국인,-13\r\n",
+        );
+
+        // Act
+        let result = client
+            .query_indicators("옵션", "풋외국인", 0, 1_714_616_000_000_000_000)
+            .await;
+
+        // Assert
+        assert_eq!(
+            result,
+            Ok(vec![Indicator {
+                timestamp: 1_714_615_200_000_000_000,
+                event: "옵션".to_string(),
+                property: "풋외국인".to_string(),
+                value: -13,
+            }])
+        );
+
+        let requests = server.requests();
+        let query = requests.last().expect("No request received");
+        assert_eq!(query.method, "POST");
+        assert_eq!(query.path, "/api/v2/query");
+        assert_eq!(query.query("org"), Some("goboolean"));
+        assert_eq!(query.header("Content-Type"), Some("application/vnd.flux"));
+        assert!(String::from_utf8_lossy(&query.body).contains("from(bucket: \"sample-bucket\")"));
+    }
+
+    #[tokio::test]
+    async fn test_insert_candle() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        let client = Client::new(config(server.url(), ApiVersion::V2))
+            .await
+            .expect("Failed to create client");
+
+        // Act
+        let result = client.insert_candle(sample_candle()).await;
+
+        // Assert
+        assert!(result.is_ok());
+
+        let points = server.points();
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].measurement, "candle");
+        assert_eq!(points[0].tag("event"), Some("BTCUSDT"));
+        assert_eq!(points[0].field("open"), Some(&FieldValue::Float(100.0)));
+        assert_eq!(points[0].field("close"), Some(&FieldValue::Float(150.0)));
+        assert_eq!(points[0].timestamp, 1_714_450_980_000_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_insert_candles() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        let client = Client::new(config(server.url(), ApiVersion::V1))
+            .await
+            .expect("Failed to create client");
+
         let candles = vec![
+            sample_candle(),
             Candle {
-                timestamp: 1714450980,
-                event: "BTCUSDT".to_string(),
-                open: 100.0,
-                high: 200.0,
-                low: 50.0,
-                close: 150.0,
-            },
-            Candle {
-                timestamp: 1714451980,
-                event: "BTCUSDT".to_string(),
-                open: 100.0,
-                high: 200.0,
-                low: 50.0,
-                close: 150.0,
+                timestamp: 1_714_451_980_000_000_000,
+                ..sample_candle()
             },
         ];
 
         // Act
-        let result = client.insert_candles(candles).await;
+        let result = client.insert_candles(candles.clone()).await;
 
         // Assert
         assert!(result.is_ok());
+        assert_eq!(
+            client
+                .query_candles("BTCUSDT", 0, 1_714_452_000_000_000_000)
+                .await,
+            Ok(candles)
+        );
     }
 
     #[tokio::test]
     async fn test_insert_indicator() {
         // Arrange
-        let config = Config::new().expect("Failed to create config");
-        let client = Client::new(config).await.expect("Failed to create client");
+        let server = FakeServer::start().expect("Failed to start server");
+        let client = Client::new(config(server.url(), ApiVersion::V3))
+            .await
+            .expect("Failed to create client");
 
         let indicator = Indicator {
-            timestamp: 1714450980,
+            timestamp: 1_714_450_980_000_000_000,
             event: "BTCUSDT".to_string(),
             property: "rsi".to_string(),
             value: 70,
@@ -145,33 +1012,48 @@ mod tests {
 
         // Assert
         assert!(result.is_ok());
+
+        let points = server.points();
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].measurement, "indicator");
+        assert_eq!(points[0].tag("property"), Some("rsi"));
+        assert_eq!(points[0].field("value"), Some(&FieldValue::Integer(70)));
+        assert_eq!(points[0].timestamp, 1_714_450_980_000_000_000);
     }
 
     #[tokio::test]
     async fn test_insert_indicators() {
         // Arrange
-        let config = Config::new().expect("Failed to create config");
-        let client = Client::new(config).await.expect("Failed to create client");
+        let server = FakeServer::start().expect("Failed to start server");
+        let client = Client::new(config(server.url(), ApiVersion::V2))
+            .await
+            .expect("Failed to create client");
 
         let indicators = vec![
             Indicator {
-                timestamp: 1714450980,
+                timestamp: 1_714_450_980_000_000_000,
                 event: "BTCUSDT".to_string(),
                 property: "rsi".to_string(),
                 value: 70,
             },
             Indicator {
-                timestamp: 1714451980,
+                timestamp: 1_714_451_980_000_000_000,
                 event: "BTCUSDT".to_string(),
                 property: "rsi".to_string(),
-                value: 70,
+                value: 30,
             },
         ];
 
         // Act
-        let result = client.insert_indicators(indicators).await;
+        let result = client.insert_indicators(indicators.clone()).await;
 
         // Assert
         assert!(result.is_ok());
+        assert_eq!(
+            client
+                .query_indicators("BTCUSDT", "rsi", 0, 1_714_452_000_000_000_000)
+                .await,
+            Ok(indicators)
+        );
     }
 }
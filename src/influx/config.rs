@@ -1,40 +1,206 @@
+use crate::config::parse_or;
+use crate::influx::api::ApiVersion;
+use crate::influx::batch::Batch;
+use crate::influx::bootstrap::Bootstrap;
+use crate::influx::health::HealthCheck;
+use crate::influx::precision::Precision;
+use crate::influx::query::QueryLanguage;
+use crate::influx::retry::Retry;
+use crate::influx::schema::Schema;
+use crate::influx::target::{Target, Targets, PRIMARY};
+use crate::influx::tls::Tls;
 use envconfig::Envconfig;
 use serde::Deserialize;
 
-use std::error::Error;
 use std::env;
+use std::error::Error;
+use std::time::Duration;
 
-#[derive(Debug, Deserialize, Envconfig)]
+#[derive(Debug, Clone, Deserialize, Envconfig)]
 pub struct Config {
     pub url: String,
     pub token: String,
     pub org: String,
     pub bucket: String,
+    #[envconfig(from = "INFLUXDB_API_VERSION", default = "v2")]
+    pub api_version: ApiVersion,
+    /// Database for the v1 and v3 APIs, defaults to the bucket.
+    #[envconfig(from = "INFLUXDB_DATABASE")]
+    pub database: Option<String>,
+    #[envconfig(from = "INFLUXDB_RETENTION_POLICY")]
+    pub retention_policy: Option<String>,
+    #[envconfig(from = "INFLUXDB_USERNAME")]
+    pub username: Option<String>,
+    #[envconfig(from = "INFLUXDB_PASSWORD")]
+    pub password: Option<String>,
+    #[envconfig(from = "INFLUXDB_PRECISION", default = "ns")]
+    pub precision: Precision,
+    /// Compress write requests with gzip.
+    #[envconfig(from = "INFLUXDB_GZIP", default = "false")]
+    pub gzip: bool,
+    /// Gzip level from 0 (fastest) to 9 (smallest).
+    #[envconfig(from = "INFLUXDB_GZIP_LEVEL", default = "6")]
+    pub gzip_level: u32,
+    /// Time a request may take, connecting included, before it fails.
+    #[envconfig(from = "INFLUXDB_TIMEOUT_MS", default = "30000")]
+    pub timeout_ms: u64,
+    /// Language used to read candles and indicators back.
+    #[envconfig(from = "INFLUXDB_QUERY_LANGUAGE", default = "influxql")]
+    pub query_language: QueryLanguage,
+    /// Query the latest stored timestamp of every series at startup and skip
+    /// lines at or before it.
+    #[envconfig(from = "INFLUXDB_RESUME", default = "false")]
+    pub resume: bool,
+    /// Additional targets every record is replicated to.
+    #[envconfig(from = "INFLUXDB_TARGETS", default = "")]
+    pub targets: Targets,
+    #[envconfig(nested = true)]
+    pub batch: Batch,
+    #[envconfig(nested = true)]
+    pub retry: Retry,
+    #[envconfig(nested = true)]
+    pub bootstrap: Bootstrap,
+    #[envconfig(nested = true)]
+    pub health: HealthCheck,
+    #[envconfig(nested = true)]
+    pub tls: Tls,
+    #[envconfig(nested = true)]
+    pub schema: Schema,
 }
 
 impl Config {
     fn retrieve_env_var(key: &str) -> Result<String, Box<dyn Error>> {
-        env::var(key).map_err(|e| {
-            Box::new(e) as Box<dyn Error>
-        })
+        env::var(key).map_err(|e| Box::new(e) as Box<dyn Error>)
+    }
+
+    fn validate(self) -> Result<Self, Box<dyn Error>> {
+        if self.gzip_level > 9 {
+            return Err(
+                format!("Expected gzip level from 0 to 9, found {}", self.gzip_level).into(),
+            );
+        }
+        if self.timeout_ms == 0 {
+            return Err("Expected a request timeout above 0 ms".into());
+        }
+        if self.batch.flush_interval_ms == 0 {
+            return Err("Expected a flush interval above 0 ms".into());
+        }
+        if self.batch.queue_capacity == 0 {
+            return Err("Expected a queue capacity above 0".into());
+        }
+        Ok(self)
     }
 
     pub fn new() -> Result<Self, Box<dyn Error>> {
-        Ok(Self {
+        Self {
             url: Self::retrieve_env_var("INFLUXDB_URL")?,
             token: Self::retrieve_env_var("INFLUXDB_TOKEN")?,
             org: Self::retrieve_env_var("INFLUXDB_ORG")?,
             bucket: Self::retrieve_env_var("INFLUXDB_BUCKET")?,
-        })
+            api_version: parse_or(env::var("INFLUXDB_API_VERSION").ok(), ApiVersion::V2)?,
+            database: env::var("INFLUXDB_DATABASE").ok(),
+            retention_policy: env::var("INFLUXDB_RETENTION_POLICY").ok(),
+            username: env::var("INFLUXDB_USERNAME").ok(),
+            password: env::var("INFLUXDB_PASSWORD").ok(),
+            precision: parse_or(env::var("INFLUXDB_PRECISION").ok(), Precision::Nanoseconds)?,
+            gzip: parse_or(env::var("INFLUXDB_GZIP").ok(), false)?,
+            gzip_level: parse_or(env::var("INFLUXDB_GZIP_LEVEL").ok(), 6)?,
+            timeout_ms: parse_or(env::var("INFLUXDB_TIMEOUT_MS").ok(), 30_000)?,
+            query_language: parse_or(
+                env::var("INFLUXDB_QUERY_LANGUAGE").ok(),
+                QueryLanguage::InfluxQl,
+            )?,
+            resume: parse_or(env::var("INFLUXDB_RESUME").ok(), false)?,
+            targets: parse_or(env::var("INFLUXDB_TARGETS").ok(), Targets::default())?,
+            batch: Batch::new()?,
+            retry: Retry::new()?,
+            bootstrap: Bootstrap::new()?,
+            health: HealthCheck::new()?,
+            tls: Tls::new()?,
+            schema: Schema::new()?,
+        }
+        .validate()
     }
 
     pub fn init() -> Result<Self, Box<dyn Error>> {
-        Ok(Self {
+        Self {
             url: env!("INFLUXDB_URL").to_string(),
             token: env!("INFLUXDB_TOKEN").to_string(),
             org: env!("INFLUXDB_ORG").to_string(),
             bucket: env!("INFLUXDB_BUCKET").to_string(),
-        })
+            api_version: parse_or(option_env!("INFLUXDB_API_VERSION"), ApiVersion::V2)?,
+            database: option_env!("INFLUXDB_DATABASE").map(ToString::to_string),
+            retention_policy: option_env!("INFLUXDB_RETENTION_POLICY").map(ToString::to_string),
+            username: option_env!("INFLUXDB_USERNAME").map(ToString::to_string),
+            password: option_env!("INFLUXDB_PASSWORD").map(ToString::to_string),
+            precision: parse_or(option_env!("INFLUXDB_PRECISION"), Precision::Nanoseconds)?,
+            gzip: parse_or(option_env!("INFLUXDB_GZIP"), false)?,
+            gzip_level: parse_or(option_env!("INFLUXDB_GZIP_LEVEL"), 6)?,
+            timeout_ms: parse_or(option_env!("INFLUXDB_TIMEOUT_MS"), 30_000)?,
+            query_language: parse_or(
+                option_env!("INFLUXDB_QUERY_LANGUAGE"),
+                QueryLanguage::InfluxQl,
+            )?,
+            resume: parse_or(option_env!("INFLUXDB_RESUME"), false)?,
+            targets: parse_or(option_env!("INFLUXDB_TARGETS"), Targets::default())?,
+            batch: Batch::init()?,
+            retry: Retry::init()?,
+            bootstrap: Bootstrap::init()?,
+            health: HealthCheck::init()?,
+            tls: Tls::init()?,
+            schema: Schema::init()?,
+        }
+        .validate()
+    }
+
+    /// The config of an additional target, sharing the precision, schema,
+    /// batching, retries and checks of this config but none of its
+    /// connection settings apart from the org.
+    #[must_use]
+    pub fn for_target(&self, target: &Target) -> Self {
+        Self {
+            url: target.url.clone(),
+            token: target.token.clone(),
+            org: target.org.clone().unwrap_or_else(|| self.org.clone()),
+            bucket: target.bucket.clone(),
+            database: target.database.clone(),
+            retention_policy: target.retention_policy.clone(),
+            username: target.username.clone(),
+            password: target.password.clone(),
+            gzip: target.gzip,
+            tls: target.tls.clone(),
+            targets: Targets::default(),
+            ..self.clone()
+        }
+    }
+
+    /// This config, named `primary`, followed by the config of every
+    /// additional target.
+    #[must_use]
+    pub fn all_targets(&self) -> Vec<(String, Self)> {
+        let primary = Self {
+            targets: Targets::default(),
+            ..self.clone()
+        };
+        std::iter::once((PRIMARY.to_string(), primary))
+            .chain(
+                self.targets
+                    .0
+                    .iter()
+                    .map(|target| (target.name.clone(), self.for_target(target))),
+            )
+            .collect()
+    }
+
+    #[must_use]
+    pub const fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+
+    /// The database written to by the v1 and v3 APIs.
+    #[must_use]
+    pub fn database(&self) -> &str {
+        self.database.as_deref().unwrap_or(&self.bucket)
     }
 }
 
@@ -82,4 +248,52 @@ mod tests {
         assert_eq!(config.org, INFLUXDB_ORG);
         assert_eq!(config.bucket, INFLUXDB_BUCKET);
     }
+
+    #[test]
+    fn test_for_target_keeps_connection_settings_apart() {
+        // Arrange
+        let mut primary =
+            crate::influx::fake::config("http://primary:8086".to_string(), ApiVersion::V1);
+        primary.username = Some("admin".to_string());
+        primary.password = Some("secret".to_string());
+        primary.retention_policy = Some("autogen".to_string());
+        primary.gzip = true;
+        primary.tls.server_name = Some("primary.internal".to_string());
+        let targets: Targets = r#"[
+            {"name":"plain","url":"http://a:8086","token":"t","bucket":"hts"},
+            {"name":"secure","url":"https://b:8086","token":"t","bucket":"hts",
+             "username":"writer","gzip":true,"tls":{"server_name":"b.internal"}}
+        ]"#
+        .parse()
+        .expect("Failed to parse targets");
+
+        // Act
+        let plain = primary.for_target(&targets.0[0]);
+        let secure = primary.for_target(&targets.0[1]);
+
+        // Assert
+        assert_eq!(plain.org, primary.org);
+        assert_eq!(plain.username, None);
+        assert_eq!(plain.password, None);
+        assert_eq!(plain.retention_policy, None);
+        assert!(!plain.gzip);
+        assert_eq!(plain.tls, Tls::default());
+        assert_eq!(secure.username.as_deref(), Some("writer"));
+        assert!(secure.gzip);
+        assert_eq!(secure.tls.server_name.as_deref(), Some("b.internal"));
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_flush_interval() {
+        // Arrange
+        let mut config =
+            crate::influx::fake::config("http://localhost:8086".to_string(), ApiVersion::V2);
+        config.batch.flush_interval_ms = 0;
+
+        // Act
+        let config = config.validate();
+
+        // Assert
+        assert!(config.is_err());
+    }
 }
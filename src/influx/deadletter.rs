@@ -0,0 +1,34 @@
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+/// Line-protocol file collecting points the server rejected. Every point is
+/// preceded by a comment with the reason, so the file can be fixed up and
+/// written again as is.
+pub struct DeadLetter {
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl DeadLetter {
+    pub fn open(path: &str) -> Result<Self, io::Error> {
+        let file = OpenOptions::new().append(true).create(true).open(path)?;
+        Ok(Self::with_writer(Box::new(file)))
+    }
+
+    #[must_use]
+    pub fn with_writer(writer: Box<dyn Write + Send>) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    pub fn record(&self, line: &str, reason: &str) -> Result<(), io::Error> {
+        let mut writer = self
+            .writer
+            .lock()
+            .map_err(|e| io::Error::other(format!("Failed to lock dead-letter file: {e}")))?;
+        let reason = reason.replace(['\r', '\n'], " ");
+        writeln!(writer, "# {reason}\n{line}")?;
+        writer.flush()
+    }
+}
@@ -0,0 +1,373 @@
+//! The subset of `InfluxQL` the fake server answers from its stored points:
+//!
+//! ```text
+//! SELECT <fields> | * FROM <measurement>
+//!     [WHERE <tag> = '<value>' | time <op> <nanoseconds> [AND ...]]
+//!     [GROUP BY <tag>, ...] [ORDER BY time [ASC | DESC]] [LIMIT <n>]
+//! ```
+//!
+//! This covers every statement built by [`crate::influx::query::influxql`].
+
+use crate::influx::fake::points::StoredPoint;
+use chrono::{DateTime, SecondsFormat};
+use rinfluxdb_lineprotocol::FieldValue;
+use serde_json::{json, Map, Value};
+
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Word(String),
+    Identifier(String),
+    String(String),
+    Number(u128),
+    Symbol(String),
+}
+
+fn quoted(chars: &mut Peekable<Chars>, quote: char) -> Result<String, String> {
+    chars.next();
+    let mut output = String::new();
+    loop {
+        match chars.next() {
+            Some(c) if c == quote => return Ok(output),
+            Some('\\') => output.push(chars.next().ok_or("unterminated quote")?),
+            Some(c) => output.push(c),
+            None => return Err("unterminated quote".to_string()),
+        }
+    }
+}
+
+fn take_while(chars: &mut Peekable<Chars>, predicate: impl Fn(char) -> bool) -> String {
+    let mut output = String::new();
+    while let Some(c) = chars.next_if(|c| predicate(*c)) {
+        output.push(c);
+    }
+    output
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, String> {
+    let mut chars = query.chars().peekable();
+    let mut tokens = Vec::new();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            tokens.push(Token::Identifier(quoted(&mut chars, '"')?));
+        } else if c == '\'' {
+            tokens.push(Token::String(quoted(&mut chars, '\'')?));
+        } else if c.is_ascii_digit() {
+            let number = take_while(&mut chars, |c| c.is_ascii_digit());
+            tokens.push(Token::Number(
+                number
+                    .parse()
+                    .map_err(|_| format!("invalid number {number}"))?,
+            ));
+        } else if c.is_alphanumeric() || c == '_' {
+            tokens.push(Token::Word(take_while(&mut chars, |c| {
+                c.is_alphanumeric() || c == '_'
+            })));
+        } else if matches!(c, '<' | '>' | '=' | '!') {
+            tokens.push(Token::Symbol(take_while(&mut chars, |c| {
+                matches!(c, '<' | '>' | '=' | '!')
+            })));
+        } else if matches!(c, ',' | '*' | ';') {
+            tokens.push(Token::Symbol(c.to_string()));
+            chars.next();
+        } else {
+            return Err(format!("unexpected {c:?}"));
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug)]
+enum Condition {
+    Tag(String, String),
+    Time(String, u128),
+}
+
+impl Condition {
+    fn matches(&self, point: &StoredPoint) -> bool {
+        match self {
+            Self::Tag(name, value) => point.tag(name) == Some(value.as_str()),
+            Self::Time(operator, time) => match operator.as_str() {
+                ">=" => point.timestamp >= *time,
+                ">" => point.timestamp > *time,
+                "<=" => point.timestamp <= *time,
+                "<" => point.timestamp < *time,
+                _ => point.timestamp == *time,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Select {
+    /// `None` for `*`.
+    fields: Option<Vec<String>>,
+    measurement: String,
+    conditions: Vec<Condition>,
+    group_by: Vec<String>,
+    descending: bool,
+    limit: Option<usize>,
+}
+
+struct Parser {
+    tokens: std::iter::Peekable<std::vec::IntoIter<Token>>,
+}
+
+impl Parser {
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(
+            self.tokens.peek(),
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword)
+        );
+        if found {
+            self.tokens.next();
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), String> {
+        if self.keyword(keyword) {
+            Ok(())
+        } else {
+            Err(format!(
+                "expected {keyword}, found {:?}",
+                self.tokens.peek()
+            ))
+        }
+    }
+
+    fn symbol(&mut self, symbol: &str) -> bool {
+        let found = self.tokens.peek() == Some(&Token::Symbol(symbol.to_string()));
+        if found {
+            self.tokens.next();
+        }
+        found
+    }
+
+    fn identifier(&mut self) -> Result<String, String> {
+        match self.tokens.next() {
+            Some(Token::Word(name) | Token::Identifier(name)) => Ok(name),
+            other => Err(format!("expected identifier, found {other:?}")),
+        }
+    }
+
+    fn identifiers(&mut self) -> Result<Vec<String>, String> {
+        let mut names = vec![self.identifier()?];
+        while self.symbol(",") {
+            names.push(self.identifier()?);
+        }
+        Ok(names)
+    }
+
+    fn condition(&mut self) -> Result<Condition, String> {
+        let name = self.identifier()?;
+        let operator = match self.tokens.next() {
+            Some(Token::Symbol(operator)) => operator,
+            other => return Err(format!("expected operator, found {other:?}")),
+        };
+
+        match (name.as_str(), self.tokens.next()) {
+            ("time", Some(Token::Number(time))) => Ok(Condition::Time(operator, time)),
+            (_, Some(Token::String(value))) if operator == "=" => Ok(Condition::Tag(name, value)),
+            (_, other) => Err(format!("unsupported condition on {name}: {other:?}")),
+        }
+    }
+
+    fn select(&mut self) -> Result<Select, String> {
+        let mut select = Select::default();
+
+        self.expect_keyword("SELECT")?;
+        if !self.symbol("*") {
+            select.fields = Some(self.identifiers()?);
+        }
+        self.expect_keyword("FROM")?;
+        select.measurement = self.identifier()?;
+
+        if self.keyword("WHERE") {
+            select.conditions.push(self.condition()?);
+            while self.keyword("AND") {
+                select.conditions.push(self.condition()?);
+            }
+        }
+        if self.keyword("GROUP") {
+            self.expect_keyword("BY")?;
+            select.group_by = self.identifiers()?;
+        }
+        if self.keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            self.expect_keyword("time")?;
+            select.descending = self.keyword("DESC");
+            if !select.descending {
+                self.keyword("ASC");
+            }
+        }
+        if self.keyword("LIMIT") {
+            match self.tokens.next() {
+                Some(Token::Number(limit)) => {
+                    select.limit = Some(usize::try_from(limit).map_err(|e| e.to_string())?);
+                }
+                other => return Err(format!("expected limit, found {other:?}")),
+            }
+        }
+
+        self.symbol(";");
+        self.tokens
+            .next()
+            .map_or(Ok(select), |token| Err(format!("unsupported {token:?}")))
+    }
+}
+
+fn to_json(value: &FieldValue) -> Value {
+    match value {
+        FieldValue::Float(f) => json!(f),
+        FieldValue::Integer(i) => json!(i),
+        FieldValue::UnsignedInteger(u) => json!(u),
+        FieldValue::String(s) => json!(s),
+        FieldValue::Boolean(b) => json!(b),
+        FieldValue::Timestamp(t) => json!(t.to_rfc3339()),
+    }
+}
+
+fn time(timestamp: u128) -> Value {
+    i64::try_from(timestamp).map_or(Value::Null, |nanos| {
+        json!(DateTime::from_timestamp_nanos(nanos).to_rfc3339_opts(SecondsFormat::AutoSi, true))
+    })
+}
+
+/// Answers `query` from `points` with a `/query` JSON response body, or an
+/// error message if the statement is not supported. Statements other than
+/// `SELECT`, such as `CREATE DATABASE`, succeed without a result.
+pub fn answer(query: &str, points: &[StoredPoint]) -> Result<String, String> {
+    let mut parser = Parser {
+        tokens: tokenize(query)?.into_iter().peekable(),
+    };
+    if !matches!(parser.tokens.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case("SELECT"))
+    {
+        return Ok(json!({ "results": [{ "statement_id": 0 }] }).to_string());
+    }
+    let select = parser.select()?;
+
+    let mut groups: BTreeMap<Vec<String>, Vec<&StoredPoint>> = BTreeMap::new();
+    for point in points.iter().filter(|point| {
+        point.measurement == select.measurement
+            && select.conditions.iter().all(|c| c.matches(point))
+    }) {
+        let key = select
+            .group_by
+            .iter()
+            .map(|tag| point.tag(tag).unwrap_or_default().to_string())
+            .collect();
+        groups.entry(key).or_default().push(point);
+    }
+
+    let series: Vec<Value> = groups
+        .into_iter()
+        .map(|(key, mut points)| {
+            points.sort_by_key(|point| point.timestamp);
+            if select.descending {
+                points.reverse();
+            }
+            if let Some(limit) = select.limit {
+                points.truncate(limit);
+            }
+
+            let columns = select.fields.clone().unwrap_or_else(|| {
+                let mut columns: Vec<String> = points
+                    .iter()
+                    .flat_map(|point| point.fields.keys().chain(point.tags.keys()))
+                    .filter(|name| !select.group_by.contains(name))
+                    .cloned()
+                    .collect();
+                columns.sort();
+                columns.dedup();
+                columns
+            });
+            let values: Vec<Value> = points
+                .iter()
+                .map(|point| {
+                    let mut row = vec![time(point.timestamp)];
+                    row.extend(columns.iter().map(|column| {
+                        point
+                            .field(column)
+                            .map(to_json)
+                            .or_else(|| point.tag(column).map(|tag| json!(tag)))
+                            .unwrap_or(Value::Null)
+                    }));
+                    Value::Array(row)
+                })
+                .collect();
+
+            let mut series = Map::new();
+            series.insert("name".to_string(), json!(select.measurement));
+            if !select.group_by.is_empty() {
+                let tags: Map<String, Value> = select
+                    .group_by
+                    .iter()
+                    .cloned()
+                    .zip(key.into_iter().map(Value::String))
+                    .collect();
+                series.insert("tags".to_string(), Value::Object(tags));
+            }
+            let columns = std::iter::once("time".to_string()).chain(columns);
+            series.insert("columns".to_string(), json!(columns.collect::<Vec<_>>()));
+            series.insert("values".to_string(), Value::Array(values));
+            Value::Object(series)
+        })
+        .collect();
+
+    let result = if series.is_empty() {
+        json!({ "statement_id": 0 })
+    } else {
+        json!({ "statement_id": 0, "series": series })
+    };
+    Ok(json!({ "results": [result] }).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::influx::fake::points::parse;
+    use crate::influx::query::influxql;
+    use crate::influx::schema::Schema;
+
+    #[test]
+    fn test_answer_latest_candles() {
+        // Arrange
+        let points = parse(
+            "candle,event=A open=1,high=2,low=0.5,close=1.5 1\n\
+             candle,event=A open=2,high=3,low=1.5,close=2.5 2\n\
+             candle,event=B open=5,high=6,low=4,close=5 1",
+            1_000_000_000,
+        )
+        .expect("Failed to parse points");
+        let query = influxql::latest_candles(&Schema::default());
+
+        // Act
+        let body = answer(&query, &points).expect("Failed to answer query");
+        let rows = influxql::parse(&body).expect("Failed to parse response");
+
+        // Assert
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].columns["event"], "B");
+        assert_eq!(rows[0].timestamp, 1_000_000_000);
+        assert_eq!(rows[1].columns["event"], "A");
+        assert_eq!(rows[1].columns["close"], "2.5");
+        assert_eq!(rows[1].timestamp, 2_000_000_000);
+    }
+
+    #[test]
+    fn test_answer_fails_on_unsupported_statement() {
+        // Act
+        let result = answer("SELECT mean(\"open\") FROM \"candle\"", &[]);
+
+        // Assert
+        assert!(result.is_err());
+    }
+}
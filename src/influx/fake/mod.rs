@@ -0,0 +1,363 @@
+//! A local stand-in for the `InfluxDB` HTTP API, recording every request it
+//! receives so tests can assert on endpoints, headers and payloads.
+//!
+//! Unless a response is configured, the server behaves like a small
+//! `InfluxDB`: writes on every API version are parsed into [`StoredPoint`]s,
+//! `SELECT` statements on `/query` are answered from them, and everything
+//! else, such as `/ping`, succeeds with `204 No Content`.
+//!
+//! Available to other crates with the `fake-server` feature.
+
+mod influxql;
+mod points;
+
+pub use points::StoredPoint;
+
+use crate::influx::api::ApiVersion;
+use crate::influx::batch::Batch;
+use crate::influx::bootstrap::Bootstrap;
+use crate::influx::config::Config;
+use crate::influx::health::HealthCheck;
+use crate::influx::precision::Precision;
+use crate::influx::query::QueryLanguage;
+use crate::influx::retry::Retry;
+use crate::influx::schema::Schema;
+use crate::influx::target::Targets;
+use crate::influx::tls::Tls;
+
+use flate2::read::GzDecoder;
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    /// Header names are lowercased.
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    #[must_use]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(String::as_str)
+    }
+
+    #[must_use]
+    pub fn query(&self, name: &str) -> Option<&str> {
+        self.query.get(name).map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Response {
+    status: u16,
+    body: String,
+}
+
+#[derive(Default)]
+struct State {
+    requests: Mutex<Vec<Request>>,
+    response: Mutex<Option<Response>>,
+    routes: Mutex<HashMap<(String, String), Response>>,
+    queue: Mutex<VecDeque<Response>>,
+    points: Mutex<Vec<StoredPoint>>,
+    stopped: AtomicBool,
+}
+
+pub struct FakeServer {
+    addr: SocketAddr,
+    state: Arc<State>,
+}
+
+impl FakeServer {
+    /// Starts the server on an ephemeral local port, with no points stored.
+    pub fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(State::default());
+
+        let accept_state = Arc::clone(&state);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if accept_state.stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let state = Arc::clone(&accept_state);
+                thread::spawn(move || {
+                    let _ = handle(stream, &state);
+                });
+            }
+        });
+
+        Ok(Self { addr, state })
+    }
+
+    #[must_use]
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn respond_with(&self, status: u16, body: &str) {
+        if let Ok(mut response) = self.state.response.lock() {
+            *response = Some(Response {
+                status,
+                body: body.to_string(),
+            });
+        }
+    }
+
+    /// Answers requests to `method` and `path` with the given response,
+    /// taking precedence over [`FakeServer::respond_with`].
+    pub fn respond_to(&self, method: &str, path: &str, status: u16, body: &str) {
+        if let Ok(mut routes) = self.state.routes.lock() {
+            routes.insert(
+                (method.to_string(), path.to_string()),
+                Response {
+                    status,
+                    body: body.to_string(),
+                },
+            );
+        }
+    }
+
+    /// Answers the next request with the given response, before any
+    /// route or default response. Queued responses are used in order.
+    pub fn enqueue(&self, status: u16, body: &str) {
+        if let Ok(mut queue) = self.state.queue.lock() {
+            queue.push_back(Response {
+                status,
+                body: body.to_string(),
+            });
+        }
+    }
+
+    #[must_use]
+    pub fn requests(&self) -> Vec<Request> {
+        self.state
+            .requests
+            .lock()
+            .map(|requests| requests.clone())
+            .unwrap_or_default()
+    }
+
+    /// Points of all successful writes, in the order they were received.
+    #[must_use]
+    pub fn points(&self) -> Vec<StoredPoint> {
+        self.state
+            .points
+            .lock()
+            .map(|points| points.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        self.state.stopped.store(true, Ordering::SeqCst);
+        // Wake up the accept loop so that it notices the server was stopped.
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+/// A client config pointing at `url`, writing with second precision.
+#[must_use]
+pub fn config(url: String, api_version: ApiVersion) -> Config {
+    Config {
+        url,
+        token: "token".to_string(),
+        org: "goboolean".to_string(),
+        bucket: "sample-bucket".to_string(),
+        api_version,
+        database: None,
+        retention_policy: None,
+        username: None,
+        password: None,
+        precision: Precision::Seconds,
+        gzip: false,
+        gzip_level: 6,
+        timeout_ms: 30_000,
+        query_language: QueryLanguage::InfluxQl,
+        resume: false,
+        targets: Targets::default(),
+        batch: Batch::default(),
+        retry: Retry {
+            backoff_ms: 1,
+            ..Retry::default()
+        },
+        bootstrap: Bootstrap::default(),
+        health: HealthCheck::default(),
+        tls: Tls::default(),
+        schema: Schema::default(),
+    }
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    reqwest::Url::parse(&format!("http://localhost/?{query}"))
+        .map(|url| url.query_pairs().into_owned().collect())
+        .unwrap_or_default()
+}
+
+fn is_write(request: &Request) -> bool {
+    matches!(
+        request.path.as_str(),
+        "/write" | "/api/v2/write" | "/api/v3/write_lp"
+    )
+}
+
+fn error(message: &str) -> Response {
+    Response {
+        status: 400,
+        body: serde_json::json!({ "error": message }).to_string(),
+    }
+}
+
+/// Parses the points of a write request and stores them, all or none.
+fn store(request: &Request, state: &State) -> Result<(), String> {
+    let mut body = String::new();
+    if request.header("Content-Encoding") == Some("gzip") {
+        GzDecoder::new(request.body.as_slice())
+            .read_to_string(&mut body)
+            .map_err(|e| format!("invalid gzip body: {e}"))?;
+    } else {
+        body = String::from_utf8_lossy(&request.body).to_string();
+    }
+
+    let nanos_per_unit = points::nanos_per_unit(request.query("precision"))?;
+    let parsed = points::parse(&body, nanos_per_unit)?;
+    if let Ok(mut points) = state.points.lock() {
+        points.extend(parsed);
+    }
+    Ok(())
+}
+
+/// Answers a request no response was configured for.
+fn answer(request: &Request, state: &State) -> Response {
+    if is_write(request) {
+        return match store(request, state) {
+            Ok(()) => Response {
+                status: 204,
+                body: String::new(),
+            },
+            Err(e) => error(&e),
+        };
+    }
+
+    if request.path == "/query" {
+        let form = parse_query(&String::from_utf8_lossy(&request.body));
+        let query = request
+            .query("q")
+            .or_else(|| form.get("q").map(String::as_str))
+            .unwrap_or_default();
+        let points = state
+            .points
+            .lock()
+            .map(|points| points.clone())
+            .unwrap_or_default();
+        return match influxql::answer(query, &points) {
+            Ok(body) => Response { status: 200, body },
+            Err(e) => error(&format!("error parsing query: {e}")),
+        };
+    }
+
+    Response {
+        status: 204,
+        body: String::new(),
+    }
+}
+
+fn handle(stream: TcpStream, state: &State) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let length = headers
+        .get("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    let configured = state
+        .queue
+        .lock()
+        .ok()
+        .and_then(|mut queue| queue.pop_front())
+        .or_else(|| {
+            state
+                .routes
+                .lock()
+                .ok()
+                .and_then(|routes| routes.get(&(method.clone(), path.to_string())).cloned())
+        });
+
+    let configured = configured.or_else(|| {
+        state
+            .response
+            .lock()
+            .ok()
+            .and_then(|response| response.clone())
+    });
+
+    let request = Request {
+        method,
+        path: path.to_string(),
+        query: parse_query(query),
+        headers,
+        body,
+    };
+    let response = match configured {
+        Some(response) => {
+            if is_write(&request) && (200..300).contains(&response.status) {
+                let _ = store(&request, state);
+            }
+            response
+        }
+        None => answer(&request, state),
+    };
+
+    if let Ok(mut requests) = state.requests.lock() {
+        requests.push(request);
+    }
+
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} Fake\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         X-Influxdb-Build: OSS\r\n\
+         X-Influxdb-Version: fake\r\n\
+         Connection: close\r\n\r\n{}",
+        response.status,
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
@@ -0,0 +1,247 @@
+//! Points stored by the fake server, parsed from the line protocol of write
+//! requests.
+
+use rinfluxdb_lineprotocol::FieldValue;
+
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A point as the fake server stored it, with its timestamp converted to
+/// nanoseconds from the precision of the write request.
+#[derive(Debug, PartialEq, Clone)]
+pub struct StoredPoint {
+    pub measurement: String,
+    pub tags: BTreeMap<String, String>,
+    pub fields: BTreeMap<String, FieldValue>,
+    pub timestamp: u128,
+}
+
+impl StoredPoint {
+    #[must_use]
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.get(name).map(String::as_str)
+    }
+
+    #[must_use]
+    pub fn field(&self, name: &str) -> Option<&FieldValue> {
+        self.fields.get(name)
+    }
+}
+
+/// Nanoseconds per unit of the `precision` parameter of a write request,
+/// accepting the spellings of every API version.
+pub fn nanos_per_unit(precision: Option<&str>) -> Result<u128, String> {
+    match precision {
+        None | Some("n" | "ns" | "nanosecond") => Ok(1),
+        Some("u" | "us" | "µs" | "microsecond") => Ok(1_000),
+        Some("ms" | "millisecond") => Ok(1_000_000),
+        Some("s" | "second") => Ok(1_000_000_000),
+        Some(precision) => Err(format!("invalid precision {precision}")),
+    }
+}
+
+struct Scanner<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl Scanner<'_> {
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("expected {expected:?}, found {c:?}")),
+            None => Err(format!("expected {expected:?}, found end of line")),
+        }
+    }
+
+    /// Reads up to the first unescaped character in `stops`, which is not
+    /// consumed.
+    fn until(&mut self, stops: &[char]) -> String {
+        let mut output = String::new();
+        while let Some(c) = self.peek() {
+            if stops.contains(&c) {
+                break;
+            }
+            self.chars.next();
+            if c == '\\' {
+                match self.chars.next() {
+                    Some(escaped @ (',' | ' ' | '=')) => output.push(escaped),
+                    Some(other) => {
+                        output.push('\\');
+                        output.push(other);
+                    }
+                    None => output.push('\\'),
+                }
+            } else {
+                output.push(c);
+            }
+        }
+        output
+    }
+
+    fn quoted(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut output = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(output),
+                Some('\\') => match self.chars.next() {
+                    Some(escaped @ ('"' | '\\')) => output.push(escaped),
+                    Some(other) => {
+                        output.push('\\');
+                        output.push(other);
+                    }
+                    None => return Err("unterminated string".to_string()),
+                },
+                Some(c) => output.push(c),
+                None => return Err("unterminated string".to_string()),
+            }
+        }
+    }
+
+    fn field_value(&mut self) -> Result<FieldValue, String> {
+        if self.peek() == Some('"') {
+            return self.quoted().map(FieldValue::String);
+        }
+
+        let value = self.until(&[',', ' ']);
+        let invalid = |_| format!("invalid field value {value}");
+        if let Some(integer) = value.strip_suffix('i') {
+            return integer.parse().map(FieldValue::Integer).map_err(invalid);
+        }
+        if let Some(unsigned) = value.strip_suffix('u') {
+            return unsigned
+                .parse()
+                .map(FieldValue::UnsignedInteger)
+                .map_err(invalid);
+        }
+        match value.as_str() {
+            "t" | "T" | "true" | "True" | "TRUE" => Ok(FieldValue::Boolean(true)),
+            "f" | "F" | "false" | "False" | "FALSE" => Ok(FieldValue::Boolean(false)),
+            _ => value
+                .parse()
+                .map(FieldValue::Float)
+                .map_err(|_| format!("invalid field value {value}")),
+        }
+    }
+}
+
+fn now() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default()
+}
+
+fn parse_line(line: &str, nanos_per_unit: u128) -> Result<StoredPoint, String> {
+    let mut scanner = Scanner {
+        chars: line.chars().peekable(),
+    };
+
+    let measurement = scanner.until(&[',', ' ']);
+    if measurement.is_empty() {
+        return Err("missing measurement".to_string());
+    }
+
+    let mut tags = BTreeMap::new();
+    while scanner.peek() == Some(',') {
+        scanner.expect(',')?;
+        let name = scanner.until(&['=', ',', ' ']);
+        scanner.expect('=')?;
+        let value = scanner.until(&[',', ' ']);
+        tags.insert(name, value);
+    }
+    scanner.expect(' ')?;
+
+    let mut fields = BTreeMap::new();
+    loop {
+        let name = scanner.until(&['=', ',', ' ']);
+        scanner.expect('=')?;
+        fields.insert(name, scanner.field_value()?);
+        if scanner.peek() != Some(',') {
+            break;
+        }
+        scanner.expect(',')?;
+    }
+
+    let timestamp = if scanner.peek() == Some(' ') {
+        scanner.expect(' ')?;
+        let timestamp = scanner.until(&[]);
+        timestamp
+            .trim()
+            .parse::<u128>()
+            .map_err(|_| format!("invalid timestamp {timestamp}"))?
+            * nanos_per_unit
+    } else {
+        now()
+    };
+
+    Ok(StoredPoint {
+        measurement,
+        tags,
+        fields,
+        timestamp,
+    })
+}
+
+/// Parses a line-protocol body, skipping empty lines and comments. Fails on
+/// the first invalid line, like `InfluxDB` does.
+pub fn parse(body: &str, nanos_per_unit: u128) -> Result<Vec<StoredPoint>, String> {
+    body.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| {
+            parse_line(line, nanos_per_unit)
+                .map_err(|e| format!("unable to parse line {}: {e}", i + 1))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line_protocol() {
+        // Arrange
+        let body = "candle,event=BTC\\ USDT,market=spot open=100,high=200.5,low=50,close=150 1\n\
+                    \n\
+                    indicator,event=옵션,property=풋외국인 value=-13i,note=\"a, \\\"b\\\"\" 2";
+
+        // Act
+        let points = parse(body, 1_000_000_000).expect("Failed to parse points");
+
+        // Assert
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].measurement, "candle");
+        assert_eq!(points[0].tag("event"), Some("BTC USDT"));
+        assert_eq!(points[0].tag("market"), Some("spot"));
+        assert_eq!(points[0].field("high"), Some(&FieldValue::Float(200.5)));
+        assert_eq!(points[0].timestamp, 1_000_000_000);
+        assert_eq!(points[1].tag("property"), Some("풋외국인"));
+        assert_eq!(points[1].field("value"), Some(&FieldValue::Integer(-13)));
+        assert_eq!(
+            points[1].field("note"),
+            Some(&FieldValue::String("a, \"b\"".to_string()))
+        );
+        assert_eq!(points[1].timestamp, 2_000_000_000);
+    }
+
+    #[test]
+    fn test_parse_fails_on_invalid_line() {
+        // Arrange
+        let body = "candle open=1 1\ncandle open=one 2";
+
+        // Act
+        let result = parse(body, 1);
+
+        // Assert
+        assert_eq!(
+            result,
+            Err("unable to parse line 2: invalid field value one".to_string())
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use crate::influx::client::Client;
+use envconfig::Envconfig;
+use serde::Deserialize;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Settings of the background health check.
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Envconfig)]
+pub struct HealthCheck {
+    /// Seconds between pings, `0` disables the health check.
+    #[envconfig(from = "INFLUXDB_HEALTH_INTERVAL_SECONDS", default = "10")]
+    pub interval_seconds: u64,
+    /// Consecutive failed pings after which the HTTP client is rebuilt.
+    #[envconfig(from = "INFLUXDB_HEALTH_REBUILD_AFTER", default = "3")]
+    pub rebuild_after: u32,
+}
+
+impl Default for HealthCheck {
+    fn default() -> Self {
+        Self::init_from_hashmap(&HashMap::new()).expect("Defaults are valid")
+    }
+}
+
+impl HealthCheck {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(Self::init_from_env()?)
+    }
+
+    pub fn init() -> Result<Self, Box<dyn Error>> {
+        let compiled = [
+            (
+                "INFLUXDB_HEALTH_INTERVAL_SECONDS",
+                option_env!("INFLUXDB_HEALTH_INTERVAL_SECONDS"),
+            ),
+            (
+                "INFLUXDB_HEALTH_REBUILD_AFTER",
+                option_env!("INFLUXDB_HEALTH_REBUILD_AFTER"),
+            ),
+        ];
+        let vars: HashMap<String, String> = compiled
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| (key.to_string(), value.to_string())))
+            .collect();
+
+        Ok(Self::init_from_hashmap(&vars)?)
+    }
+
+    #[must_use]
+    pub fn interval(&self) -> Option<Duration> {
+        (self.interval_seconds > 0).then(|| Duration::from_secs(self.interval_seconds))
+    }
+}
+
+/// Up/down state of the connection to `InfluxDB`, shared between the client,
+/// the health monitor and the reader.
+#[derive(Debug)]
+pub struct Health {
+    up: AtomicBool,
+    failures: AtomicU32,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            up: AtomicBool::new(true),
+            failures: AtomicU32::new(0),
+        }
+    }
+}
+
+impl Health {
+    #[must_use]
+    pub fn is_up(&self) -> bool {
+        self.up.load(Ordering::SeqCst)
+    }
+
+    /// Consecutive failures since the last success.
+    #[must_use]
+    pub fn failures(&self) -> u32 {
+        self.failures.load(Ordering::SeqCst)
+    }
+
+    pub fn record_success(&self) {
+        self.failures.store(0, Ordering::SeqCst);
+        if !self.up.swap(true, Ordering::SeqCst) {
+            println!("InfluxDB is up");
+        }
+    }
+
+    /// Marks the connection as down, returning the number of consecutive
+    /// failures.
+    pub fn record_failure(&self) -> u32 {
+        if self.up.swap(false, Ordering::SeqCst) {
+            println!("InfluxDB is down");
+        }
+        self.failures.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Blocks until the connection is up, checking every `poll`.
+    pub fn wait_until_up(&self, poll: Duration) {
+        while !self.is_up() {
+            thread::sleep(poll);
+        }
+    }
+}
+
+/// Pings the server every configured interval, rebuilding the HTTP client
+/// after repeated failures or when the host resolves to other addresses.
+/// Returns immediately if the health check is disabled.
+pub async fn monitor(client: Arc<Client>) {
+    let check = client.config().health.clone();
+    let Some(interval) = check.interval() else {
+        return;
+    };
+
+    loop {
+        tokio::time::sleep(interval).await;
+        check_once(&client, &check).await;
+    }
+}
+
+async fn check_once(client: &Client, check: &HealthCheck) {
+    let health = client.health();
+    let rebuild = match client.ping().await {
+        Ok(()) => client.dns_changed().await,
+        Err(e) => {
+            println!("Failed to ping InfluxDB: {e}");
+            health.failures() >= check.rebuild_after
+        }
+    };
+
+    if rebuild {
+        println!("Rebuilding InfluxDB client");
+        match client.rebuild().await {
+            Ok(()) => {
+                if client.ping().await.is_ok() {
+                    health.record_success();
+                }
+            }
+            Err(e) => println!("Failed to rebuild InfluxDB client: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(target_os = "windows"))]
+mod tests {
+    use super::*;
+    use crate::influx::api::ApiVersion;
+    use crate::influx::fake::{config, FakeServer};
+
+    #[test]
+    fn test_health_failures() {
+        // Arrange
+        let health = Health::default();
+
+        // Act
+        let first = health.record_failure();
+        let second = health.record_failure();
+
+        // Assert
+        assert_eq!((first, second), (1, 2));
+        assert!(!health.is_up());
+
+        health.record_success();
+        assert!(health.is_up());
+        assert_eq!(health.failures(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_check_tracks_server_state() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        let client = Client::new(config(server.url(), ApiVersion::V2))
+            .await
+            .expect("Failed to create client");
+        let check = HealthCheck {
+            interval_seconds: 1,
+            rebuild_after: 2,
+        };
+        let health = client.health();
+
+        // Act & Assert
+        server.respond_with(503, "");
+        check_once(&client, &check).await;
+        assert!(!health.is_up());
+        assert_eq!(health.failures(), 1);
+
+        server.respond_with(204, "");
+        check_once(&client, &check).await;
+        assert!(health.is_up());
+        assert_eq!(health.failures(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_check_rebuilds_after_repeated_failures() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        let client = Client::new(config(server.url(), ApiVersion::V2))
+            .await
+            .expect("Failed to create client");
+        let check = HealthCheck {
+            interval_seconds: 1,
+            rebuild_after: 2,
+        };
+        server.respond_with(503, "");
+
+        // Act
+        check_once(&client, &check).await;
+        check_once(&client, &check).await;
+
+        // Assert
+        // Two failed checks, then the ping after rebuilding.
+        let pings = server
+            .requests()
+            .iter()
+            .filter(|request| request.path == "/ping")
+            .count();
+        assert_eq!(pings, 4);
+        assert!(!client.health().is_up());
+    }
+
+    #[tokio::test]
+    async fn test_health_not_monitored_if_check_disabled() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        let mut disabled = config(server.url(), ApiVersion::V2);
+        disabled.health = HealthCheck {
+            interval_seconds: 0,
+            rebuild_after: 2,
+        };
+
+        // Act
+        let monitored = Client::new(config(server.url(), ApiVersion::V2))
+            .await
+            .expect("Failed to create client");
+        let unmonitored = Client::new(disabled)
+            .await
+            .expect("Failed to create client");
+
+        // Assert
+        assert!(monitored.monitored_health().is_some());
+        assert!(unmonitored.monitored_health().is_none());
+    }
+}
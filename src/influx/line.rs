@@ -0,0 +1,149 @@
+use crate::influx::precision::Precision;
+use rinfluxdb_lineprotocol::{FieldName, FieldValue, Measurement, TagName, TagValue};
+
+use std::fmt;
+
+/// A single point in `InfluxDB` line protocol.
+///
+/// Tags and fields keep their insertion order so that the rendered line is
+/// stable, which `rinfluxdb_lineprotocol::Line` does not guarantee. The
+/// timestamp is kept in nanoseconds and encoded when the line is rendered.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Point {
+    measurement: Measurement,
+    tags: Vec<(TagName, TagValue)>,
+    fields: Vec<(FieldName, FieldValue)>,
+    timestamp: u128,
+}
+
+impl Point {
+    pub fn new(measurement: impl Into<Measurement>, timestamp: u128) -> Self {
+        Self {
+            measurement: measurement.into(),
+            tags: Vec::new(),
+            fields: Vec::new(),
+            timestamp,
+        }
+    }
+
+    #[must_use]
+    pub fn add_tag(mut self, name: impl Into<TagName>, value: impl Into<TagValue>) -> Self {
+        self.tags.push((name.into(), value.into()));
+        self
+    }
+
+    #[must_use]
+    pub fn add_field(mut self, name: impl Into<FieldName>, value: impl Into<FieldValue>) -> Self {
+        self.fields.push((name.into(), value.into()));
+        self
+    }
+}
+
+fn escape_field_value(value: &FieldValue) -> String {
+    match value {
+        FieldValue::Integer(i) => format!("{i}i"),
+        FieldValue::UnsignedInteger(u) => format!("{u}u"),
+        FieldValue::String(s) => format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")),
+        other => other.escape_to_line_protocol(),
+    }
+}
+
+impl Point {
+    /// Renders the point with its timestamp encoded in the given precision.
+    #[must_use]
+    pub fn render(&self, precision: Precision) -> String {
+        let mut line = self.measurement.escape_to_line_protocol();
+
+        for (name, value) in &self.tags {
+            line.push(',');
+            line.push_str(&name.escape_to_line_protocol());
+            line.push('=');
+            line.push_str(&value.escape_to_line_protocol());
+        }
+
+        let fields: Vec<String> = self
+            .fields
+            .iter()
+            .map(|(name, value)| {
+                format!(
+                    "{}={}",
+                    name.escape_to_line_protocol(),
+                    escape_field_value(value)
+                )
+            })
+            .collect();
+
+        format!(
+            "{line} {} {}",
+            fields.join(","),
+            precision.encode(self.timestamp)
+        )
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(Precision::Nanoseconds))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_fields() {
+        // Arrange
+        let point = Point::new("candle", 1714450980)
+            .add_field("open", 100.0)
+            .add_field("high", 200.5)
+            .add_field("value", -13_i64);
+
+        // Act
+        let line = point.to_string();
+
+        // Assert
+        assert_eq!(line, "candle open=100,high=200.5,value=-13i 1714450980");
+    }
+
+    #[test]
+    fn test_render_escapes_measurement_and_fields() {
+        // Arrange
+        let point = Point::new("옵션 지표", 1714615200).add_field("풋 외국인", -13_i64);
+
+        // Act
+        let line = point.to_string();
+
+        // Assert
+        assert_eq!(line, "옵션\\ 지표 풋\\ 외국인=-13i 1714615200");
+    }
+
+    #[test]
+    fn test_render_escapes_tags() {
+        // Arrange
+        let point = Point::new("candle", 1)
+            .add_tag("event", "KOSPI 200,F")
+            .add_field("value", 1.5);
+
+        // Act
+        let line = point.to_string();
+
+        // Assert
+        assert_eq!(line, "candle,event=KOSPI\\ 200\\,F value=1.5 1");
+    }
+
+    #[test]
+    fn test_render_precision() {
+        // Arrange
+        let point = Point::new("candle", 1_714_450_860_123_456_789).add_field("value", 1.5);
+
+        // Act
+        let seconds = point.render(Precision::Seconds);
+        let millis = point.render(Precision::Milliseconds);
+
+        // Assert
+        assert_eq!(seconds, "candle value=1.5 1714450860");
+        assert_eq!(millis, "candle value=1.5 1714450860123");
+    }
+}
@@ -1,3 +1,19 @@
 pub mod adapter;
+pub mod api;
+pub mod batch;
+pub mod bootstrap;
 pub mod client;
 pub mod config;
+pub mod deadletter;
+#[cfg(any(test, feature = "fake-server"))]
+pub mod fake;
+pub mod health;
+pub mod line;
+pub mod precision;
+pub mod query;
+pub mod replicator;
+pub mod retry;
+pub mod schema;
+pub mod spool;
+pub mod target;
+pub mod tls;
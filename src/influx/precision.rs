@@ -0,0 +1,101 @@
+use serde::Deserialize;
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+const NANOS_PER_MICRO: u128 = 1_000;
+const NANOS_PER_MILLI: u128 = 1_000_000;
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
+
+/// Precision of the timestamps written to `InfluxDB`.
+///
+/// Model timestamps are nanoseconds since the epoch; they are truncated to the
+/// configured precision when encoded.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Deserialize)]
+pub enum Precision {
+    Seconds,
+    Milliseconds,
+    Microseconds,
+    #[default]
+    Nanoseconds,
+}
+
+impl Precision {
+    #[must_use]
+    pub const fn encode(self, timestamp: u128) -> u128 {
+        match self {
+            Self::Seconds => timestamp / NANOS_PER_SECOND,
+            Self::Milliseconds => timestamp / NANOS_PER_MILLI,
+            Self::Microseconds => timestamp / NANOS_PER_MICRO,
+            Self::Nanoseconds => timestamp,
+        }
+    }
+
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Seconds => "s",
+            Self::Milliseconds => "ms",
+            Self::Microseconds => "us",
+            Self::Nanoseconds => "ns",
+        }
+    }
+}
+
+impl FromStr for Precision {
+    type Err = Box<dyn Error>;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "s" => Ok(Self::Seconds),
+            "ms" => Ok(Self::Milliseconds),
+            "u" | "us" | "µs" => Ok(Self::Microseconds),
+            "ns" => Ok(Self::Nanoseconds),
+            _ => Err(format!("Expected one of s, ms, us or ns, found {input}").into()),
+        }
+    }
+}
+
+impl fmt::Display for Precision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        // Arrange
+        let timestamp: u128 = 1_714_450_860_123_456_789;
+
+        // Act & Assert
+        assert_eq!(Precision::Seconds.encode(timestamp), 1714450860);
+        assert_eq!(Precision::Milliseconds.encode(timestamp), 1714450860123);
+        assert_eq!(Precision::Microseconds.encode(timestamp), 1714450860123456);
+        assert_eq!(
+            Precision::Nanoseconds.encode(timestamp),
+            1714450860123456789
+        );
+    }
+
+    #[test]
+    fn test_from_str() {
+        // Act & Assert
+        assert_eq!("s".parse::<Precision>().ok(), Some(Precision::Seconds));
+        assert_eq!(
+            "ms".parse::<Precision>().ok(),
+            Some(Precision::Milliseconds)
+        );
+        assert_eq!(
+            "µs".parse::<Precision>().ok(),
+            Some(Precision::Microseconds)
+        );
+        assert_eq!("ns".parse::<Precision>().ok(), Some(Precision::Nanoseconds));
+        assert!("m".parse::<Precision>().is_err());
+    }
+}
@@ -0,0 +1,538 @@
+//! Typed queries reading candles and indicators back out of `InfluxDB`.
+//!
+//! Queries are built from the same [`Schema`] the client writes with, so the
+//! measurement, tag and field names always match what was stored.
+
+use crate::influx::schema::Schema;
+use crate::model::{candle::Candle, indicator::Indicator};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Query language used to read from `InfluxDB`.
+///
+/// `InfluxQl` goes through the `/query` endpoint, which v1, v2 (with a DBRP
+/// mapping) and v3 all serve. `Flux` goes through `/api/v2/query` and is
+/// only available on v2.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Deserialize)]
+pub enum QueryLanguage {
+    #[default]
+    InfluxQl,
+    Flux,
+}
+
+impl FromStr for QueryLanguage {
+    type Err = Box<dyn Error>;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "influxql" => Ok(Self::InfluxQl),
+            "flux" => Ok(Self::Flux),
+            _ => Err(format!("Expected one of influxql or flux, found {input}").into()),
+        }
+    }
+}
+
+impl fmt::Display for QueryLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InfluxQl => write!(f, "influxql"),
+            Self::Flux => write!(f, "flux"),
+        }
+    }
+}
+
+/// A row of a query result: the timestamp in nanoseconds and the tag and
+/// field values by column name.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Row {
+    pub timestamp: u128,
+    pub columns: HashMap<String, String>,
+}
+
+impl Row {
+    fn get(&self, column: &str) -> Result<&str, Box<dyn Error>> {
+        self.columns
+            .get(column)
+            .map(String::as_str)
+            .ok_or_else(|| format!("Missing column {column}").into())
+    }
+
+    fn float(&self, column: &str) -> Result<f64, Box<dyn Error>> {
+        Ok(self.get(column)?.parse()?)
+    }
+
+    fn integer(&self, column: &str) -> Result<i64, Box<dyn Error>> {
+        let value = self.get(column)?;
+        value.parse::<i64>().or_else(|_| {
+            let float: f64 = value.parse()?;
+            if float.fract() != 0.0 {
+                return Err(format!("Expected integer, found {value}").into());
+            }
+            #[allow(clippy::cast_possible_truncation)]
+            Ok(float as i64)
+        })
+    }
+}
+
+fn timestamp_nanos(time: DateTime<Utc>) -> Result<u128, Box<dyn Error>> {
+    let nanos = time
+        .timestamp_nanos_opt()
+        .ok_or_else(|| format!("Timestamp out of range: {time}"))?;
+    u128::try_from(nanos).map_err(|_| format!("Timestamp before epoch: {time}").into())
+}
+
+impl Schema {
+    pub fn candle_from_row(&self, row: &Row) -> Result<Candle, Box<dyn Error>> {
+        Ok(Candle {
+            timestamp: row.timestamp,
+            event: row.get(&self.event_tag)?.to_string(),
+            open: row.float(&self.open_field)?,
+            high: row.float(&self.high_field)?,
+            low: row.float(&self.low_field)?,
+            close: row.float(&self.close_field)?,
+        })
+    }
+
+    pub fn indicator_from_row(&self, row: &Row) -> Result<Indicator, Box<dyn Error>> {
+        Ok(Indicator {
+            timestamp: row.timestamp,
+            event: row.get(&self.event_tag)?.to_string(),
+            property: row.get(&self.property_tag)?.to_string(),
+            value: row.integer(&self.value_field)?,
+        })
+    }
+}
+
+pub mod influxql {
+    //! `InfluxQL` statements and parsing of the `/query` JSON response.
+
+    use super::{timestamp_nanos, Row};
+    use crate::influx::schema::Schema;
+    use chrono::{DateTime, Utc};
+    use rinfluxdb_influxql::{ResponseError, TagsMap};
+    use rinfluxdb_types::Value;
+
+    use std::collections::HashMap;
+    use std::error::Error;
+
+    pub(crate) fn identifier(name: &str) -> String {
+        format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+
+    fn literal(value: &str) -> String {
+        format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+
+    fn candle_fields(schema: &Schema) -> String {
+        [
+            &schema.open_field,
+            &schema.high_field,
+            &schema.low_field,
+            &schema.close_field,
+        ]
+        .map(|field| identifier(field))
+        .join(", ")
+    }
+
+    #[must_use]
+    pub fn candles(schema: &Schema, event: &str, start: u128, stop: u128) -> String {
+        format!(
+            "SELECT {} FROM {} WHERE {} = {} AND time >= {start} AND time < {stop} GROUP BY {}",
+            candle_fields(schema),
+            identifier(&schema.candle_measurement),
+            identifier(&schema.event_tag),
+            literal(event),
+            identifier(&schema.event_tag),
+        )
+    }
+
+    #[must_use]
+    pub fn latest_candles(schema: &Schema) -> String {
+        format!(
+            "SELECT {} FROM {} GROUP BY {} ORDER BY time DESC LIMIT 1",
+            candle_fields(schema),
+            identifier(&schema.candle_measurement),
+            identifier(&schema.event_tag),
+        )
+    }
+
+    #[must_use]
+    pub fn indicators(
+        schema: &Schema,
+        event: &str,
+        property: &str,
+        start: u128,
+        stop: u128,
+    ) -> String {
+        format!(
+            "SELECT {} FROM {} WHERE {} = {} AND {} = {} AND time >= {start} AND time < {stop} \
+             GROUP BY {}, {}",
+            identifier(&schema.value_field),
+            identifier(&schema.indicator_measurement),
+            identifier(&schema.event_tag),
+            literal(event),
+            identifier(&schema.property_tag),
+            literal(property),
+            identifier(&schema.event_tag),
+            identifier(&schema.property_tag),
+        )
+    }
+
+    #[must_use]
+    pub fn latest_indicators(schema: &Schema) -> String {
+        format!(
+            "SELECT {} FROM {} GROUP BY {}, {} ORDER BY time DESC LIMIT 1",
+            identifier(&schema.value_field),
+            identifier(&schema.indicator_measurement),
+            identifier(&schema.event_tag),
+            identifier(&schema.property_tag),
+        )
+    }
+
+    /// A series of the response, kept as columns until it is split into rows.
+    struct Series {
+        index: Vec<DateTime<Utc>>,
+        columns: HashMap<String, Vec<Value>>,
+    }
+
+    impl TryFrom<(String, Vec<DateTime<Utc>>, HashMap<String, Vec<Value>>)> for Series {
+        type Error = ResponseError;
+
+        fn try_from(
+            (_, index, columns): (String, Vec<DateTime<Utc>>, HashMap<String, Vec<Value>>),
+        ) -> Result<Self, Self::Error> {
+            Ok(Self { index, columns })
+        }
+    }
+
+    fn value_to_string(value: &Value) -> String {
+        match value {
+            Value::Float(f) => f.to_string(),
+            Value::Integer(i) => i.to_string(),
+            Value::UnsignedInteger(u) => u.to_string(),
+            Value::String(s) => s.clone(),
+            Value::Boolean(b) => b.to_string(),
+            Value::Timestamp(t) => t.to_rfc3339(),
+        }
+    }
+
+    /// Parses a `/query` response into rows, with the `GROUP BY` tags added
+    /// as columns. Rows are sorted by timestamp.
+    pub fn parse(input: &str) -> Result<Vec<Row>, Box<dyn Error>> {
+        let statements = rinfluxdb_influxql::from_str::<Series, ResponseError>(input)?;
+
+        let mut rows = Vec::new();
+        for statement in statements {
+            for (series, tags) in statement? {
+                let tags: TagsMap = tags.unwrap_or_default();
+                for (i, time) in series.index.iter().enumerate() {
+                    let mut columns = tags.clone();
+                    for (name, values) in &series.columns {
+                        if let Some(value) = values.get(i) {
+                            columns.insert(name.clone(), value_to_string(value));
+                        }
+                    }
+                    rows.push(Row {
+                        timestamp: timestamp_nanos(*time)?,
+                        columns,
+                    });
+                }
+            }
+        }
+
+        rows.sort_by_key(|row| row.timestamp);
+        Ok(rows)
+    }
+}
+
+pub mod flux {
+    //! Flux scripts and parsing of the `/api/v2/query` CSV response.
+
+    use super::{timestamp_nanos, Row};
+    use crate::influx::schema::Schema;
+    use chrono::{DateTime, Utc};
+
+    use std::collections::HashMap;
+    use std::error::Error;
+
+    fn string(value: &str) -> String {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+
+    fn field_filter(fields: &[&String]) -> String {
+        fields
+            .iter()
+            .map(|field| format!("r._field == {}", string(field)))
+            .collect::<Vec<_>>()
+            .join(" or ")
+    }
+
+    fn candle_fields(schema: &Schema) -> String {
+        field_filter(&[
+            &schema.open_field,
+            &schema.high_field,
+            &schema.low_field,
+            &schema.close_field,
+        ])
+    }
+
+    #[must_use]
+    pub fn candles(schema: &Schema, bucket: &str, event: &str, start: u128, stop: u128) -> String {
+        format!(
+            "from(bucket: {})\n\
+             |> range(start: time(v: {start}), stop: time(v: {stop}))\n\
+             |> filter(fn: (r) => r._measurement == {} and r[{}] == {})\n\
+             |> filter(fn: (r) => {})\n\
+             |> pivot(rowKey: [\"_time\"], columnKey: [\"_field\"], valueColumn: \"_value\")\n\
+             |> group()\n\
+             |> sort(columns: [\"_time\"])",
+            string(bucket),
+            string(&schema.candle_measurement),
+            string(&schema.event_tag),
+            string(event),
+            candle_fields(schema),
+        )
+    }
+
+    #[must_use]
+    pub fn latest_candles(schema: &Schema, bucket: &str) -> String {
+        format!(
+            "from(bucket: {})\n\
+             |> range(start: 0)\n\
+             |> filter(fn: (r) => r._measurement == {})\n\
+             |> filter(fn: (r) => {})\n\
+             |> last()\n\
+             |> pivot(rowKey: [\"_time\"], columnKey: [\"_field\"], valueColumn: \"_value\")\n\
+             |> group(columns: [{}])\n\
+             |> sort(columns: [\"_time\"])\n\
+             |> last(column: \"_time\")",
+            string(bucket),
+            string(&schema.candle_measurement),
+            candle_fields(schema),
+            string(&schema.event_tag),
+        )
+    }
+
+    #[must_use]
+    pub fn indicators(
+        schema: &Schema,
+        bucket: &str,
+        event: &str,
+        property: &str,
+        start: u128,
+        stop: u128,
+    ) -> String {
+        format!(
+            "from(bucket: {})\n\
+             |> range(start: time(v: {start}), stop: time(v: {stop}))\n\
+             |> filter(fn: (r) => r._measurement == {} and r[{}] == {} and r[{}] == {})\n\
+             |> filter(fn: (r) => r._field == {})\n\
+             |> pivot(rowKey: [\"_time\"], columnKey: [\"_field\"], valueColumn: \"_value\")\n\
+             |> group()\n\
+             |> sort(columns: [\"_time\"])",
+            string(bucket),
+            string(&schema.indicator_measurement),
+            string(&schema.event_tag),
+            string(event),
+            string(&schema.property_tag),
+            string(property),
+            string(&schema.value_field),
+        )
+    }
+
+    #[must_use]
+    pub fn latest_indicators(schema: &Schema, bucket: &str) -> String {
+        format!(
+            "from(bucket: {})\n\
+             |> range(start: 0)\n\
+             |> filter(fn: (r) => r._measurement == {})\n\
+             |> filter(fn: (r) => r._field == {})\n\
+             |> last()\n\
+             |> pivot(rowKey: [\"_time\"], columnKey: [\"_field\"], valueColumn: \"_value\")",
+            string(bucket),
+            string(&schema.indicator_measurement),
+            string(&schema.value_field),
+        )
+    }
+
+    /// Parses an annotated-CSV response into rows. Every table of the
+    /// response starts with its own header row.
+    pub fn parse(input: &str) -> Result<Vec<Row>, Box<dyn Error>> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .comment(Some(b'#'))
+            .from_reader(input.as_bytes());
+
+        let mut header: Option<Vec<String>> = None;
+        let mut rows = Vec::new();
+
+        for record in reader.records() {
+            let record = record?;
+            if record.iter().all(str::is_empty) {
+                header = None;
+                continue;
+            }
+
+            let Some(columns) = &header else {
+                header = Some(record.iter().map(ToString::to_string).collect());
+                continue;
+            };
+            if record.iter().eq(columns.iter().map(String::as_str)) {
+                continue;
+            }
+
+            let columns: HashMap<String, String> = columns
+                .iter()
+                .zip(record.iter())
+                .filter(|(name, _)| !name.is_empty())
+                .map(|(name, value)| (name.clone(), value.to_string()))
+                .collect();
+
+            let time = columns
+                .get("_time")
+                .ok_or("Missing column _time")?
+                .parse::<DateTime<Utc>>()?;
+            rows.push(Row {
+                timestamp: timestamp_nanos(time)?,
+                columns,
+            });
+        }
+
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_influxql_candles() {
+        // Arrange
+        let schema = Schema::default();
+
+        // Act
+        let query = influxql::candles(&schema, "코스피 '200'", 1, 2);
+
+        // Assert
+        assert_eq!(
+            query,
+            "SELECT \"open\", \"high\", \"low\", \"close\" FROM \"candle\" \
+             WHERE \"event\" = '코스피 \\'200\\'' AND time >= 1 AND time < 2 GROUP BY \"event\""
+        );
+    }
+
+    #[test]
+    fn test_influxql_parse_candles() {
+        // Arrange
+        let schema = Schema::default();
+        let input = r#"{"results":[{"statement_id":0,"series":[{
+            "name":"candle","tags":{"event":"BTCUSDT"},
+            "columns":["time","open","high","low","close"],
+            "values":[
+                ["2024-04-30T04:23:00Z",100,200.5,50,150],
+                ["2024-04-30T04:24:00.5Z",101,201,51,151]
+            ]}]}]}"#;
+
+        // Act
+        let candles: Vec<Candle> = influxql::parse(input)
+            .expect("Failed to parse response")
+            .iter()
+            .map(|row| schema.candle_from_row(row))
+            .collect::<Result<_, _>>()
+            .expect("Failed to convert rows");
+
+        // Assert
+        assert_eq!(
+            candles,
+            vec![
+                Candle {
+                    timestamp: 1_714_450_980_000_000_000,
+                    event: "BTCUSDT".to_string(),
+                    open: 100.0,
+                    high: 200.5,
+                    low: 50.0,
+                    close: 150.0,
+                },
+                Candle {
+                    timestamp: 1_714_451_040_500_000_000,
+                    event: "BTCUSDT".to_string(),
+                    open: 101.0,
+                    high: 201.0,
+                    low: 51.0,
+                    close: 151.0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_influxql_parse_error() {
+        // Arrange
+        let input = r#"{"results":[{"statement_id":0,"error":"database not found: hts"}]}"#;
+
+        // Act
+        let result = influxql::parse(input);
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_flux_parse_indicators() {
+        // Arrange
+        let schema = Schema::default();
+        let input = ",result,table,_start,_stop,_time,_measurement,event,property,value\r\n\
+            ,_result,0,1970-01-01T00:00:00Z,2024-06-01T00:00:00Z,2024-05-02T02:00:00Z,indicator,옵션,풋외국인,-13\r\n\
+            ,_result,0,1970-01-01T00:00:00Z,2024-06-01T00:00:00Z,2024-05-02T02:01:00Z,indicator,옵션,풋외국인,-14\r\n\
+            \r\n";
+
+        // Act
+        let indicators: Vec<Indicator> = flux::parse(input)
+            .expect("Failed to parse response")
+            .iter()
+            .map(|row| schema.indicator_from_row(row))
+            .collect::<Result<_, _>>()
+            .expect("Failed to convert rows");
+
+        // Assert
+        assert_eq!(
+            indicators,
+            vec![
+                Indicator {
+                    timestamp: 1_714_615_200_000_000_000,
+                    event: "옵션".to_string(),
+                    property: "풋외국인".to_string(),
+                    value: -13,
+                },
+                Indicator {
+                    timestamp: 1_714_615_260_000_000_000,
+                    event: "옵션".to_string(),
+                    property: "풋외국인".to_string(),
+                    value: -14,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_query_language_from_str() {
+        // Act & Assert
+        assert_eq!(
+            "InfluxQL".parse::<QueryLanguage>().ok(),
+            Some(QueryLanguage::InfluxQl)
+        );
+        assert_eq!(
+            "flux".parse::<QueryLanguage>().ok(),
+            Some(QueryLanguage::Flux)
+        );
+        assert!("sql".parse::<QueryLanguage>().is_err());
+    }
+}
@@ -0,0 +1,415 @@
+use crate::influx::batch::Batch;
+use crate::influx::client::Client;
+use crate::influx::spool::Spool;
+use crate::model::{candle::Candle, indicator::Indicator};
+use crate::text::reader::Handler;
+
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc as std_mpsc, Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::runtime::Handle;
+use tokio::sync::mpsc::{self, error::TrySendError, Receiver, Sender};
+
+/// Time given to every target to flush its queue when the replicator is
+/// dropped.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+enum Record {
+    Candle(Candle),
+    Indicator(Indicator),
+}
+
+struct TargetWriter {
+    name: String,
+    sender: Option<Sender<Record>>,
+    /// Disconnected once the writer task finished.
+    done: Mutex<std_mpsc::Receiver<()>>,
+    /// Records dropped since the queue filled up.
+    dropped: AtomicU64,
+}
+
+/// A [`Handler`] writing every record to several `InfluxDB` targets. Every
+/// target has its own queue, batches, retries and spool, so a slow or
+/// unreachable target does not hold back the others.
+///
+/// Once a target's queue is full, records are dropped for that target until
+/// it catches up, and for good once its writer stopped.
+pub struct Replicator {
+    writers: Vec<TargetWriter>,
+}
+
+impl Replicator {
+    /// Starts a writer task on `runtime` for every named client.
+    pub fn start(
+        clients: Vec<(String, Arc<Client>)>,
+        batch: &Batch,
+        runtime: &Handle,
+    ) -> Result<Self, io::Error> {
+        let mut writers = Vec::with_capacity(clients.len());
+        for (name, client) in clients {
+            let spool = Spool::new(batch.spool_dir.as_deref(), &name, batch.max_spooled_points)?;
+            let (sender, receiver) = mpsc::channel(batch.queue_capacity.max(1));
+            let (done_sender, done) = std_mpsc::sync_channel(0);
+
+            let task_name = name.clone();
+            let task_batch = batch.clone();
+            runtime.spawn(async move {
+                run(&task_name, &client, receiver, &task_batch, spool).await;
+                drop(done_sender);
+            });
+
+            writers.push(TargetWriter {
+                name,
+                sender: Some(sender),
+                done: Mutex::new(done),
+                dropped: AtomicU64::new(0),
+            });
+        }
+
+        Ok(Self { writers })
+    }
+
+    /// Queues `record` for every target, dropping it for the targets whose
+    /// queue is full or whose writer stopped. Fails only once every writer
+    /// stopped.
+    fn send(&self, record: &Record) -> Result<(), io::Error> {
+        let mut stopped = 0;
+        for writer in &self.writers {
+            let result = writer
+                .sender
+                .as_ref()
+                .map(|sender| sender.try_send(record.clone()));
+            match result {
+                Some(Ok(())) => {
+                    let dropped = writer.dropped.swap(0, Ordering::Relaxed);
+                    if dropped > 0 {
+                        println!(
+                            "Dropped {dropped} records while the queue of {} was full",
+                            writer.name
+                        );
+                    }
+                }
+                Some(Err(TrySendError::Full(_))) => {
+                    if writer.dropped.fetch_add(1, Ordering::Relaxed) == 0 {
+                        println!(
+                            "Queue of {} is full, dropping records until it catches up",
+                            writer.name
+                        );
+                    }
+                }
+                Some(Err(TrySendError::Closed(_))) | None => {
+                    stopped += 1;
+                    if writer.dropped.fetch_add(1, Ordering::Relaxed) == 0 {
+                        println!("Writer for {} stopped, dropping its records", writer.name);
+                    }
+                }
+            }
+        }
+        if stopped == self.writers.len() {
+            return Err(io::Error::other("Writers of every target stopped"));
+        }
+        Ok(())
+    }
+}
+
+impl Handler for Replicator {
+    fn handle_candle(&self, candle: Candle) -> Result<(), io::Error> {
+        self.send(&Record::Candle(candle))
+    }
+
+    fn handle_indicator(&self, indicator: Indicator) -> Result<(), io::Error> {
+        self.send(&Record::Indicator(indicator))
+    }
+}
+
+impl Drop for Replicator {
+    /// Closes every queue and waits for the targets to write or spool what
+    /// is left in them.
+    fn drop(&mut self) {
+        for writer in &mut self.writers {
+            writer.sender = None;
+        }
+        for writer in &mut self.writers {
+            let done = writer
+                .done
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner);
+            if done.recv_timeout(SHUTDOWN_TIMEOUT) == Err(std_mpsc::RecvTimeoutError::Timeout) {
+                println!("Timed out flushing {}", writer.name);
+            }
+        }
+    }
+}
+
+fn render(client: &Client, record: &Record) -> String {
+    let config = client.config();
+    let point = match record {
+        Record::Candle(candle) => config.schema.candle_point(candle),
+        Record::Indicator(indicator) => config.schema.indicator_point(indicator),
+    };
+    point.render(config.precision)
+}
+
+/// Collects records into batches of up to `batch.size` lines, sending a
+/// batch when it is full or the flush interval passed, until the queue is
+/// closed.
+async fn run(
+    name: &str,
+    client: &Client,
+    mut receiver: Receiver<Record>,
+    batch: &Batch,
+    mut spool: Spool,
+) {
+    let mut lines = Vec::new();
+    loop {
+        let deadline = tokio::time::sleep(batch.flush_interval());
+        tokio::pin!(deadline);
+
+        let closed = loop {
+            tokio::select! {
+                record = receiver.recv() => match record {
+                    Some(record) => {
+                        lines.push(render(client, &record));
+                        if lines.len() >= batch.size {
+                            break false;
+                        }
+                    }
+                    None => break true,
+                },
+                () = &mut deadline => break false,
+            }
+        };
+
+        flush(
+            name,
+            client,
+            &mut spool,
+            std::mem::take(&mut lines),
+            batch.size,
+        )
+        .await;
+        if closed {
+            return;
+        }
+    }
+}
+
+/// Spools `lines`, returning whether they were kept or dropped on purpose.
+fn spool_lines(name: &str, spool: &mut Spool, lines: &[String]) -> bool {
+    match spool.push(lines) {
+        Ok(0) => true,
+        Ok(dropped) => {
+            println!("Spool of {name} is full, dropped {dropped} points");
+            true
+        }
+        Err(e) => {
+            println!("Failed to spool points for {name}: {e}");
+            false
+        }
+    }
+}
+
+fn finish_replay(name: &str, spool: &mut Spool) {
+    if let Err(e) = spool.finish_replay() {
+        println!("Failed to clear replayed spool of {name}: {e}");
+    }
+}
+
+/// Writes `lines`, spooling them if that fails, and replays the spool once
+/// the target accepts writes again. Spooled lines stay on disk until the
+/// replay wrote them or spooled the rest again.
+async fn flush(name: &str, client: &Client, spool: &mut Spool, lines: Vec<String>, size: usize) {
+    if !lines.is_empty() {
+        if let Err(e) = client.write_lines(&lines).await {
+            println!(
+                "Failed to write to {name}, spooling {} points: {e}",
+                lines.len()
+            );
+            spool_lines(name, spool, &lines);
+            return;
+        }
+    }
+
+    if spool.is_empty() {
+        return;
+    }
+    let spooled = match spool.take() {
+        Ok(spooled) => spooled,
+        Err(e) => {
+            println!("Failed to read spool of {name}: {e}");
+            return;
+        }
+    };
+
+    let mut chunks = spooled.chunks(size.max(1));
+    while let Some(chunk) = chunks.next() {
+        if let Err(e) = client.write_lines(chunk).await {
+            println!("Failed to replay spool of {name}: {e}");
+            let rest: Vec<String> = std::iter::once(chunk)
+                .chain(chunks)
+                .flatten()
+                .cloned()
+                .collect();
+            if spool_lines(name, spool, &rest) {
+                finish_replay(name, spool);
+            }
+            return;
+        }
+    }
+    finish_replay(name, spool);
+    println!("Replayed {} spooled points to {name}", spooled.len());
+}
+
+#[cfg(test)]
+#[cfg(not(target_os = "windows"))]
+mod tests {
+    use super::*;
+    use crate::influx::api::ApiVersion;
+    use crate::influx::fake::{config, FakeServer};
+    use crate::model::candle::tests::candle;
+
+    fn written(server: &FakeServer) -> Vec<String> {
+        server
+            .requests()
+            .iter()
+            .filter(|request| request.path == "/api/v2/write")
+            .map(|request| String::from_utf8_lossy(&request.body).to_string())
+            .collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_replicates_despite_down_target() {
+        // Arrange
+        let primary = FakeServer::start().expect("Failed to start server");
+        let research = FakeServer::start().expect("Failed to start server");
+        let clients = vec![
+            (
+                "primary".to_string(),
+                Arc::new(
+                    Client::new(config(primary.url(), ApiVersion::V2))
+                        .await
+                        .expect("Failed to create client"),
+                ),
+            ),
+            (
+                "research".to_string(),
+                Arc::new(
+                    Client::new(config(research.url(), ApiVersion::V2))
+                        .await
+                        .expect("Failed to create client"),
+                ),
+            ),
+        ];
+        let batch = Batch {
+            size: 2,
+            flush_interval_ms: 50,
+            spool_dir: None,
+            ..Batch::default()
+        };
+        research.respond_with(503, "");
+
+        let replicator =
+            Replicator::start(clients, &batch, &Handle::current()).expect("Failed to start");
+
+        // Act
+        for timestamp in 1..=3 {
+            replicator
+                .handle_candle(candle("BTCUSDT", timestamp * 1_000_000_000, 150.0))
+                .expect("Failed to handle candle");
+        }
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        research.respond_with(204, "");
+        tokio::task::spawn_blocking(move || drop(replicator))
+            .await
+            .expect("Failed to stop replicator");
+
+        // Assert
+        assert_eq!(
+            written(&primary),
+            vec![
+                "candle,event=BTCUSDT open=100,high=200,low=50,close=150 1\n\
+                 candle,event=BTCUSDT open=100,high=200,low=50,close=150 2",
+                "candle,event=BTCUSDT open=100,high=200,low=50,close=150 3",
+            ]
+        );
+        // Both batches failed and were spooled, then replayed once the
+        // target came back.
+        let research = written(&research);
+        assert_eq!(
+            research[research.len() - 2..],
+            [
+                "candle,event=BTCUSDT open=100,high=200,low=50,close=150 1\n\
+                 candle,event=BTCUSDT open=100,high=200,low=50,close=150 2",
+                "candle,event=BTCUSDT open=100,high=200,low=50,close=150 3",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_drops_records_once_queue_is_full() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        // The writer task never runs, so nothing leaves the queue.
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to create runtime");
+        let client = runtime
+            .block_on(Client::new(config(server.url(), ApiVersion::V2)))
+            .expect("Failed to create client");
+        let batch = Batch {
+            queue_capacity: 2,
+            ..Batch::default()
+        };
+        let replicator = Replicator::start(
+            vec![("research".to_string(), Arc::new(client))],
+            &batch,
+            runtime.handle(),
+        )
+        .expect("Failed to start");
+
+        // Act
+        let results: Vec<Result<(), io::Error>> = (1..=5)
+            .map(|timestamp| replicator.handle_candle(candle("BTCUSDT", timestamp, 150.0)))
+            .collect();
+        let dropped = replicator.writers[0].dropped.load(Ordering::Relaxed);
+        drop(runtime);
+        drop(replicator);
+
+        // Assert
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(dropped, 3);
+    }
+
+    #[test]
+    fn test_stopped_target_does_not_block_others() {
+        // Arrange
+        let writer = |name: &str| {
+            let (sender, receiver) = mpsc::channel(4);
+            let (_, done) = std_mpsc::sync_channel(0);
+            let writer = TargetWriter {
+                name: name.to_string(),
+                sender: Some(sender),
+                done: Mutex::new(done),
+                dropped: AtomicU64::new(0),
+            };
+            (writer, receiver)
+        };
+        let (stopped, receiver) = writer("primary");
+        drop(receiver);
+        let (research, mut receiver) = writer("research");
+        let replicator = Replicator {
+            writers: vec![stopped, research],
+        };
+
+        // Act
+        let result = replicator.handle_candle(candle("BTCUSDT", 1, 150.0));
+
+        // Assert
+        assert!(result.is_ok());
+        assert!(matches!(receiver.try_recv(), Ok(Record::Candle(_))));
+        assert_eq!(replicator.writers[0].dropped.load(Ordering::Relaxed), 1);
+    }
+}
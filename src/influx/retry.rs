@@ -0,0 +1,230 @@
+use envconfig::Envconfig;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Response, StatusCode};
+use serde::Deserialize;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+/// Settings for retrying failed writes and keeping rejected points.
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Envconfig)]
+pub struct Retry {
+    /// Retries of a write failing with a transient error before giving up.
+    #[envconfig(from = "INFLUXDB_MAX_RETRIES", default = "3")]
+    pub max_retries: u32,
+    /// Delay before the first retry in milliseconds, doubled on every retry.
+    #[envconfig(from = "INFLUXDB_RETRY_BACKOFF_MS", default = "500")]
+    pub backoff_ms: u64,
+    /// Longest `Retry-After` of the server waited for before a retry.
+    #[envconfig(from = "INFLUXDB_MAX_RETRY_AFTER_SECONDS", default = "60")]
+    pub max_retry_after_seconds: u64,
+    /// File receiving points the server rejected, along with its reason.
+    #[envconfig(from = "INFLUXDB_DEAD_LETTER_FILE")]
+    pub dead_letter_file: Option<String>,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self::init_from_hashmap(&HashMap::new()).expect("Defaults are valid")
+    }
+}
+
+impl Retry {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(Self::init_from_env()?)
+    }
+
+    pub fn init() -> Result<Self, Box<dyn Error>> {
+        let compiled = [
+            ("INFLUXDB_MAX_RETRIES", option_env!("INFLUXDB_MAX_RETRIES")),
+            (
+                "INFLUXDB_RETRY_BACKOFF_MS",
+                option_env!("INFLUXDB_RETRY_BACKOFF_MS"),
+            ),
+            (
+                "INFLUXDB_MAX_RETRY_AFTER_SECONDS",
+                option_env!("INFLUXDB_MAX_RETRY_AFTER_SECONDS"),
+            ),
+            (
+                "INFLUXDB_DEAD_LETTER_FILE",
+                option_env!("INFLUXDB_DEAD_LETTER_FILE"),
+            ),
+        ];
+        let vars: HashMap<String, String> = compiled
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| (key.to_string(), value.to_string())))
+            .collect();
+
+        Ok(Self::init_from_hashmap(&vars)?)
+    }
+
+    /// Delay before the retry following `attempt` failed attempts.
+    #[must_use]
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        Duration::from_millis(self.backoff_ms.saturating_mul(1 << attempt.min(16)))
+    }
+
+    /// Delay before the retry following `attempt` failed attempts, waiting
+    /// for the server's `retry_after` up to `max_retry_after_seconds`.
+    #[must_use]
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        retry_after.map_or_else(
+            || self.backoff(attempt),
+            |retry_after| retry_after.min(Duration::from_secs(self.max_retry_after_seconds)),
+        )
+    }
+}
+
+/// Why a write request failed, deciding how the client handles it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum WriteError {
+    /// The server could not be reached or is overloaded (429, 502, 503,
+    /// 504). Worth retrying, after `retry_after` if the server said so.
+    Transient {
+        reason: String,
+        retry_after: Option<Duration>,
+    },
+    /// The body was too large (413). Worth retrying in smaller batches.
+    TooLarge { reason: String },
+    /// Some or all points were rejected (400, 422), e.g. because of a field
+    /// type conflict. Points that were accepted are stored already.
+    Rejected { reason: String },
+    /// The token is missing or invalid (401).
+    Unauthenticated,
+    /// The token may not write to the bucket (403).
+    Unauthorized,
+    /// Any other failure, not worth retrying.
+    Failed { reason: String },
+}
+
+impl WriteError {
+    /// Classifies a response that was not successful.
+    pub async fn from_response(response: Response) -> Self {
+        let status = response.status();
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+            .map(Duration::from_secs);
+        let body = response.text().await.unwrap_or_default();
+        Self::classify(status, retry_after, &body)
+    }
+
+    #[must_use]
+    pub fn classify(status: StatusCode, retry_after: Option<Duration>, body: &str) -> Self {
+        let reason = format!("{status}: {}", body.trim());
+        match status {
+            StatusCode::UNAUTHORIZED => Self::Unauthenticated,
+            StatusCode::FORBIDDEN => Self::Unauthorized,
+            StatusCode::PAYLOAD_TOO_LARGE => Self::TooLarge { reason },
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => Self::Rejected { reason },
+            StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT => Self::Transient {
+                reason,
+                retry_after,
+            },
+            _ => Self::Failed { reason },
+        }
+    }
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Transient { reason, .. } => write!(f, "Transient failure: {reason}"),
+            Self::TooLarge { reason } => write!(f, "Request too large: {reason}"),
+            Self::Rejected { reason } => write!(f, "Points rejected: {reason}"),
+            Self::Unauthenticated => write!(f, "Authentication failed"),
+            Self::Unauthorized => write!(f, "Not authorized to write"),
+            Self::Failed { reason } => write!(f, "Write failed: {reason}"),
+        }
+    }
+}
+
+impl From<WriteError> for influxdb::Error {
+    fn from(error: WriteError) -> Self {
+        match error {
+            WriteError::Unauthenticated => Self::AuthenticationError,
+            WriteError::Unauthorized => Self::AuthorizationError,
+            WriteError::Transient { reason, .. } => Self::ConnectionError { error: reason },
+            WriteError::TooLarge { reason }
+            | WriteError::Rejected { reason }
+            | WriteError::Failed { reason } => Self::DatabaseError { error: reason },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        // Act & Assert
+        assert_eq!(
+            WriteError::classify(
+                StatusCode::BAD_REQUEST,
+                None,
+                "partial write: field type conflict\n"
+            ),
+            WriteError::Rejected {
+                reason: "400 Bad Request: partial write: field type conflict".to_string()
+            }
+        );
+        assert_eq!(
+            WriteError::classify(
+                StatusCode::TOO_MANY_REQUESTS,
+                Some(Duration::from_secs(2)),
+                ""
+            ),
+            WriteError::Transient {
+                reason: "429 Too Many Requests: ".to_string(),
+                retry_after: Some(Duration::from_secs(2)),
+            }
+        );
+        assert!(matches!(
+            WriteError::classify(StatusCode::PAYLOAD_TOO_LARGE, None, ""),
+            WriteError::TooLarge { .. }
+        ));
+        assert_eq!(
+            WriteError::classify(StatusCode::FORBIDDEN, None, ""),
+            WriteError::Unauthorized
+        );
+        assert!(matches!(
+            WriteError::classify(StatusCode::INTERNAL_SERVER_ERROR, None, ""),
+            WriteError::Failed { .. }
+        ));
+    }
+
+    #[test]
+    fn test_backoff_doubles() {
+        // Arrange
+        let retry = Retry::default();
+
+        // Act & Assert
+        assert_eq!(retry.backoff(0), Duration::from_millis(500));
+        assert_eq!(retry.backoff(2), Duration::from_millis(2000));
+    }
+
+    #[test]
+    fn test_delay_caps_retry_after() {
+        // Arrange
+        let retry = Retry::default();
+
+        // Act & Assert
+        assert_eq!(
+            retry.delay(0, Some(Duration::from_secs(2))),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            retry.delay(0, Some(Duration::from_secs(u64::MAX))),
+            Duration::from_secs(60)
+        );
+        assert_eq!(retry.delay(1, None), Duration::from_millis(1000));
+    }
+}
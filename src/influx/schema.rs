@@ -0,0 +1,228 @@
+use crate::influx::line::Point;
+use crate::model::{candle::Candle, indicator::Indicator};
+use envconfig::Envconfig;
+use serde::Deserialize;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::str::FromStr;
+
+/// Static tags given as `key=value` pairs separated by commas,
+/// e.g. `source=trading-pc,market=krx`.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Deserialize)]
+pub struct StaticTags(pub Vec<(String, String)>);
+
+impl FromStr for StaticTags {
+    type Err = Box<dyn Error>;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        input
+            .split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.split_once('=') {
+                Some((key, value)) if !key.trim().is_empty() && !value.trim().is_empty() => {
+                    Ok((key.trim().to_string(), value.trim().to_string()))
+                }
+                _ => Err(format!("Expected static tag as key=value, found {pair}").into()),
+            })
+            .collect::<Result<_, Self::Err>>()
+            .map(Self)
+    }
+}
+
+/// Maps models onto `InfluxDB` measurements, tags and fields.
+///
+/// By default candles go to the `candle` measurement and indicators to the
+/// `indicator` measurement, with the event (and property) stored as tags so
+/// that series can be queried across symbols.
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Envconfig)]
+pub struct Schema {
+    #[envconfig(from = "INFLUXDB_CANDLE_MEASUREMENT", default = "candle")]
+    pub candle_measurement: String,
+    #[envconfig(from = "INFLUXDB_INDICATOR_MEASUREMENT", default = "indicator")]
+    pub indicator_measurement: String,
+    #[envconfig(from = "INFLUXDB_EVENT_TAG", default = "event")]
+    pub event_tag: String,
+    #[envconfig(from = "INFLUXDB_PROPERTY_TAG", default = "property")]
+    pub property_tag: String,
+    #[envconfig(from = "INFLUXDB_STATIC_TAGS", default = "")]
+    pub static_tags: StaticTags,
+    #[envconfig(from = "INFLUXDB_OPEN_FIELD", default = "open")]
+    pub open_field: String,
+    #[envconfig(from = "INFLUXDB_HIGH_FIELD", default = "high")]
+    pub high_field: String,
+    #[envconfig(from = "INFLUXDB_LOW_FIELD", default = "low")]
+    pub low_field: String,
+    #[envconfig(from = "INFLUXDB_CLOSE_FIELD", default = "close")]
+    pub close_field: String,
+    #[envconfig(from = "INFLUXDB_VALUE_FIELD", default = "value")]
+    pub value_field: String,
+}
+
+impl Default for Schema {
+    fn default() -> Self {
+        Self::init_from_hashmap(&HashMap::new()).expect("Default schema is valid")
+    }
+}
+
+impl Schema {
+    /// Reads the schema from the environment, falling back to the defaults.
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(Self::init_from_env()?)
+    }
+
+    /// Reads the schema from the environment at compile time, falling back
+    /// to the defaults.
+    pub fn init() -> Result<Self, Box<dyn Error>> {
+        let compiled = [
+            (
+                "INFLUXDB_CANDLE_MEASUREMENT",
+                option_env!("INFLUXDB_CANDLE_MEASUREMENT"),
+            ),
+            (
+                "INFLUXDB_INDICATOR_MEASUREMENT",
+                option_env!("INFLUXDB_INDICATOR_MEASUREMENT"),
+            ),
+            ("INFLUXDB_EVENT_TAG", option_env!("INFLUXDB_EVENT_TAG")),
+            (
+                "INFLUXDB_PROPERTY_TAG",
+                option_env!("INFLUXDB_PROPERTY_TAG"),
+            ),
+            ("INFLUXDB_STATIC_TAGS", option_env!("INFLUXDB_STATIC_TAGS")),
+            ("INFLUXDB_OPEN_FIELD", option_env!("INFLUXDB_OPEN_FIELD")),
+            ("INFLUXDB_HIGH_FIELD", option_env!("INFLUXDB_HIGH_FIELD")),
+            ("INFLUXDB_LOW_FIELD", option_env!("INFLUXDB_LOW_FIELD")),
+            ("INFLUXDB_CLOSE_FIELD", option_env!("INFLUXDB_CLOSE_FIELD")),
+            ("INFLUXDB_VALUE_FIELD", option_env!("INFLUXDB_VALUE_FIELD")),
+        ];
+        let vars: HashMap<String, String> = compiled
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| (key.to_string(), value.to_string())))
+            .collect();
+
+        Ok(Self::init_from_hashmap(&vars)?)
+    }
+
+    fn with_static_tags(&self, mut point: Point) -> Point {
+        for (name, value) in &self.static_tags.0 {
+            point = point.add_tag(name.as_str(), value.as_str());
+        }
+        point
+    }
+
+    #[must_use]
+    pub fn candle_point(&self, candle: &Candle) -> Point {
+        let point = Point::new(self.candle_measurement.as_str(), candle.timestamp)
+            .add_tag(self.event_tag.as_str(), candle.event.as_str());
+
+        self.with_static_tags(point)
+            .add_field(self.open_field.as_str(), candle.open)
+            .add_field(self.high_field.as_str(), candle.high)
+            .add_field(self.low_field.as_str(), candle.low)
+            .add_field(self.close_field.as_str(), candle.close)
+    }
+
+    #[must_use]
+    pub fn indicator_point(&self, indicator: &Indicator) -> Point {
+        let point = Point::new(self.indicator_measurement.as_str(), indicator.timestamp)
+            .add_tag(self.event_tag.as_str(), indicator.event.as_str())
+            .add_tag(self.property_tag.as_str(), indicator.property.as_str());
+
+        self.with_static_tags(point)
+            .add_field(self.value_field.as_str(), indicator.value)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unreadable_literal)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_candle_point() {
+        // Arrange
+        let schema = Schema::default();
+        let candle = Candle {
+            timestamp: 1_714_450_860_000_000_000,
+            event: "코스피 200".to_string(),
+            open: 368.85,
+            high: 368.9,
+            low: 368.7,
+            close: 368.75,
+        };
+
+        // Act
+        let line = schema.candle_point(&candle).to_string();
+
+        // Assert
+        assert_eq!(
+            line,
+            "candle,event=코스피\\ 200 open=368.85,high=368.9,low=368.7,close=368.75 1714450860000000000"
+        );
+    }
+
+    #[test]
+    fn test_default_indicator_point() {
+        // Arrange
+        let schema = Schema::default();
+        let indicator = Indicator {
+            timestamp: 1_714_615_200_000_000_000,
+            event: "옵션".to_string(),
+            property: "풋외국인".to_string(),
+            value: -13,
+        };
+
+        // Act
+        let line = schema.indicator_point(&indicator).to_string();
+
+        // Assert
+        assert_eq!(
+            line,
+            "indicator,event=옵션,property=풋외국인 value=-13i 1714615200000000000"
+        );
+    }
+
+    #[test]
+    fn test_custom_schema() {
+        // Arrange
+        let vars: HashMap<String, String> = [
+            ("INFLUXDB_INDICATOR_MEASUREMENT", "hts_indicator"),
+            ("INFLUXDB_EVENT_TAG", "symbol"),
+            ("INFLUXDB_PROPERTY_TAG", "name"),
+            ("INFLUXDB_VALUE_FIELD", "v"),
+            ("INFLUXDB_STATIC_TAGS", "source=trading-pc, market=krx"),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+        let indicator = Indicator {
+            timestamp: 1_714_615_200_000_000_000,
+            event: "옵션".to_string(),
+            property: "풋외국인".to_string(),
+            value: -13,
+        };
+
+        // Act
+        let schema = Schema::init_from_hashmap(&vars).expect("Failed to create schema");
+        let line = schema.indicator_point(&indicator).to_string();
+
+        // Assert
+        assert_eq!(
+            line,
+            "hts_indicator,symbol=옵션,name=풋외국인,source=trading-pc,market=krx v=-13i 1714615200000000000"
+        );
+    }
+
+    #[test]
+    fn test_parse_static_tags_fail() {
+        // Arrange
+        let input = "source=trading-pc,market";
+
+        // Act
+        let result = StaticTags::from_str(input);
+
+        // Assert
+        assert!(result.is_err());
+    }
+}
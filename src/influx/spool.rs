@@ -0,0 +1,189 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Line-protocol lines that could not be written to a target yet, kept until
+/// the target accepts writes again.
+#[derive(Debug)]
+pub enum Spool {
+    /// Keeps up to `max_lines` lines, dropping the ones beyond.
+    Memory {
+        lines: Vec<String>,
+        max_lines: usize,
+    },
+    /// Appends lines to `<name>.lp`, which is renamed to
+    /// `<name>.lp.replaying` while it is replayed.
+    File(PathBuf),
+}
+
+fn replaying(path: &Path) -> PathBuf {
+    path.with_extension("lp.replaying")
+}
+
+/// Moves the lines of a replay that did not finish back into the spool.
+fn recover(path: &Path) -> Result<(), io::Error> {
+    let replaying = replaying(path);
+    let content = match fs::read_to_string(&replaying) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    let mut file = OpenOptions::new().append(true).create(true).open(path)?;
+    file.write_all(content.as_bytes())?;
+    file.flush()?;
+    fs::remove_file(replaying)
+}
+
+impl Spool {
+    /// A spool for the target `name`, kept in `dir` if given and in memory
+    /// up to `max_lines` lines otherwise. Lines spooled to the file by a
+    /// previous run, including a replay it did not finish, are picked up
+    /// again.
+    pub fn new(dir: Option<&str>, name: &str, max_lines: usize) -> Result<Self, io::Error> {
+        let Some(dir) = dir else {
+            return Ok(Self::Memory {
+                lines: Vec::new(),
+                max_lines,
+            });
+        };
+
+        fs::create_dir_all(dir)?;
+        let path = PathBuf::from(dir).join(format!("{name}.lp"));
+        recover(&path)?;
+        Ok(Self::File(path))
+    }
+
+    /// Appends `lines`, returning how many were dropped because the memory
+    /// spool is full.
+    pub fn push(&mut self, lines: &[String]) -> Result<usize, io::Error> {
+        match self {
+            Self::Memory {
+                lines: spooled,
+                max_lines,
+            } => {
+                let kept = lines.len().min(max_lines.saturating_sub(spooled.len()));
+                spooled.extend_from_slice(&lines[..kept]);
+                Ok(lines.len() - kept)
+            }
+            Self::File(path) => {
+                let mut file = OpenOptions::new().append(true).create(true).open(path)?;
+                for line in lines {
+                    writeln!(file, "{line}")?;
+                }
+                file.flush()?;
+                Ok(0)
+            }
+        }
+    }
+
+    /// Returns all spooled lines for a replay. The file spool keeps them
+    /// until [`Self::finish_replay`], so they survive a replay that is
+    /// interrupted.
+    pub fn take(&mut self) -> Result<Vec<String>, io::Error> {
+        match self {
+            Self::Memory { lines, .. } => Ok(std::mem::take(lines)),
+            Self::File(path) => {
+                recover(path)?;
+                let replaying = replaying(path);
+                match fs::rename(&*path, &replaying) {
+                    Ok(()) => (),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+                    Err(e) => return Err(e),
+                }
+                let content = fs::read_to_string(replaying)?;
+                Ok(content.lines().map(ToString::to_string).collect())
+            }
+        }
+    }
+
+    /// Removes the lines returned by [`Self::take`], once they were written
+    /// or spooled again.
+    pub fn finish_replay(&mut self) -> Result<(), io::Error> {
+        match self {
+            Self::Memory { .. } => Ok(()),
+            Self::File(path) => match fs::remove_file(replaying(path)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            },
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::Memory { lines, .. } => lines.is_empty(),
+            Self::File(path) => [path.clone(), replaying(path)]
+                .iter()
+                .all(|path| fs::metadata(path).map_or(true, |metadata| metadata.len() == 0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_file_spool_round_trip() {
+        // Arrange
+        let dir = env::temp_dir().join(format!("hts-connector-spool-{}", std::process::id()));
+        let mut spool = Spool::new(dir.to_str(), "research", 0).expect("Failed to create spool");
+        let lines = vec![
+            "candle,event=a open=1 1".to_string(),
+            "candle,event=a open=2 2".to_string(),
+        ];
+
+        // Act
+        spool.push(&lines).expect("Failed to spool lines");
+        let spooled = spool.take().expect("Failed to take lines");
+        spool.finish_replay().expect("Failed to finish replay");
+
+        // Assert
+        assert_eq!(spooled, lines);
+        assert!(spool.is_empty());
+        assert_eq!(fs::read_dir(&dir).map(Iterator::count).ok(), Some(0));
+
+        fs::remove_dir_all(&dir).expect("Failed to remove spool directory");
+    }
+
+    #[test]
+    fn test_file_spool_recovers_interrupted_replay() {
+        // Arrange
+        let dir =
+            env::temp_dir().join(format!("hts-connector-spool-replay-{}", std::process::id()));
+        let mut spool = Spool::new(dir.to_str(), "research", 0).expect("Failed to create spool");
+        let replayed = vec!["candle,event=a open=1 1".to_string()];
+        let spooled = vec!["candle,event=a open=2 2".to_string()];
+        spool.push(&replayed).expect("Failed to spool lines");
+        spool.take().expect("Failed to take lines");
+        spool.push(&spooled).expect("Failed to spool lines");
+
+        // Act
+        let mut spool = Spool::new(dir.to_str(), "research", 0).expect("Failed to create spool");
+        let lines = spool.take().expect("Failed to take lines");
+
+        // Assert
+        assert_eq!(lines, [&spooled[..], &replayed[..]].concat());
+
+        fs::remove_dir_all(&dir).expect("Failed to remove spool directory");
+    }
+
+    #[test]
+    fn test_memory_spool_drops_lines_beyond_max() {
+        // Arrange
+        let mut spool = Spool::new(None, "research", 3).expect("Failed to create spool");
+        let lines: Vec<String> = (1..=2)
+            .map(|timestamp| format!("candle,event=a open=1 {timestamp}"))
+            .collect();
+
+        // Act
+        let first = spool.push(&lines).expect("Failed to spool lines");
+        let second = spool.push(&lines).expect("Failed to spool lines");
+        let spooled = spool.take().expect("Failed to take lines");
+
+        // Assert
+        assert_eq!((first, second), (0, 1));
+        assert_eq!(spooled, [&lines[..], &lines[..1]].concat());
+    }
+}
@@ -0,0 +1,158 @@
+use crate::influx::tls::Tls;
+use serde::Deserialize;
+
+use std::error::Error;
+use std::str::FromStr;
+
+/// An additional `InfluxDB` written to alongside the primary one. A left out
+/// org is taken from the primary config; credentials, retention policy, TLS
+/// and gzip belong to the target and default to none.
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+pub struct Target {
+    pub name: String,
+    pub url: String,
+    pub token: String,
+    #[serde(default)]
+    pub org: Option<String>,
+    pub bucket: String,
+    #[serde(default)]
+    pub database: Option<String>,
+    #[serde(default)]
+    pub retention_policy: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub gzip: bool,
+    #[serde(default)]
+    pub tls: Tls,
+}
+
+/// Name of the target configured by the `INFLUXDB_*` variables, reserved.
+pub const PRIMARY: &str = "primary";
+
+/// Whether `name` may name a target. Names also name its spool file, so they
+/// are limited to ASCII letters, digits, `_` and `-`.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Additional targets, given as a JSON array such as
+/// `[{"name":"research","url":"http://research:8086","token":"…","bucket":"hts"}]`.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Deserialize)]
+pub struct Targets(pub Vec<Target>);
+
+impl FromStr for Targets {
+    type Err = Box<dyn Error>;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        if input.trim().is_empty() {
+            return Ok(Self::default());
+        }
+
+        let targets: Vec<Target> = serde_json::from_str(input)
+            .map_err(|e| format!("Expected a JSON array of targets, {e}"))?;
+        for (i, target) in targets.iter().enumerate() {
+            if !is_valid_name(&target.name) {
+                return Err(format!(
+                    "Expected target names of letters, digits, _ and -, found {:?}",
+                    target.name
+                )
+                .into());
+            }
+            if target.name == PRIMARY || targets[..i].iter().any(|t| t.name == target.name) {
+                return Err(
+                    format!("Expected unique target names, found {:?}", target.name).into(),
+                );
+            }
+        }
+
+        Ok(Self(targets))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_targets_from_str() {
+        // Arrange
+        let input =
+            r#"[{"name":"research","url":"http://research:8086","token":"t","bucket":"hts"}]"#;
+
+        // Act
+        let targets: Targets = input.parse().expect("Failed to parse targets");
+
+        // Assert
+        assert_eq!(
+            targets,
+            Targets(vec![Target {
+                name: "research".to_string(),
+                url: "http://research:8086".to_string(),
+                token: "t".to_string(),
+                org: None,
+                bucket: "hts".to_string(),
+                database: None,
+                retention_policy: None,
+                username: None,
+                password: None,
+                gzip: false,
+                tls: Tls::default(),
+            }])
+        );
+        assert_eq!("".parse::<Targets>().ok(), Some(Targets::default()));
+    }
+
+    #[test]
+    fn test_targets_fail_if_names_repeat() {
+        // Arrange
+        let input = r#"[
+            {"name":"a","url":"http://a:8086","token":"t","bucket":"hts"},
+            {"name":"a","url":"http://b:8086","token":"t","bucket":"hts"}
+        ]"#;
+
+        // Act
+        let targets = input.parse::<Targets>();
+
+        // Assert
+        assert!(targets.is_err());
+    }
+
+    #[test]
+    fn test_targets_fail_if_name_is_primary() {
+        // Arrange
+        let input = r#"[{"name":"primary","url":"http://a:8086","token":"t","bucket":"hts"}]"#;
+
+        // Act
+        let targets = input.parse::<Targets>();
+
+        // Assert
+        assert!(targets.is_err());
+    }
+
+    #[test]
+    fn test_targets_fail_if_name_is_not_a_file_name() {
+        for name in ["", "../x", "a/b", "a b", "."] {
+            // Arrange
+            let input = format!(
+                r#"[{{"name":{name:?},"url":"http://a:8086","token":"t","bucket":"hts"}}]"#
+            );
+
+            // Act
+            let targets = input.parse::<Targets>();
+
+            // Assert
+            assert!(targets.is_err(), "{name:?} was accepted");
+        }
+        assert!(
+            r#"[{"name":"research_2-b","url":"http://a:8086","token":"t","bucket":"hts"}]"#
+                .parse::<Targets>()
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use envconfig::Envconfig;
+use reqwest::{Certificate, ClientBuilder, Identity, Url};
+use serde::Deserialize;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+
+/// TLS settings for the connection to `InfluxDB`.
+#[derive(Debug, PartialEq, Eq, Clone, Default, Deserialize, Envconfig)]
+#[serde(default)]
+pub struct Tls {
+    /// PEM bundle of CA certificates trusted in addition to the built-in roots.
+    #[envconfig(from = "INFLUXDB_TLS_CA_FILE")]
+    pub ca_file: Option<String>,
+    /// PEM client certificate presented to the server, requires `key_file`.
+    #[envconfig(from = "INFLUXDB_TLS_CERT_FILE")]
+    pub cert_file: Option<String>,
+    #[envconfig(from = "INFLUXDB_TLS_KEY_FILE")]
+    pub key_file: Option<String>,
+    /// Name used for SNI and certificate verification instead of the URL host,
+    /// while still connecting to the address of the URL host.
+    #[envconfig(from = "INFLUXDB_TLS_SERVER_NAME")]
+    pub server_name: Option<String>,
+    /// Accept any server certificate. Only meant for lab setups.
+    #[envconfig(from = "INFLUXDB_TLS_INSECURE", default = "false")]
+    pub insecure: bool,
+}
+
+impl Tls {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(Self::init_from_env()?)
+    }
+
+    pub fn init() -> Result<Self, Box<dyn Error>> {
+        let compiled = [
+            ("INFLUXDB_TLS_CA_FILE", option_env!("INFLUXDB_TLS_CA_FILE")),
+            (
+                "INFLUXDB_TLS_CERT_FILE",
+                option_env!("INFLUXDB_TLS_CERT_FILE"),
+            ),
+            (
+                "INFLUXDB_TLS_KEY_FILE",
+                option_env!("INFLUXDB_TLS_KEY_FILE"),
+            ),
+            (
+                "INFLUXDB_TLS_SERVER_NAME",
+                option_env!("INFLUXDB_TLS_SERVER_NAME"),
+            ),
+            (
+                "INFLUXDB_TLS_INSECURE",
+                option_env!("INFLUXDB_TLS_INSECURE"),
+            ),
+        ];
+        let vars: HashMap<String, String> = compiled
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| (key.to_string(), value.to_string())))
+            .collect();
+
+        Ok(Self::init_from_hashmap(&vars)?)
+    }
+
+    fn read(path: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        fs::read(path).map_err(|e| format!("Failed to read {path}: {e}").into())
+    }
+
+    /// Applies the CA bundle, client identity and verification settings.
+    pub fn configure(&self, mut builder: ClientBuilder) -> Result<ClientBuilder, Box<dyn Error>> {
+        if let Some(ca_file) = &self.ca_file {
+            let certificates = Certificate::from_pem_bundle(&Self::read(ca_file)?)?;
+            if certificates.is_empty() {
+                return Err(format!("No certificate found in {ca_file}").into());
+            }
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        match (&self.cert_file, &self.key_file) {
+            (Some(cert_file), Some(key_file)) => {
+                let mut pem = Self::read(cert_file)?;
+                pem.push(b'\n');
+                pem.extend(Self::read(key_file)?);
+                builder = builder.identity(Identity::from_pem(&pem)?);
+            }
+            (None, None) => (),
+            _ => {
+                return Err(
+                    "INFLUXDB_TLS_CERT_FILE and INFLUXDB_TLS_KEY_FILE must be set together".into(),
+                )
+            }
+        }
+
+        Ok(builder.danger_accept_invalid_certs(self.insecure))
+    }
+
+    /// Rewrites `url` to use the configured server name, pinning that name
+    /// to the addresses of the original host. Returns the URL to use.
+    pub async fn override_server_name(
+        &self,
+        builder: ClientBuilder,
+        url: &str,
+    ) -> Result<(ClientBuilder, String), Box<dyn Error>> {
+        let Some(server_name) = &self.server_name else {
+            return Ok((builder, url.to_string()));
+        };
+
+        let mut url = Url::parse(url)?;
+        let host = url.host_str().ok_or("URL has no host")?.to_string();
+        let port = url.port_or_known_default().ok_or("URL has no port")?;
+        let addrs: Vec<_> = tokio::net::lookup_host((host.as_str(), port))
+            .await?
+            .collect();
+
+        url.set_host(Some(server_name))?;
+        let builder = builder.resolve_to_addrs(server_name, &addrs);
+
+        Ok((builder, url.as_str().trim_end_matches('/').to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::influx::fake::FakeServer;
+
+    #[test]
+    fn test_configure_fail_if_no_ca_file_exists() {
+        // Arrange
+        let tls = Tls {
+            ca_file: Some("tests/missing-ca.pem".to_string()),
+            ..Tls::default()
+        };
+
+        // Act
+        let result = tls.configure(reqwest::Client::builder());
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_configure_fail_if_cert_without_key() {
+        // Arrange
+        let tls = Tls {
+            cert_file: Some("tests/client.pem".to_string()),
+            ..Tls::default()
+        };
+
+        // Act
+        let result = tls.configure(reqwest::Client::builder());
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_override_server_name() {
+        // Arrange
+        let server = FakeServer::start().expect("Failed to start server");
+        let tls = Tls {
+            server_name: Some("influxdb.internal".to_string()),
+            insecure: true,
+            ..Tls::default()
+        };
+        let builder = tls
+            .configure(reqwest::Client::builder())
+            .expect("Failed to configure TLS");
+
+        // Act
+        let (builder, url) = tls
+            .override_server_name(builder, &server.url())
+            .await
+            .expect("Failed to override server name");
+        let client = builder.build().expect("Failed to build client");
+        let response = client.get(format!("{url}/ping")).send().await;
+
+        // Assert
+        assert!(url.starts_with("http://influxdb.internal:"));
+        assert!(response.is_ok());
+
+        let requests = server.requests();
+        let ping = requests.last().expect("No request received");
+        assert!(ping
+            .header("Host")
+            .is_some_and(|host| host.starts_with("influxdb.internal")));
+    }
+}
@@ -0,0 +1,149 @@
+use crate::kafka::config::Config;
+use crate::model::{candle::Candle, indicator::Indicator};
+use crate::text::reader::Handler;
+use rdkafka::config::ClientConfig;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::util::Timeout;
+
+use std::io;
+use std::time::Duration;
+use tokio::runtime::{Builder, Runtime};
+
+/// A [`Handler`] publishing every record to Kafka, keyed by its event so
+/// that the records of a symbol stay in order on one partition.
+///
+/// A record is handled once the brokers acknowledged it, so a failed
+/// delivery fails the line like a failed `InfluxDB` write does.
+pub struct KafkaHandler {
+    producer: FutureProducer,
+    config: Config,
+    runtime: Runtime,
+}
+
+impl KafkaHandler {
+    pub fn new(config: Config) -> Result<Self, io::Error> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", &config.brokers)
+            .set("message.timeout.ms", config.delivery_timeout_ms.to_string())
+            // Keeps the order of a key when sends are retried.
+            .set("enable.idempotence", "true")
+            .create()
+            .map_err(|e| io::Error::other(format!("Failed to create producer: {e}")))?;
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| io::Error::other(format!("Failed to create runtime: {e}")))?;
+
+        Ok(Self {
+            producer,
+            config,
+            runtime,
+        })
+    }
+
+    fn publish(&self, topic: &str, key: &str, payload: &[u8]) -> Result<(), io::Error> {
+        let record = FutureRecord::to(topic).key(key).payload(payload);
+        let timeout = Timeout::After(Duration::from_millis(self.config.delivery_timeout_ms));
+        self.runtime
+            .block_on(self.producer.send(record, timeout))
+            .map(|_| ())
+            .map_err(|(e, _)| io::Error::other(format!("Failed to deliver to {topic}: {e}")))
+    }
+}
+
+impl Handler for KafkaHandler {
+    fn handle_candle(&self, candle: Candle) -> Result<(), io::Error> {
+        let payload = self.config.format.encode_candle(&candle)?;
+        self.publish(&self.config.candle_topic, &candle.event, &payload)
+    }
+
+    fn handle_indicator(&self, indicator: Indicator) -> Result<(), io::Error> {
+        let payload = self.config.format.encode_indicator(&indicator)?;
+        self.publish(&self.config.indicator_topic, &indicator.event, &payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kafka::payload::Format;
+    use crate::model::candle::tests::candle;
+    use crate::model::proto;
+    use prost::Message as _;
+    use rdkafka::consumer::{BaseConsumer, Consumer};
+    use rdkafka::mocking::MockCluster;
+    use rdkafka::{Message, Offset, TopicPartitionList};
+
+    fn config(brokers: String, format: Format) -> Config {
+        Config {
+            brokers,
+            candle_topic: "candle".to_string(),
+            indicator_topic: "indicator".to_string(),
+            format,
+            delivery_timeout_ms: 5000,
+        }
+    }
+
+    #[test]
+    fn test_publish_candle_keyed_by_event() {
+        // Arrange
+        let cluster = MockCluster::new(1).expect("Failed to start cluster");
+        cluster
+            .create_topic("candle", 3, 1)
+            .expect("Failed to create topic");
+        let handler = KafkaHandler::new(config(cluster.bootstrap_servers(), Format::Protobuf))
+            .expect("Failed to create handler");
+
+        // Act
+        let result = handler.handle_candle(candle("BTCUSDT", 1_714_450_980_000_000_000, 150.0));
+
+        // Assert
+        assert!(result.is_ok());
+
+        let consumer: BaseConsumer = ClientConfig::new()
+            .set("bootstrap.servers", cluster.bootstrap_servers())
+            .set("group.id", "test")
+            .set("auto.offset.reset", "earliest")
+            .create()
+            .expect("Failed to create consumer");
+        let mut partitions = TopicPartitionList::new();
+        for partition in 0..3 {
+            partitions
+                .add_partition_offset("candle", partition, Offset::Beginning)
+                .expect("Failed to add partition");
+        }
+        consumer.assign(&partitions).expect("Failed to assign");
+        let message = consumer
+            .poll(Duration::from_secs(10))
+            .expect("No message received")
+            .expect("Failed to consume");
+
+        assert_eq!(message.key(), Some(b"BTCUSDT".as_slice()));
+        assert_eq!(
+            proto::Candle::decode(message.payload().unwrap_or_default())
+                .expect("Failed to decode candle"),
+            proto::Candle {
+                event: "BTCUSDT".to_string(),
+                timestamp: 1_714_450_980_000_000_000,
+                open: 100.0,
+                high: 200.0,
+                low: 50.0,
+                close: 150.0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_publish_fails_if_not_acknowledged() {
+        // Arrange
+        let mut config = config("127.0.0.1:1".to_string(), Format::Json);
+        config.delivery_timeout_ms = 200;
+        let handler = KafkaHandler::new(config).expect("Failed to create handler");
+
+        // Act
+        let result = handler.handle_candle(candle("BTCUSDT", 1_714_450_980_000_000_000, 150.0));
+
+        // Assert
+        assert!(result.is_err());
+    }
+}
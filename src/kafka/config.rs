@@ -0,0 +1,51 @@
+use crate::config::parse_or;
+use crate::kafka::payload::Format;
+use envconfig::Envconfig;
+use serde::Deserialize;
+
+use std::env;
+use std::error::Error;
+
+/// Brokers used when `KAFKA_BROKERS` is not set.
+pub const DEFAULT_BROKERS: &str = "localhost:9092";
+
+#[derive(Debug, Deserialize, Envconfig)]
+pub struct Config {
+    /// Comma-separated `host:port` list of bootstrap brokers.
+    pub brokers: String,
+    pub candle_topic: String,
+    pub indicator_topic: String,
+    pub format: Format,
+    /// Time a record may take to be acknowledged by the brokers, including
+    /// retries, before handling it fails.
+    pub delivery_timeout_ms: u64,
+}
+
+impl Config {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            brokers: env::var("KAFKA_BROKERS").unwrap_or_else(|_| DEFAULT_BROKERS.to_string()),
+            candle_topic: env::var("KAFKA_CANDLE_TOPIC").unwrap_or_else(|_| "candle".to_string()),
+            indicator_topic: env::var("KAFKA_INDICATOR_TOPIC")
+                .unwrap_or_else(|_| "indicator".to_string()),
+            format: parse_or(env::var("KAFKA_FORMAT").ok(), Format::default())?,
+            delivery_timeout_ms: parse_or(env::var("KAFKA_DELIVERY_TIMEOUT_MS").ok(), 5000)?,
+        })
+    }
+
+    pub fn init() -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            brokers: option_env!("KAFKA_BROKERS")
+                .unwrap_or(DEFAULT_BROKERS)
+                .to_string(),
+            candle_topic: option_env!("KAFKA_CANDLE_TOPIC")
+                .unwrap_or("candle")
+                .to_string(),
+            indicator_topic: option_env!("KAFKA_INDICATOR_TOPIC")
+                .unwrap_or("indicator")
+                .to_string(),
+            format: parse_or(option_env!("KAFKA_FORMAT"), Format::default())?,
+            delivery_timeout_ms: parse_or(option_env!("KAFKA_DELIVERY_TIMEOUT_MS"), 5000)?,
+        })
+    }
+}
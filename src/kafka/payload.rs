@@ -0,0 +1,98 @@
+//! Encoding of the records published to Kafka.
+//!
+//! The Protobuf messages are the `Candle` and `Indicator` messages of
+//! `proto/hts/feed.proto`, see [`crate::model::proto`].
+
+use crate::model::proto;
+use crate::model::{candle::Candle, indicator::Indicator};
+use prost::Message;
+use serde::Deserialize;
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+/// Encoding of the message payloads.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Deserialize)]
+pub enum Format {
+    #[default]
+    Json,
+    Protobuf,
+}
+
+impl FromStr for Format {
+    type Err = Box<dyn Error>;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "protobuf" | "proto" => Ok(Self::Protobuf),
+            _ => Err(format!("Expected one of json or protobuf, found {input}").into()),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Json => write!(f, "json"),
+            Self::Protobuf => write!(f, "protobuf"),
+        }
+    }
+}
+
+impl Format {
+    pub fn encode_candle(self, candle: &Candle) -> Result<Vec<u8>, io::Error> {
+        match self {
+            Self::Json => Ok(serde_json::to_vec(candle)?),
+            Self::Protobuf => Ok(proto::Candle::try_from(candle.clone())?.encode_to_vec()),
+        }
+    }
+
+    pub fn encode_indicator(self, indicator: &Indicator) -> Result<Vec<u8>, io::Error> {
+        match self {
+            Self::Json => Ok(serde_json::to_vec(indicator)?),
+            Self::Protobuf => Ok(proto::Indicator::try_from(indicator.clone())?.encode_to_vec()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_indicator() {
+        // Arrange
+        let indicator = Indicator {
+            timestamp: 1_714_615_200_000_000_000,
+            event: "옵션".to_string(),
+            property: "풋외국인".to_string(),
+            value: -13,
+        };
+
+        // Act
+        let json = Format::Json
+            .encode_indicator(&indicator)
+            .expect("Failed to encode indicator");
+        let protobuf = Format::Protobuf
+            .encode_indicator(&indicator)
+            .expect("Failed to encode indicator");
+
+        // Assert
+        assert_eq!(
+            String::from_utf8_lossy(&json),
+            r#"{"timestamp":1714615200000000000,"event":"옵션","property":"풋외국인","value":-13}"#
+        );
+        assert_eq!(
+            proto::Indicator::decode(protobuf.as_slice()).expect("Failed to decode indicator"),
+            proto::Indicator {
+                event: "옵션".to_string(),
+                timestamp: 1_714_615_200_000_000_000,
+                property: "풋외국인".to_string(),
+                value: -13,
+            }
+        );
+    }
+}
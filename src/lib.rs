@@ -1,3 +1,25 @@
+pub mod config;
+pub mod dryrun;
+pub mod export;
+#[cfg(feature = "grpc")]
+pub mod grpc;
 pub mod influx;
+#[cfg(feature = "kafka")]
+pub mod kafka;
 pub mod model;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod normalized;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod questdb;
+pub mod reconcile;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 pub mod text;
+#[cfg(feature = "webhook")]
+pub mod webhook;
+#[cfg(feature = "websocket")]
+pub mod websocket;
@@ -1,28 +1,414 @@
+use hts_connector::dryrun::adapter::DryRunHandler;
+use hts_connector::dryrun::config::Config as DryRunConfig;
+use hts_connector::export::adapter::ExportHandler;
+use hts_connector::export::config::Config as ExportConfig;
+#[cfg(feature = "grpc")]
+use hts_connector::grpc::adapter::GrpcHandler;
+#[cfg(feature = "grpc")]
+use hts_connector::grpc::config::Config as GrpcConfig;
 use hts_connector::influx::adapter::InfluxHandler;
 use hts_connector::influx::client::Client as InfluxClient;
 use hts_connector::influx::config::Config as InfluxConfig;
+use hts_connector::influx::health::{monitor, Health};
+use hts_connector::influx::replicator::Replicator;
+#[cfg(feature = "kafka")]
+use hts_connector::kafka::adapter::KafkaHandler;
+#[cfg(feature = "kafka")]
+use hts_connector::kafka::config::Config as KafkaConfig;
+#[cfg(feature = "mqtt")]
+use hts_connector::mqtt::adapter::MqttHandler;
+#[cfg(feature = "mqtt")]
+use hts_connector::mqtt::config::Config as MqttConfig;
+use hts_connector::normalized::adapter::NormalizedHandler;
+use hts_connector::normalized::config::Config as NormalizedConfig;
+#[cfg(feature = "parquet")]
+use hts_connector::parquet::adapter::ParquetHandler;
+#[cfg(feature = "parquet")]
+use hts_connector::parquet::config::Config as ParquetConfig;
+#[cfg(feature = "postgres")]
+use hts_connector::postgres::adapter::PostgresHandler;
+#[cfg(feature = "postgres")]
+use hts_connector::postgres::config::Config as PostgresConfig;
+use hts_connector::questdb::adapter::QuestDbHandler;
+use hts_connector::questdb::config::Config as QuestDbConfig;
+use hts_connector::reconcile::command::reconcile;
+#[cfg(feature = "sqlite")]
+use hts_connector::sqlite::adapter::SqliteHandler;
+#[cfg(feature = "sqlite")]
+use hts_connector::sqlite::config::Config as SqliteConfig;
 use hts_connector::text::config::Config as TextConfig;
-use hts_connector::text::reader::Reader as TextReader;
+use hts_connector::text::fanout::FanOut;
+use hts_connector::text::reader::{Handler, Reader as TextReader};
+use hts_connector::text::watermark::Watermarks;
+#[cfg(feature = "webhook")]
+use hts_connector::webhook::adapter::WebhookHandler;
+#[cfg(feature = "webhook")]
+use hts_connector::webhook::config::Config as WebhookConfig;
+#[cfg(feature = "websocket")]
+use hts_connector::websocket::adapter::WebSocketHandler;
+#[cfg(feature = "websocket")]
+use hts_connector::websocket::config::Config as WebSocketConfig;
 
+use std::env;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::runtime::Runtime;
 
-fn main() {
-    let path: &'static str = env!("INFLUXDB_URL");
-    println!("the $PATH variable at the time of compiling was: {path}");
+/// Returns the dry-run config if `--dry-run` or `--dry-run=<path>` was passed.
+fn dry_run_config() -> Option<DryRunConfig> {
+    env::args().skip(1).find_map(|arg| {
+        if arg == "--dry-run" {
+            Some(DryRunConfig::init())
+        } else {
+            arg.strip_prefix("--dry-run=").map(|output| DryRunConfig {
+                output: output.to_string(),
+            })
+        }
+    })
+}
 
-    let runtime = Runtime::new().expect("Failed to create runtime");
+/// Returns the export config if `--export` or `--export=<dir>` was passed.
+fn export_config() -> Option<ExportConfig> {
+    env::args().skip(1).find_map(|arg| {
+        if arg == "--export" {
+            Some(ExportConfig::init().expect("Failed to create config"))
+        } else {
+            arg.strip_prefix("--export=").map(|dir| ExportConfig {
+                dir: dir.to_string(),
+                ..ExportConfig::init().expect("Failed to create config")
+            })
+        }
+    })
+}
+
+/// Returns the normalized output config if `--normalized` or
+/// `--normalized=<dir>` was passed.
+fn normalized_config() -> Option<NormalizedConfig> {
+    env::args().skip(1).find_map(|arg| {
+        if arg == "--normalized" {
+            Some(NormalizedConfig::init().expect("Failed to create config"))
+        } else {
+            arg.strip_prefix("--normalized=")
+                .map(|dir| NormalizedConfig {
+                    dir: dir.to_string(),
+                    ..NormalizedConfig::init().expect("Failed to create config")
+                })
+        }
+    })
+}
+
+/// Returns the Parquet config if `--parquet` or `--parquet=<dir>` was passed.
+#[cfg(feature = "parquet")]
+fn parquet_config() -> Option<ParquetConfig> {
+    env::args().skip(1).find_map(|arg| {
+        if arg == "--parquet" {
+            Some(ParquetConfig::init().expect("Failed to create config"))
+        } else {
+            arg.strip_prefix("--parquet=").map(|dir| ParquetConfig {
+                dir: dir.to_string(),
+                ..ParquetConfig::init().expect("Failed to create config")
+            })
+        }
+    })
+}
+
+/// Flags of the handlers that replace `InfluxDB`, of which only one can be
+/// passed.
+const REPLACING_FLAGS: &[&str] = &["--dry-run", "--export", "--questdb", "--sqlite"];
+
+/// Fails if more than one of [`REPLACING_FLAGS`] was passed.
+fn check_replacing_flags() -> Result<(), String> {
+    let passed: Vec<&str> = REPLACING_FLAGS
+        .iter()
+        .copied()
+        .filter(|flag| {
+            env::args().skip(1).any(|arg| {
+                arg == *flag
+                    || arg
+                        .strip_prefix(flag)
+                        .is_some_and(|rest| rest.starts_with('='))
+            })
+        })
+        .collect();
+    if passed.len() > 1 {
+        return Err(format!(
+            "Expected at most one of {}, found {}",
+            REPLACING_FLAGS.join(", "),
+            passed.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the dry-run handler if `--dry-run` or `--dry-run=<path>` was passed.
+fn dry_run_handler() -> Option<Box<dyn Handler>> {
+    let config = dry_run_config()?;
+    let influx = InfluxConfig::init().expect("Failed to create config");
+    Some(Box::new(
+        DryRunHandler::new(&config, influx.schema, influx.precision)
+            .expect("Failed to create dry-run handler"),
+    ))
+}
+
+/// Returns the export handler if `--export` or `--export=<dir>` was passed.
+fn export_handler() -> Option<Box<dyn Handler>> {
+    let config = export_config()?;
+    let influx = InfluxConfig::init().expect("Failed to create config");
+    Some(Box::new(
+        ExportHandler::new(config, influx.schema, influx.precision)
+            .expect("Failed to create export handler"),
+    ))
+}
+
+/// Passes the records to the normalized output as well if `--normalized` or
+/// `--normalized=<dir>` was passed, after `handler` handled them.
+fn with_normalized(handler: Box<dyn Handler>) -> Box<dyn Handler> {
+    let Some(config) = normalized_config() else {
+        return handler;
+    };
+    let normalized = NormalizedHandler::new(config).expect("Failed to create normalized handler");
+    Box::new(FanOut::new(vec![handler, Box::new(normalized)]))
+}
+
+/// Broadcasts the records to WebSocket subscribers as well if `--websocket`
+/// was passed, after `handler` handled them.
+#[cfg(feature = "websocket")]
+fn with_websocket(handler: Box<dyn Handler>) -> Box<dyn Handler> {
+    if !env::args().skip(1).any(|arg| arg == "--websocket") {
+        return handler;
+    }
+    let config = WebSocketConfig::init().expect("Failed to create config");
+    let websocket = WebSocketHandler::new(&config).expect("Failed to start WebSocket server");
+    Box::new(FanOut::new(vec![handler, Box::new(websocket)]))
+}
+
+/// Streams the records to gRPC subscribers as well if `--grpc` was passed,
+/// after `handler` handled them.
+#[cfg(feature = "grpc")]
+fn with_grpc(handler: Box<dyn Handler>) -> Box<dyn Handler> {
+    if !env::args().skip(1).any(|arg| arg == "--grpc") {
+        return handler;
+    }
+    let config = GrpcConfig::init().expect("Failed to create config");
+    let grpc = GrpcHandler::new(&config).expect("Failed to start gRPC server");
+    Box::new(FanOut::new(vec![handler, Box::new(grpc)]))
+}
+
+/// Publishes the records to an MQTT broker as well if `--mqtt` was passed,
+/// after `handler` handled them.
+#[cfg(feature = "mqtt")]
+fn with_mqtt(handler: Box<dyn Handler>) -> Box<dyn Handler> {
+    if !env::args().skip(1).any(|arg| arg == "--mqtt") {
+        return handler;
+    }
+    let config = MqttConfig::init().expect("Failed to create config");
+    let mqtt = MqttHandler::new(config).expect("Failed to create MQTT handler");
+    Box::new(FanOut::new(vec![handler, Box::new(mqtt)]))
+}
+
+/// POSTs the records to the webhook as well if `--webhook` was passed,
+/// after `handler` handled them.
+#[cfg(feature = "webhook")]
+fn with_webhook(handler: Box<dyn Handler>) -> Box<dyn Handler> {
+    if !env::args().skip(1).any(|arg| arg == "--webhook") {
+        return handler;
+    }
+    let config = WebhookConfig::init().expect("Failed to create config");
+    let webhook = WebhookHandler::new(config).expect("Failed to create webhook handler");
+    Box::new(FanOut::new(vec![handler, Box::new(webhook)]))
+}
 
-    let client = runtime.block_on(async {
+/// Publishes the records to Kafka as well if `--kafka` was passed, after
+/// `handler` handled them.
+#[cfg(feature = "kafka")]
+fn with_kafka(handler: Box<dyn Handler>) -> Box<dyn Handler> {
+    if !env::args().skip(1).any(|arg| arg == "--kafka") {
+        return handler;
+    }
+    let config = KafkaConfig::init().expect("Failed to create config");
+    let kafka = KafkaHandler::new(config).expect("Failed to create Kafka handler");
+    Box::new(FanOut::new(vec![handler, Box::new(kafka)]))
+}
+
+/// Archives the records as Parquet files as well if `--parquet` or
+/// `--parquet=<dir>` was passed, after `handler` handled them.
+#[cfg(feature = "parquet")]
+fn with_parquet(handler: Box<dyn Handler>) -> Box<dyn Handler> {
+    let Some(config) = parquet_config() else {
+        return handler;
+    };
+    let parquet = ParquetHandler::new(config).expect("Failed to create Parquet handler");
+    Box::new(FanOut::new(vec![handler, Box::new(parquet)]))
+}
+
+/// Writes the records to PostgreSQL as well if `--postgres` was passed,
+/// after `handler` handled them.
+#[cfg(feature = "postgres")]
+fn with_postgres(handler: Box<dyn Handler>) -> Box<dyn Handler> {
+    if !env::args().skip(1).any(|arg| arg == "--postgres") {
+        return handler;
+    }
+    let config = PostgresConfig::init().expect("Failed to create config");
+    let postgres = PostgresHandler::new(config).expect("Failed to create PostgreSQL handler");
+    Box::new(FanOut::new(vec![handler, Box::new(postgres)]))
+}
+
+/// Returns the QuestDB handler if `--questdb` was passed.
+fn questdb_handler() -> Option<Box<dyn Handler>> {
+    if !env::args().skip(1).any(|arg| arg == "--questdb") {
+        return None;
+    }
+    let config = QuestDbConfig::init().expect("Failed to create config");
+    let influx = InfluxConfig::init().expect("Failed to create config");
+    Some(Box::new(QuestDbHandler::new(config, influx.schema)))
+}
+
+/// Returns the SQLite handler if `--sqlite` or `--sqlite=<path>` was passed.
+#[cfg(feature = "sqlite")]
+fn sqlite_handler() -> Option<Box<dyn Handler>> {
+    let config = env::args().skip(1).find_map(|arg| {
+        if arg == "--sqlite" {
+            Some(SqliteConfig::init())
+        } else {
+            arg.strip_prefix("--sqlite=").map(|path| SqliteConfig {
+                path: path.to_string(),
+            })
+        }
+    })?;
+    Some(Box::new(
+        SqliteHandler::new(&config).expect("Failed to create SQLite handler"),
+    ))
+}
+
+/// Connects to every configured `InfluxDB` target, filling in the resume
+/// watermarks and, with a single target and the health check enabled, the
+/// connection health the reader pauses on.
+fn influx_handler(
+    runtime: &Runtime,
+    watermarks: &mut Watermarks,
+    health: &mut Option<Arc<Health>>,
+) -> Box<dyn Handler> {
+    let config = InfluxConfig::init().expect("Failed to create config");
+    let replicate = !config.targets.0.is_empty();
+    let clients: Vec<(String, Arc<InfluxClient>)> = runtime.block_on(async {
+        let mut clients = Vec::new();
+        for (name, config) in config.all_targets() {
+            let client = InfluxClient::new(config)
+                .await
+                .map_err(|e| format!("{name}: {e}"))
+                .expect("Failed to create client");
+            clients.push((name, Arc::new(client)));
+        }
+        if config.resume {
+            // Lines are skipped for every target, so only those stored in
+            // all of them are.
+            let mut stored: Option<Watermarks> = None;
+            for (name, client) in &clients {
+                let target = client
+                    .watermarks()
+                    .await
+                    .map_err(|e| format!("{name}: {e}"))
+                    .expect("Failed to query stored timestamps");
+                stored = Some(match stored {
+                    Some(stored) => stored.min(&target),
+                    None => target,
+                });
+            }
+            *watermarks = stored.unwrap_or_default();
+        }
+        clients
+    });
+    for (_, client) in &clients {
+        runtime.spawn(monitor(Arc::clone(client)));
+    }
+
+    if replicate {
+        Box::new(
+            Replicator::start(clients, &config.batch, runtime.handle())
+                .expect("Failed to start replicator"),
+        )
+    } else {
+        let (_, client) = clients.into_iter().next().expect("No InfluxDB target");
+        *health = client.monitored_health();
+        Box::new(InfluxHandler::shared(client))
+    }
+}
+
+/// Runs `reconcile [--backfill] [<path>]`, comparing the text file with the
+/// candles stored in `InfluxDB`.
+fn run_reconcile(runtime: &Runtime, args: &[String]) {
+    let backfill = args.iter().any(|arg| arg == "--backfill");
+    let path = args.iter().find(|arg| !arg.starts_with("--")).map_or_else(
+        || TextConfig::init().expect("Failed to create config").path,
+        Clone::clone,
+    );
+
+    let report = runtime.block_on(async {
         let config = InfluxConfig::init().expect("Failed to create config");
-        InfluxClient::new(config)
+        let client = InfluxClient::new(config)
+            .await
+            .expect("Failed to create client");
+        reconcile(&client, &path, backfill)
             .await
-            .expect("Failed to create client")
+            .expect("Failed to reconcile")
     });
-    let adapter = InfluxHandler::new(client);
+
+    print!("{report}");
+    if backfill {
+        println!("Backfilled {} candles", report.missing().len());
+    }
+}
+
+fn main() {
+    // Leaves stdout to the dry-run output when it is written there.
+    let quiet = dry_run_config().is_some_and(|config| config.is_stdout());
+    let path: &'static str = env!("INFLUXDB_URL");
+    if !quiet {
+        println!("the $PATH variable at the time of compiling was: {path}");
+    }
+
+    let runtime = Runtime::new().expect("Failed to create runtime");
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "reconcile") {
+        run_reconcile(&runtime, &args[1..]);
+        return;
+    }
+
+    check_replacing_flags().expect("Conflicting flags");
+    let mut watermarks = Watermarks::new();
+    let mut health = None;
+    let handler = dry_run_handler()
+        .or_else(export_handler)
+        .or_else(questdb_handler);
+    #[cfg(feature = "sqlite")]
+    let handler = handler.or_else(sqlite_handler);
+    let handler = handler.unwrap_or_else(|| influx_handler(&runtime, &mut watermarks, &mut health));
+    #[cfg(feature = "kafka")]
+    let handler = with_kafka(handler);
+    #[cfg(feature = "postgres")]
+    let handler = with_postgres(handler);
+    #[cfg(feature = "parquet")]
+    let handler = with_parquet(handler);
+    let handler = with_normalized(handler);
+    #[cfg(feature = "websocket")]
+    let handler = with_websocket(handler);
+    #[cfg(feature = "grpc")]
+    let handler = with_grpc(handler);
+    #[cfg(feature = "mqtt")]
+    let handler = with_mqtt(handler);
+    #[cfg(feature = "webhook")]
+    let handler = with_webhook(handler);
 
     let config = TextConfig::init().expect("Failed to create config");
-    let reader = TextReader::new(config, Box::new(adapter)).expect("Failed to create reader");
+    let reader = TextReader::new(config, handler)
+        .expect("Failed to create reader")
+        .with_watermarks(watermarks);
+    let reader = match health {
+        Some(health) => reader.with_health(health),
+        None => reader,
+    };
+    let reader = if quiet { reader.quiet() } else { reader };
 
     let one_day = Duration::from_secs(24 * 60 * 60);
     reader
@@ -1,9 +1,31 @@
-#[derive(Debug, PartialEq, Clone)]
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Candle {
     pub event: String,
+    /// Nanoseconds since the Unix epoch.
     pub timestamp: u128,
     pub open: f64,
     pub high: f64,
     pub low: f64,
     pub close: f64,
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// A candle of `event` at `timestamp`, shared by the tests of the
+    /// handlers.
+    #[must_use]
+    pub fn candle(event: &str, timestamp: u128, close: f64) -> Candle {
+        Candle {
+            event: event.to_string(),
+            timestamp,
+            open: 100.0,
+            high: 200.0,
+            low: 50.0,
+            close,
+        }
+    }
+}
@@ -1,5 +1,8 @@
-#[derive(Debug, PartialEq, Eq, Clone)]
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
 pub struct Indicator {
+    /// Nanoseconds since the Unix epoch.
     pub timestamp: u128,
     pub event: String,
     pub property: String,
@@ -1,2 +1,4 @@
 pub mod candle;
 pub mod indicator;
+#[cfg(any(feature = "grpc", feature = "kafka"))]
+pub mod proto;
@@ -0,0 +1,70 @@
+//! Protobuf messages of candles and indicators, as declared in
+//! `proto/hts/feed.proto`. Shared by the Kafka payloads and the gRPC feed.
+
+use crate::model::{candle::Candle as CandleRecord, indicator::Indicator as IndicatorRecord};
+use prost::Message;
+
+use std::io;
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Candle {
+    #[prost(string, tag = "1")]
+    pub event: String,
+    /// Nanoseconds since the Unix epoch.
+    #[prost(int64, tag = "2")]
+    pub timestamp: i64,
+    #[prost(double, tag = "3")]
+    pub open: f64,
+    #[prost(double, tag = "4")]
+    pub high: f64,
+    #[prost(double, tag = "5")]
+    pub low: f64,
+    #[prost(double, tag = "6")]
+    pub close: f64,
+}
+
+#[derive(Clone, PartialEq, Eq, Message)]
+pub struct Indicator {
+    #[prost(string, tag = "1")]
+    pub event: String,
+    /// Nanoseconds since the Unix epoch.
+    #[prost(int64, tag = "2")]
+    pub timestamp: i64,
+    #[prost(string, tag = "3")]
+    pub property: String,
+    #[prost(int64, tag = "4")]
+    pub value: i64,
+}
+
+fn timestamp(timestamp: u128) -> Result<i64, io::Error> {
+    i64::try_from(timestamp)
+        .map_err(|_| io::Error::other(format!("Timestamp out of range: {timestamp}")))
+}
+
+impl TryFrom<CandleRecord> for Candle {
+    type Error = io::Error;
+
+    fn try_from(candle: CandleRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            timestamp: timestamp(candle.timestamp)?,
+            event: candle.event,
+            open: candle.open,
+            high: candle.high,
+            low: candle.low,
+            close: candle.close,
+        })
+    }
+}
+
+impl TryFrom<IndicatorRecord> for Indicator {
+    type Error = io::Error;
+
+    fn try_from(indicator: IndicatorRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            timestamp: timestamp(indicator.timestamp)?,
+            event: indicator.event,
+            property: indicator.property,
+            value: indicator.value,
+        })
+    }
+}
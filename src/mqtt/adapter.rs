@@ -0,0 +1,300 @@
+use crate::model::{candle::Candle, indicator::Indicator};
+use crate::mqtt::config::Config;
+use crate::mqtt::topic::{Template, Values};
+use crate::text::reader::Handler;
+use rumqttc::{Client, ClientError, Connection, Event, MqttOptions, Outgoing, QoS};
+
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Interval of the pings keeping the connection alive.
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+
+/// A [`Handler`] publishing every record as JSON to an MQTT broker, on the
+/// topic of the configured [`Template`].
+///
+/// Records are queued for a connection thread, which reconnects after the
+/// configured backoff while the broker is unreachable; once the queue is
+/// full, records are dropped until the broker is back, so an unreachable
+/// broker never holds up the other handlers. Queued records are sent before
+/// the handler is dropped if the broker is reachable.
+pub struct MqttHandler {
+    client: Client,
+    topic: Template,
+    qos: QoS,
+    retain: bool,
+    stopping: Arc<AtomicBool>,
+    connection: Option<JoinHandle<()>>,
+    /// Records dropped since the queue filled up.
+    dropped: AtomicU64,
+}
+
+impl MqttHandler {
+    pub fn new(config: Config) -> Result<Self, io::Error> {
+        let qos = rumqttc::qos(config.qos).map_err(io::Error::other)?;
+        let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+        options.set_keep_alive(KEEP_ALIVE);
+        if let Some(username) = &config.username {
+            options.set_credentials(username, config.password.clone().unwrap_or_default());
+        }
+        let (client, connection) = Client::new(options, config.capacity.max(1));
+
+        let stopping = Arc::new(AtomicBool::new(false));
+        let connection = {
+            let stopping = Arc::clone(&stopping);
+            let backoff = config.reconnect_backoff();
+            thread::Builder::new()
+                .name("mqtt".to_string())
+                .spawn(move || run(connection, &stopping, backoff))?
+        };
+
+        Ok(Self {
+            client,
+            topic: config.topic,
+            qos,
+            retain: config.retain,
+            stopping,
+            connection: Some(connection),
+            dropped: AtomicU64::new(0),
+        })
+    }
+
+    fn publish(&self, values: Values, payload: Vec<u8>) -> Result<(), io::Error> {
+        let topic = self.topic.render(values);
+        match self
+            .client
+            .try_publish(topic, self.qos, self.retain, payload)
+        {
+            Ok(()) => {
+                let dropped = self.dropped.swap(0, Ordering::Relaxed);
+                if dropped > 0 {
+                    println!("Dropped {dropped} records while the MQTT queue was full");
+                }
+                Ok(())
+            }
+            // The queue is full unless the connection thread stopped.
+            Err(ClientError::TryRequest(_))
+                if self
+                    .connection
+                    .as_ref()
+                    .is_some_and(|connection| !connection.is_finished()) =>
+            {
+                if self.dropped.fetch_add(1, Ordering::Relaxed) == 0 {
+                    println!("MQTT queue is full, dropping records until the broker is back");
+                }
+                Ok(())
+            }
+            Err(_) => Err(io::Error::other("MQTT connection stopped")),
+        }
+    }
+}
+
+/// Drives the connection until the handler disconnects, or fails to while
+/// the broker is unreachable.
+fn run(mut connection: Connection, stopping: &AtomicBool, backoff: Duration) {
+    for event in connection.iter() {
+        match event {
+            Ok(Event::Incoming(rumqttc::Packet::ConnAck(_))) => {
+                println!("Connected to MQTT broker");
+            }
+            Ok(Event::Outgoing(Outgoing::Disconnect)) => return,
+            Ok(_) => (),
+            Err(_) if stopping.load(Ordering::Relaxed) => return,
+            Err(e) => {
+                println!("MQTT connection failed, reconnecting: {e}");
+                thread::sleep(backoff);
+            }
+        }
+    }
+}
+
+impl Handler for MqttHandler {
+    fn handle_candle(&self, candle: Candle) -> Result<(), io::Error> {
+        let values = Values {
+            kind: "candle",
+            event: &candle.event,
+            property: "",
+        };
+        self.publish(values, serde_json::to_vec(&candle)?)
+    }
+
+    fn handle_indicator(&self, indicator: Indicator) -> Result<(), io::Error> {
+        let values = Values {
+            kind: "indicator",
+            event: &indicator.event,
+            property: &indicator.property,
+        };
+        self.publish(values, serde_json::to_vec(&indicator)?)
+    }
+}
+
+impl Drop for MqttHandler {
+    /// Sends the queued records and disconnects before returning. With the
+    /// queue full, returns at once instead, leaving the connection thread to
+    /// stop once the client is gone.
+    fn drop(&mut self) {
+        self.stopping.store(true, Ordering::Relaxed);
+        if self.client.try_disconnect().is_err() {
+            println!("MQTT queue is full, dropping the queued records");
+            return;
+        }
+        if let Some(connection) = self.connection.take() {
+            if connection.join().is_err() {
+                println!("MQTT connection panicked");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::candle::tests::candle;
+    use bytes::BytesMut;
+    use rumqttc::{ConnAck, ConnectReturnCode, Packet, PingResp, PubAck, Publish};
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// Accepts one client and returns what it published, acknowledging it
+    /// like a broker would.
+    fn broker(listener: &TcpListener) -> Vec<Publish> {
+        let (mut stream, _) = listener.accept().expect("Failed to accept");
+        let mut input = BytesMut::new();
+        let mut published = Vec::new();
+        loop {
+            let packet = Packet::read(&mut input, 64 * 1024);
+            if let Err(rumqttc::Error::InsufficientBytes(_)) = packet {
+                let mut chunk = [0; 1024];
+                match stream.read(&mut chunk).expect("Failed to read") {
+                    0 => return published,
+                    read => input.extend_from_slice(&chunk[..read]),
+                }
+                continue;
+            }
+            let mut output = BytesMut::new();
+            match packet.expect("Invalid packet") {
+                Packet::Connect(_) => ConnAck::new(ConnectReturnCode::Success, false)
+                    .write(&mut output)
+                    .map(drop),
+                Packet::Publish(publish) => {
+                    let ack = PubAck::new(publish.pkid).write(&mut output).map(drop);
+                    published.push(publish);
+                    ack
+                }
+                Packet::PingReq => PingResp.write(&mut output).map(drop),
+                Packet::Disconnect => return published,
+                _ => Ok(()),
+            }
+            .expect("Failed to encode reply");
+            stream.write_all(&output).expect("Failed to write");
+        }
+    }
+
+    #[test]
+    fn test_publishes_to_rendered_topics() {
+        // Arrange
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind");
+        let port = listener.local_addr().expect("No address").port();
+        let broker = thread::spawn(move || broker(&listener));
+        let handler = MqttHandler::new(Config {
+            host: "127.0.0.1".to_string(),
+            port,
+            client_id: "test".to_string(),
+            username: None,
+            password: None,
+            topic: "hts/{kind}/{event}/{property}"
+                .parse()
+                .expect("Failed to parse template"),
+            qos: 1,
+            retain: true,
+            capacity: 16,
+            reconnect_backoff_ms: 50,
+        })
+        .expect("Failed to create handler");
+
+        // Act
+        handler
+            .handle_candle(candle("BTCUSDT", 1_714_450_980_000_000_000, 150.0))
+            .expect("Failed to handle candle");
+        handler
+            .handle_indicator(Indicator {
+                timestamp: 1_714_450_980_000_000_000,
+                event: "BTCUSDT".to_string(),
+                property: "rsi".to_string(),
+                value: 70,
+            })
+            .expect("Failed to handle indicator");
+        drop(handler);
+        let published = broker.join().expect("Broker panicked");
+
+        // Assert
+        let published: Vec<(&str, QoS, bool, &[u8])> = published
+            .iter()
+            .map(|publish| {
+                (
+                    publish.topic.as_str(),
+                    publish.qos,
+                    publish.retain,
+                    &publish.payload[..],
+                )
+            })
+            .collect();
+        assert_eq!(
+            published,
+            vec![
+                (
+                    "hts/candle/BTCUSDT",
+                    QoS::AtLeastOnce,
+                    true,
+                    &br#"{"event":"BTCUSDT","timestamp":1714450980000000000,"open":100.0,"high":200.0,"low":50.0,"close":150.0}"#[..],
+                ),
+                (
+                    "hts/indicator/BTCUSDT/rsi",
+                    QoS::AtLeastOnce,
+                    true,
+                    &br#"{"timestamp":1714450980000000000,"event":"BTCUSDT","property":"rsi","value":70}"#[..],
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_drops_records_while_broker_is_unreachable() {
+        // Arrange
+        let port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("Failed to find a free port")
+            .port();
+        let handler = MqttHandler::new(Config {
+            host: "127.0.0.1".to_string(),
+            port,
+            client_id: "test".to_string(),
+            username: None,
+            password: None,
+            topic: "hts/{kind}/{event}"
+                .parse()
+                .expect("Failed to parse template"),
+            qos: 1,
+            retain: false,
+            capacity: 1,
+            reconnect_backoff_ms: 60_000,
+        })
+        .expect("Failed to create handler");
+        let start = std::time::Instant::now();
+
+        // Act
+        let results: Vec<Result<(), io::Error>> = (0..10)
+            .map(|timestamp| handler.handle_candle(candle("BTCUSDT", timestamp, 150.0)))
+            .collect();
+        let dropped = handler.dropped.load(Ordering::Relaxed);
+        drop(handler);
+
+        // Assert
+        assert!(results.iter().all(Result::is_ok));
+        assert!(dropped > 0);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,75 @@
+use crate::config::parse_or;
+use crate::mqtt::topic::Template;
+use envconfig::Envconfig;
+use serde::Deserialize;
+
+use std::env;
+use std::error::Error;
+use std::time::Duration;
+
+/// Broker host used when `MQTT_HOST` is not set.
+pub const DEFAULT_HOST: &str = "localhost";
+/// Topic template used when `MQTT_TOPIC` is not set.
+pub const DEFAULT_TOPIC: &str = "hts/{kind}/{event}/{property}";
+
+#[derive(Debug, Clone, Deserialize, Envconfig)]
+pub struct Config {
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    /// Credentials, sent only if the username is set.
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub topic: Template,
+    /// 0 (at most once), 1 (at least once) or 2 (exactly once).
+    pub qos: u8,
+    /// Whether the broker keeps the last record of every topic for new
+    /// subscribers.
+    pub retain: bool,
+    /// Records queued for the client at most, after which records are
+    /// dropped until the broker is back.
+    pub capacity: usize,
+    /// Time to wait before reconnecting after the connection failed.
+    pub reconnect_backoff_ms: u64,
+}
+
+impl Config {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            host: env::var("MQTT_HOST").unwrap_or_else(|_| DEFAULT_HOST.to_string()),
+            port: parse_or(env::var("MQTT_PORT").ok(), 1883)?,
+            client_id: env::var("MQTT_CLIENT_ID").unwrap_or_else(|_| "hts-connector".to_string()),
+            username: env::var("MQTT_USERNAME").ok(),
+            password: env::var("MQTT_PASSWORD").ok(),
+            topic: env::var("MQTT_TOPIC")
+                .unwrap_or_else(|_| DEFAULT_TOPIC.to_string())
+                .parse()?,
+            qos: parse_or(env::var("MQTT_QOS").ok(), 1)?,
+            retain: parse_or(env::var("MQTT_RETAIN").ok(), true)?,
+            capacity: parse_or(env::var("MQTT_CAPACITY").ok(), 1024)?,
+            reconnect_backoff_ms: parse_or(env::var("MQTT_RECONNECT_BACKOFF_MS").ok(), 1000)?,
+        })
+    }
+
+    pub fn init() -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            host: option_env!("MQTT_HOST").unwrap_or(DEFAULT_HOST).to_string(),
+            port: parse_or(option_env!("MQTT_PORT"), 1883)?,
+            client_id: option_env!("MQTT_CLIENT_ID")
+                .unwrap_or("hts-connector")
+                .to_string(),
+            username: option_env!("MQTT_USERNAME").map(str::to_string),
+            password: option_env!("MQTT_PASSWORD").map(str::to_string),
+            topic: option_env!("MQTT_TOPIC").unwrap_or(DEFAULT_TOPIC).parse()?,
+            qos: parse_or(option_env!("MQTT_QOS"), 1)?,
+            retain: parse_or(option_env!("MQTT_RETAIN"), true)?,
+            capacity: parse_or(option_env!("MQTT_CAPACITY"), 1024)?,
+            reconnect_backoff_ms: parse_or(option_env!("MQTT_RECONNECT_BACKOFF_MS"), 1000)?,
+        })
+    }
+
+    #[must_use]
+    pub const fn reconnect_backoff(&self) -> Duration {
+        Duration::from_millis(self.reconnect_backoff_ms)
+    }
+}
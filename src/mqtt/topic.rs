@@ -0,0 +1,177 @@
+//! Topics records are published to.
+//!
+//! A template is a topic whose levels may contain the placeholders
+//! `{kind}` (`candle` or `indicator`), `{event}` and `{property}`. A level
+//! left empty by its placeholders is dropped, so the default
+//! `hts/{kind}/{event}/{property}` publishes candles to `hts/candle/<event>`
+//! and indicators to `hts/indicator/<event>/<property>`.
+//!
+//! The MQTT separator and wildcards (`/`, `+` and `#`) in the values are
+//! replaced by `_`, so every record stays in a single level.
+
+use serde::Deserialize;
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Kind,
+    Event,
+    Property,
+}
+
+/// The values substituted for the placeholders of a template.
+#[derive(Debug, Clone, Copy)]
+pub struct Values<'a> {
+    pub kind: &'a str,
+    pub event: &'a str,
+    pub property: &'a str,
+}
+
+fn escape(value: &str) -> String {
+    value.replace(['/', '+', '#', '\0'], "_")
+}
+
+/// A topic template, parsed from its string form.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Template {
+    levels: Vec<Vec<Part>>,
+}
+
+impl Template {
+    fn parse_level(level: &str) -> Result<Vec<Part>, Box<dyn Error>> {
+        let mut parts = Vec::new();
+        let mut rest = level;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .ok_or_else(|| format!("Unclosed placeholder in topic level {level}"))?;
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_string()));
+            }
+            parts.push(match &rest[start + 1..end] {
+                "kind" => Part::Kind,
+                "event" => Part::Event,
+                "property" => Part::Property,
+                name => return Err(format!("Unknown topic placeholder {{{name}}}").into()),
+            });
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+        Ok(parts)
+    }
+
+    /// The topic of a record.
+    #[must_use]
+    pub fn render(&self, values: Values) -> String {
+        self.levels
+            .iter()
+            .filter_map(|parts| {
+                let level: String = parts
+                    .iter()
+                    .map(|part| match part {
+                        Part::Literal(literal) => literal.clone(),
+                        Part::Kind => escape(values.kind),
+                        Part::Event => escape(values.event),
+                        Part::Property => escape(values.property),
+                    })
+                    .collect();
+                let placeholder = parts.iter().any(|part| !matches!(part, Part::Literal(_)));
+                (!level.is_empty() || !placeholder).then_some(level)
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+impl FromStr for Template {
+    type Err = Box<dyn Error>;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        if input.is_empty() {
+            return Err("Topic template is empty".into());
+        }
+        if input.contains(['+', '#']) {
+            return Err(format!("Topic template {input} contains a wildcard").into());
+        }
+        Ok(Self {
+            levels: input
+                .split('/')
+                .map(Self::parse_level)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl TryFrom<String> for Template {
+    type Error = Box<dyn Error>;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        input.parse()
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, parts) in self.levels.iter().enumerate() {
+            if index > 0 {
+                write!(f, "/")?;
+            }
+            for part in parts {
+                match part {
+                    Part::Literal(literal) => write!(f, "{literal}")?,
+                    Part::Kind => write!(f, "{{kind}}")?,
+                    Part::Event => write!(f, "{{event}}")?,
+                    Part::Property => write!(f, "{{property}}")?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_render() {
+        // Arrange
+        let template: Template = "hts/{kind}/{event}/{property}"
+            .parse()
+            .expect("Failed to parse template");
+
+        // Act
+        let candle = template.render(Values {
+            kind: "candle",
+            event: "테스트",
+            property: "",
+        });
+        let indicator = template.render(Values {
+            kind: "indicator",
+            event: "BTC/USDT",
+            property: "rsi",
+        });
+
+        // Assert
+        assert_eq!(candle, "hts/candle/테스트");
+        assert_eq!(indicator, "hts/indicator/BTC_USDT/rsi");
+        assert_eq!(template.to_string(), "hts/{kind}/{event}/{property}");
+    }
+
+    #[test]
+    fn test_template_rejects_invalid() {
+        // Act & Assert
+        assert!("hts/#".parse::<Template>().is_err());
+        assert!("hts/{kind".parse::<Template>().is_err());
+        assert!("hts/{symbol}".parse::<Template>().is_err());
+        assert!("".parse::<Template>().is_err());
+    }
+}
@@ -0,0 +1,249 @@
+use crate::model::{candle::Candle, indicator::Indicator};
+use crate::normalized::config::Config;
+use crate::normalized::row::{
+    schema, CandleRow, Format, IndicatorRow, Type, CANDLE_COLUMNS, INDICATOR_COLUMNS,
+};
+use crate::text::reader::Handler;
+use chrono::{DateTime, NaiveDate};
+use chrono_tz::Asia::Seoul;
+use serde::Serialize;
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+enum Output {
+    Csv(Box<csv::Writer<File>>),
+    JsonLines(BufWriter<File>),
+}
+
+impl Output {
+    /// Writes `row` as one line; the CSV header is written with the first.
+    fn write(&mut self, row: &impl Serialize) -> Result<(), io::Error> {
+        match self {
+            Self::Csv(writer) => {
+                writer.serialize(row).map_err(io::Error::other)?;
+                writer.flush()
+            }
+            Self::JsonLines(writer) => {
+                serde_json::to_writer(&mut *writer, row)?;
+                writer.write_all(b"\n")?;
+                writer.flush()
+            }
+        }
+    }
+}
+
+struct Current {
+    date: NaiveDate,
+    output: Output,
+}
+
+/// The file of one kind of record, started anew every trading day.
+struct Kind {
+    name: &'static str,
+    current: Mutex<Option<Current>>,
+}
+
+impl Kind {
+    const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            current: Mutex::new(None),
+        }
+    }
+}
+
+/// A [`Handler`] writing a normalized copy of the feed: UTF-8 CSV or JSON
+/// Lines files with UTC timestamps and fixed columns, one set per kind of
+/// record, next to a JSON Schema of their rows.
+///
+/// The raw export is decoded from the encoding set in `TEXT_FILE_ENCODING`,
+/// such as `cp949`. A new file is started for every Seoul trading day of
+/// the records. Existing files are never appended to, so a restart starts
+/// the next file of the day.
+pub struct NormalizedHandler {
+    config: Config,
+    candles: Kind,
+    indicators: Kind,
+}
+
+impl NormalizedHandler {
+    pub fn new(config: Config) -> Result<Self, io::Error> {
+        fs::create_dir_all(&config.dir)?;
+        let handler = Self {
+            config,
+            candles: Kind::new("candle"),
+            indicators: Kind::new("indicator"),
+        };
+        handler.write_schema(&handler.candles, CANDLE_COLUMNS)?;
+        handler.write_schema(&handler.indicators, INDICATOR_COLUMNS)?;
+        Ok(handler)
+    }
+
+    fn write_schema(&self, kind: &Kind, columns: &[(&str, Type)]) -> Result<(), io::Error> {
+        let path = PathBuf::from(&self.config.dir)
+            .join(format!("{}-{}.schema.json", self.config.prefix, kind.name));
+        let schema = serde_json::to_string_pretty(&schema(kind.name, columns))?;
+        fs::write(path, format!("{schema}\n"))
+    }
+
+    fn date_of(timestamp: u128) -> Result<NaiveDate, io::Error> {
+        let nanos = i64::try_from(timestamp)
+            .map_err(|_| io::Error::other(format!("Timestamp out of range: {timestamp}")))?;
+        Ok(DateTime::from_timestamp_nanos(nanos)
+            .with_timezone(&Seoul)
+            .date_naive())
+    }
+
+    fn path(&self, kind: &Kind, date: NaiveDate, sequence: u32) -> PathBuf {
+        PathBuf::from(&self.config.dir).join(format!(
+            "{}-{}-{date}-{sequence:03}.{}",
+            self.config.prefix,
+            kind.name,
+            self.config.format.extension()
+        ))
+    }
+
+    fn open(&self, kind: &Kind, date: NaiveDate) -> Result<Current, io::Error> {
+        let path = (0..)
+            .map(|sequence| self.path(kind, date, sequence))
+            .find(|path| !path.exists())
+            .ok_or_else(|| io::Error::other("No free file name"))?;
+        let file = File::create(path)?;
+
+        let output = match self.config.format {
+            Format::Csv => Output::Csv(Box::new(csv::Writer::from_writer(file))),
+            Format::JsonLines => Output::JsonLines(BufWriter::new(file)),
+        };
+
+        Ok(Current { date, output })
+    }
+
+    fn write_row(
+        &self,
+        kind: &Kind,
+        row: &impl Serialize,
+        timestamp: u128,
+    ) -> Result<(), io::Error> {
+        let date = Self::date_of(timestamp)?;
+
+        let mut guard = kind
+            .current
+            .lock()
+            .map_err(|e| io::Error::other(format!("Failed to lock {} file: {e}", kind.name)))?;
+
+        if guard.as_ref().is_none_or(|current| current.date != date) {
+            *guard = Some(self.open(kind, date)?);
+        }
+
+        let Some(current) = guard.as_mut() else {
+            return Err(io::Error::other("No normalized file open"));
+        };
+        current.output.write(row)?;
+        drop(guard);
+
+        Ok(())
+    }
+}
+
+impl Handler for NormalizedHandler {
+    fn handle_candle(&self, candle: Candle) -> Result<(), io::Error> {
+        self.write_row(&self.candles, &CandleRow::new(&candle)?, candle.timestamp)
+    }
+
+    fn handle_indicator(&self, indicator: Indicator) -> Result<(), io::Error> {
+        self.write_row(
+            &self.indicators,
+            &IndicatorRow::new(&indicator)?,
+            indicator.timestamp,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::candle::tests::candle;
+    use std::env;
+
+    fn config(name: &str, format: Format) -> Config {
+        let dir = env::temp_dir().join(format!("hts-connector-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Config {
+            dir: dir.to_string_lossy().to_string(),
+            prefix: "hts".to_string(),
+            format,
+        }
+    }
+
+    fn read(dir: &str, name: &str) -> String {
+        fs::read_to_string(PathBuf::from(dir).join(name)).expect("Failed to read file")
+    }
+
+    #[test]
+    fn test_normalized_csv_rotates_by_day() {
+        // Arrange
+        let config = config("normalized-csv", Format::Csv);
+        let dir = config.dir.clone();
+        let handler = NormalizedHandler::new(config).expect("Failed to create handler");
+
+        // Act
+        // 2024-04-30 13:21 and 13:22 in Seoul, then 2024-05-01 09:00.
+        handler
+            .handle_candle(candle("테스트", 1_714_450_860_000_000_000, 368.75))
+            .expect("Failed to handle candle");
+        handler
+            .handle_candle(candle("테스트", 1_714_450_920_000_000_000, 368.75))
+            .expect("Failed to handle candle");
+        handler
+            .handle_candle(candle("테스트", 1_714_521_600_000_000_000, 368.75))
+            .expect("Failed to handle candle");
+        drop(handler);
+
+        // Assert
+        assert_eq!(
+            read(&dir, "hts-candle-2024-04-30-000.csv"),
+            "time,event,open,high,low,close\n\
+             2024-04-30T04:21:00.000000000Z,테스트,100.0,200.0,50.0,368.75\n\
+             2024-04-30T04:22:00.000000000Z,테스트,100.0,200.0,50.0,368.75\n"
+        );
+        assert_eq!(
+            read(&dir, "hts-candle-2024-05-01-000.csv"),
+            "time,event,open,high,low,close\n\
+             2024-05-01T00:00:00.000000000Z,테스트,100.0,200.0,50.0,368.75\n"
+        );
+        assert!(read(&dir, "hts-candle.schema.json").contains("\"date-time\""));
+
+        fs::remove_dir_all(&dir).expect("Failed to remove directory");
+    }
+
+    #[test]
+    fn test_normalized_json_lines() {
+        // Arrange
+        let config = config("normalized-jsonl", Format::JsonLines);
+        let dir = config.dir.clone();
+        let handler = NormalizedHandler::new(config).expect("Failed to create handler");
+
+        // Act
+        handler
+            .handle_indicator(Indicator {
+                timestamp: 1_714_450_860_000_000_000,
+                event: "테스트".to_string(),
+                property: "rsi".to_string(),
+                value: 70,
+            })
+            .expect("Failed to handle indicator");
+        drop(handler);
+
+        // Assert
+        assert_eq!(
+            read(&dir, "hts-indicator-2024-04-30-000.jsonl"),
+            "{\"time\":\"2024-04-30T04:21:00.000000000Z\",\"event\":\"테스트\",\
+             \"property\":\"rsi\",\"value\":70}\n"
+        );
+
+        fs::remove_dir_all(&dir).expect("Failed to remove directory");
+    }
+}
@@ -0,0 +1,41 @@
+use crate::config::parse_or;
+use crate::normalized::row::Format;
+use envconfig::Envconfig;
+use serde::Deserialize;
+
+use std::env;
+use std::error::Error;
+
+/// Directory used when `NORMALIZED_DIR` is not set.
+pub const DEFAULT_DIR: &str = "normalized";
+
+#[derive(Debug, Deserialize, Envconfig)]
+pub struct Config {
+    /// Directory the files and their schemas are written to.
+    pub dir: String,
+    /// File names are `<prefix>-<kind>-<date>-<sequence>.<extension>`.
+    pub prefix: String,
+    pub format: Format,
+}
+
+impl Config {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            dir: env::var("NORMALIZED_DIR").unwrap_or_else(|_| DEFAULT_DIR.to_string()),
+            prefix: env::var("NORMALIZED_PREFIX").unwrap_or_else(|_| "hts".to_string()),
+            format: parse_or(env::var("NORMALIZED_FORMAT").ok(), Format::default())?,
+        })
+    }
+
+    pub fn init() -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            dir: option_env!("NORMALIZED_DIR")
+                .unwrap_or(DEFAULT_DIR)
+                .to_string(),
+            prefix: option_env!("NORMALIZED_PREFIX")
+                .unwrap_or("hts")
+                .to_string(),
+            format: parse_or(option_env!("NORMALIZED_FORMAT"), Format::default())?,
+        })
+    }
+}
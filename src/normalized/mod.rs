@@ -0,0 +1,3 @@
+pub mod adapter;
+pub mod config;
+pub mod row;
@@ -0,0 +1,153 @@
+//! The normalized rows: UTF-8, UTC timestamps and the same columns in every
+//! file of a kind.
+
+use crate::model::{candle::Candle, indicator::Indicator};
+use chrono::{DateTime, SecondsFormat};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+/// Encoding of the output files.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Deserialize)]
+pub enum Format {
+    /// Comma-separated values with a header line.
+    #[default]
+    Csv,
+    /// One JSON object per line.
+    JsonLines,
+}
+
+impl Format {
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::JsonLines => "jsonl",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = Box<dyn Error>;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "jsonl" | "json-lines" | "ndjson" => Ok(Self::JsonLines),
+            _ => Err(format!("Expected one of csv or jsonl, found {input}").into()),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+/// Column types, named as in JSON Schema.
+#[derive(Debug, Clone, Copy)]
+pub enum Type {
+    /// RFC 3339 in UTC with nanoseconds, such as `2024-04-30T04:21:00.000000000Z`.
+    Time,
+    String,
+    Number,
+    Integer,
+}
+
+pub const CANDLE_COLUMNS: &[(&str, Type)] = &[
+    ("time", Type::Time),
+    ("event", Type::String),
+    ("open", Type::Number),
+    ("high", Type::Number),
+    ("low", Type::Number),
+    ("close", Type::Number),
+];
+
+pub const INDICATOR_COLUMNS: &[(&str, Type)] = &[
+    ("time", Type::Time),
+    ("event", Type::String),
+    ("property", Type::String),
+    ("value", Type::Integer),
+];
+
+/// A JSON Schema describing the rows with `columns`, all of them required.
+#[must_use]
+pub fn schema(title: &str, columns: &[(&str, Type)]) -> Value {
+    let properties: Map<String, Value> = columns
+        .iter()
+        .map(|(name, kind)| {
+            let property = match kind {
+                Type::Time => json!({ "type": "string", "format": "date-time" }),
+                Type::String => json!({ "type": "string" }),
+                Type::Number => json!({ "type": "number" }),
+                Type::Integer => json!({ "type": "integer" }),
+            };
+            ((*name).to_string(), property)
+        })
+        .collect();
+    let required: Vec<&str> = columns.iter().map(|(name, _)| *name).collect();
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": title,
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+fn time(timestamp: u128) -> Result<String, io::Error> {
+    let nanos = i64::try_from(timestamp)
+        .map_err(|_| io::Error::other(format!("Timestamp out of range: {timestamp}")))?;
+    Ok(DateTime::from_timestamp_nanos(nanos).to_rfc3339_opts(SecondsFormat::Nanos, true))
+}
+
+/// A candle with the fields in the order of [`CANDLE_COLUMNS`].
+#[derive(Debug, Serialize)]
+pub struct CandleRow<'a> {
+    pub time: String,
+    pub event: &'a str,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+impl<'a> CandleRow<'a> {
+    pub fn new(candle: &'a Candle) -> Result<Self, io::Error> {
+        Ok(Self {
+            time: time(candle.timestamp)?,
+            event: &candle.event,
+            open: candle.open,
+            high: candle.high,
+            low: candle.low,
+            close: candle.close,
+        })
+    }
+}
+
+/// An indicator with the fields in the order of [`INDICATOR_COLUMNS`].
+#[derive(Debug, Serialize)]
+pub struct IndicatorRow<'a> {
+    pub time: String,
+    pub event: &'a str,
+    pub property: &'a str,
+    pub value: i64,
+}
+
+impl<'a> IndicatorRow<'a> {
+    pub fn new(indicator: &'a Indicator) -> Result<Self, io::Error> {
+        Ok(Self {
+            time: time(indicator.timestamp)?,
+            event: &indicator.event,
+            property: &indicator.property,
+            value: indicator.value,
+        })
+    }
+}
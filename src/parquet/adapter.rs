@@ -0,0 +1,475 @@
+use crate::model::{candle::Candle, indicator::Indicator};
+use crate::parquet::config::Config;
+use crate::parquet::record::Record;
+use crate::text::reader::Handler;
+use chrono::{DateTime, NaiveDate};
+use chrono_tz::Asia::Seoul;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Extension of a file still being written; it is renamed to `.parquet` once
+/// the footer is written, so readers never pick up a partial file.
+const IN_PROGRESS: &str = "inprogress";
+
+/// Escapes the characters that cannot appear in a directory name, the way
+/// Hive escapes partition values.
+fn escape(value: &str) -> String {
+    value.chars().fold(String::new(), |mut output, c| {
+        if c.is_control() || matches!(c, '/' | '\\' | ':' | '=' | '%' | '"' | '*' | '?') {
+            let _ = write!(output, "%{:02X}", u32::from(c));
+        } else {
+            output.push(c);
+        }
+        output
+    })
+}
+
+fn date_of(timestamp: u128) -> Result<NaiveDate, io::Error> {
+    let nanos = i64::try_from(timestamp)
+        .map_err(|_| io::Error::other(format!("Timestamp out of range: {timestamp}")))?;
+    Ok(DateTime::from_timestamp_nanos(nanos)
+        .with_timezone(&Seoul)
+        .date_naive())
+}
+
+/// An open file of one record kind, trading date and event.
+struct Partition<R> {
+    path: PathBuf,
+    writer: ArrowWriter<File>,
+    rows: Vec<R>,
+    opened: Instant,
+}
+
+impl<R: Record> Partition<R> {
+    fn open(config: &Config, date: NaiveDate, event: &str) -> Result<Self, io::Error> {
+        let dir = Path::new(&config.dir)
+            .join(R::KIND)
+            .join(format!("date={date}"))
+            .join(format!("event={}", escape(event)));
+        fs::create_dir_all(&dir)?;
+
+        let path = (0..)
+            .map(|sequence| dir.join(format!("part-{sequence:03}.parquet")))
+            .find(|path| !path.exists() && !path.with_extension(IN_PROGRESS).exists())
+            .ok_or_else(|| io::Error::other("No free file name"))?;
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let writer = ArrowWriter::try_new(
+            File::create(path.with_extension(IN_PROGRESS))?,
+            R::schema(),
+            Some(properties),
+        )
+        .map_err(io::Error::other)?;
+
+        Ok(Self {
+            path,
+            writer,
+            rows: Vec::new(),
+            opened: Instant::now(),
+        })
+    }
+
+    /// Writes the collected rows to the file as one row group.
+    fn write_batch(&mut self) -> Result<(), io::Error> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+        let batch = R::batch(&self.rows).map_err(io::Error::other)?;
+        self.writer.write(&batch).map_err(io::Error::other)?;
+        self.writer.flush().map_err(io::Error::other)?;
+        self.rows.clear();
+        Ok(())
+    }
+
+    /// Bytes written so far, including rows not flushed yet.
+    fn size(&self) -> usize {
+        self.writer.bytes_written() + self.writer.in_progress_size()
+    }
+
+    fn close(mut self) -> Result<(), io::Error> {
+        self.write_batch()?;
+        self.writer.close().map_err(io::Error::other)?;
+        fs::rename(self.path.with_extension(IN_PROGRESS), &self.path)
+    }
+}
+
+type Partitions<R> = BTreeMap<(NaiveDate, String), Partition<R>>;
+
+fn close_all<R: Record>(partitions: Partitions<R>) -> Result<(), io::Error> {
+    partitions.into_values().try_for_each(Partition::close)
+}
+
+/// Closes the partitions opened at least `max_age` ago.
+fn close_expired<R: Record>(
+    partitions: &mut Partitions<R>,
+    max_age: Duration,
+) -> Result<(), io::Error> {
+    let expired: Vec<(NaiveDate, String)> = partitions
+        .iter()
+        .filter(|(_, partition)| partition.opened.elapsed() >= max_age)
+        .map(|(key, _)| key.clone())
+        .collect();
+    expired
+        .iter()
+        .filter_map(|key| partitions.remove(key))
+        .try_for_each(Partition::close)
+}
+
+/// Closes the partitions of the days before `date`.
+fn close_before<R: Record>(
+    partitions: &mut Partitions<R>,
+    date: NaiveDate,
+) -> Result<(), io::Error> {
+    let current = partitions.split_off(&(date, String::new()));
+    close_all(mem::replace(partitions, current))
+}
+
+fn push<R: Record>(
+    config: &Config,
+    partitions: &mut Partitions<R>,
+    date: NaiveDate,
+    record: R,
+) -> Result<(), io::Error> {
+    let key = (date, record.event().to_string());
+    if !partitions.contains_key(&key) {
+        let partition = Partition::open(config, date, record.event())?;
+        partitions.insert(key.clone(), partition);
+    }
+    let Some(partition) = partitions.get_mut(&key) else {
+        return Err(io::Error::other("No Parquet file open"));
+    };
+
+    partition.rows.push(record);
+    if partition.rows.len() < config.batch_rows {
+        return Ok(());
+    }
+    partition.write_batch()?;
+    if config.max_bytes > 0 && partition.size() >= config.max_bytes {
+        if let Some(partition) = partitions.remove(&key) {
+            partition.close()?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct Archive {
+    candles: Partitions<Candle>,
+    indicators: Partitions<Indicator>,
+    /// Latest trading date seen; files of earlier days are closed.
+    date: Option<NaiveDate>,
+}
+
+impl Archive {
+    fn advance(&mut self, date: NaiveDate) -> Result<(), io::Error> {
+        if self.date.is_some_and(|latest| latest >= date) {
+            return Ok(());
+        }
+        self.date = Some(date);
+        close_before(&mut self.candles, date)?;
+        close_before(&mut self.indicators, date)
+    }
+
+    fn close_expired(&mut self, max_age: Option<Duration>) -> Result<(), io::Error> {
+        let Some(max_age) = max_age else {
+            return Ok(());
+        };
+        close_expired(&mut self.candles, max_age)?;
+        close_expired(&mut self.indicators, max_age)
+    }
+
+    fn close(&mut self) -> Result<(), io::Error> {
+        close_all(mem::take(&mut self.candles))?;
+        close_all(mem::take(&mut self.indicators))
+    }
+}
+
+/// Removes the files under `dir` a previous run did not finish.
+fn remove_unfinished(dir: &Path) -> Result<(), io::Error> {
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(next) = dirs.pop() {
+        for entry in fs::read_dir(next)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path
+                .extension()
+                .is_some_and(|extension| extension == IN_PROGRESS)
+            {
+                println!("Removing unfinished Parquet file {}", path.display());
+                fs::remove_file(path)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A [`Handler`] archiving candles and indicators as Parquet files
+/// partitioned by record kind, Seoul trading date and event.
+///
+/// Rows are collected into Arrow batches per partition, each written as a
+/// row group. A file is closed once records of a later day arrive, it
+/// reaches the configured size or age, and the next record of its partition
+/// starts a new one. Existing files are never appended to, so a restart
+/// starts the next file of the day; files a previous run did not finish
+/// have no footer and are removed.
+pub struct ParquetHandler {
+    config: Config,
+    archive: Mutex<Archive>,
+}
+
+impl ParquetHandler {
+    pub fn new(config: Config) -> Result<Self, io::Error> {
+        fs::create_dir_all(&config.dir)?;
+        remove_unfinished(Path::new(&config.dir))?;
+        Ok(Self {
+            config,
+            archive: Mutex::new(Archive::default()),
+        })
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Archive>, io::Error> {
+        self.archive
+            .lock()
+            .map_err(|e| io::Error::other(format!("Failed to lock archive: {e}")))
+    }
+}
+
+impl Handler for ParquetHandler {
+    fn handle_candle(&self, candle: Candle) -> Result<(), io::Error> {
+        let date = date_of(candle.timestamp)?;
+        let mut archive = self.lock()?;
+        archive.advance(date)?;
+        archive.close_expired(self.config.max_file_age())?;
+        push(&self.config, &mut archive.candles, date, candle)
+    }
+
+    fn handle_indicator(&self, indicator: Indicator) -> Result<(), io::Error> {
+        let date = date_of(indicator.timestamp)?;
+        let mut archive = self.lock()?;
+        archive.advance(date)?;
+        archive.close_expired(self.config.max_file_age())?;
+        push(&self.config, &mut archive.indicators, date, indicator)
+    }
+}
+
+impl Drop for ParquetHandler {
+    /// Writes the collected rows and the footers of the open files.
+    fn drop(&mut self) {
+        let archive = self
+            .archive
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Err(e) = archive.close() {
+            println!("Failed to close Parquet files: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::candle::tests::candle;
+    use arrow_array::{Float64Array, RecordBatch};
+    use arrow_schema::{DataType, TimeUnit};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use std::env;
+
+    fn config(name: &str, batch_rows: usize, max_bytes: usize) -> Config {
+        let dir = env::temp_dir().join(format!("hts-connector-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Config {
+            dir: dir.to_string_lossy().to_string(),
+            batch_rows,
+            max_bytes,
+            max_file_age_ms: 0,
+        }
+    }
+
+    /// Paths of the files under `dir`, relative to it.
+    fn files(dir: &Path) -> Vec<String> {
+        let mut files = Vec::new();
+        let mut dirs = vec![dir.to_path_buf()];
+        while let Some(next) = dirs.pop() {
+            for entry in fs::read_dir(next).expect("Failed to read directory") {
+                let path = entry.expect("Failed to read entry").path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else if let Ok(file) = path.strip_prefix(dir) {
+                    files.push(file.to_string_lossy().to_string());
+                }
+            }
+        }
+        files.sort();
+        files
+    }
+
+    fn read(path: &Path) -> RecordBatch {
+        ParquetRecordBatchReaderBuilder::try_new(File::open(path).expect("Failed to open file"))
+            .expect("Failed to read metadata")
+            .build()
+            .expect("Failed to create reader")
+            .next()
+            .expect("No batch in file")
+            .expect("Failed to read batch")
+    }
+
+    #[test]
+    fn test_archive_partitions_by_date_and_event() {
+        // Arrange
+        let config = config("parquet-partition", 2, 0);
+        let dir = PathBuf::from(&config.dir);
+        let handler = ParquetHandler::new(config).expect("Failed to create handler");
+
+        // Act
+        // 2024-04-30 13:21 and 13:22 in Seoul, then 2024-05-01 09:00.
+        handler
+            .handle_candle(candle("테스트", 1_714_450_860_000_000_000, 368.75))
+            .expect("Failed to handle candle");
+        handler
+            .handle_candle(candle("BTC/KRW", 1_714_450_860_000_000_000, 100.0))
+            .expect("Failed to handle candle");
+        handler
+            .handle_candle(candle("테스트", 1_714_450_920_000_000_000, 368.8))
+            .expect("Failed to handle candle");
+        handler
+            .handle_indicator(Indicator {
+                timestamp: 1_714_450_920_000_000_000,
+                event: "테스트".to_string(),
+                property: "rsi".to_string(),
+                value: 70,
+            })
+            .expect("Failed to handle indicator");
+        handler
+            .handle_candle(candle("테스트", 1_714_521_600_000_000_000, 369.0))
+            .expect("Failed to handle candle");
+        drop(handler);
+
+        // Assert
+        assert_eq!(
+            files(&dir),
+            vec![
+                "candle/date=2024-04-30/event=BTC%2FKRW/part-000.parquet",
+                "candle/date=2024-04-30/event=테스트/part-000.parquet",
+                "candle/date=2024-05-01/event=테스트/part-000.parquet",
+                "indicator/date=2024-04-30/event=테스트/part-000.parquet",
+            ]
+        );
+
+        let batch = read(&dir.join("candle/date=2024-04-30/event=테스트/part-000.parquet"));
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(
+            batch
+                .schema()
+                .field_with_name("time")
+                .map(|f| f.data_type().clone())
+                .ok(),
+            Some(DataType::Timestamp(
+                TimeUnit::Nanosecond,
+                Some("UTC".into())
+            ))
+        );
+        let close = batch
+            .column_by_name("close")
+            .and_then(|column| column.as_any().downcast_ref::<Float64Array>())
+            .expect("No close column");
+        assert_eq!(close.values().to_vec(), vec![368.75, 368.8]);
+
+        fs::remove_dir_all(&dir).expect("Failed to remove directory");
+    }
+
+    #[test]
+    fn test_archive_rotates_by_size() {
+        // Arrange
+        let config = config("parquet-rotate", 1, 1);
+        let dir = PathBuf::from(&config.dir);
+        let handler = ParquetHandler::new(config).expect("Failed to create handler");
+
+        // Act
+        handler
+            .handle_candle(candle("테스트", 1_714_450_860_000_000_000, 368.75))
+            .expect("Failed to handle candle");
+        handler
+            .handle_candle(candle("테스트", 1_714_450_920_000_000_000, 368.8))
+            .expect("Failed to handle candle");
+        drop(handler);
+
+        // Assert
+        assert_eq!(
+            files(&dir),
+            vec![
+                "candle/date=2024-04-30/event=테스트/part-000.parquet",
+                "candle/date=2024-04-30/event=테스트/part-001.parquet",
+            ]
+        );
+        assert_eq!(
+            read(&dir.join("candle/date=2024-04-30/event=테스트/part-001.parquet")).num_rows(),
+            1
+        );
+
+        fs::remove_dir_all(&dir).expect("Failed to remove directory");
+    }
+
+    #[test]
+    fn test_archive_closes_files_by_age() {
+        // Arrange
+        let config = Config {
+            max_file_age_ms: 1,
+            ..config("parquet-age", 8192, 0)
+        };
+        let dir = PathBuf::from(&config.dir);
+        let handler = ParquetHandler::new(config).expect("Failed to create handler");
+
+        // Act
+        handler
+            .handle_candle(candle("테스트", 1_714_450_860_000_000_000, 368.75))
+            .expect("Failed to handle candle");
+        std::thread::sleep(Duration::from_millis(10));
+        handler
+            .handle_candle(candle("테스트", 1_714_450_920_000_000_000, 368.8))
+            .expect("Failed to handle candle");
+
+        // Assert
+        assert_eq!(
+            read(&dir.join("candle/date=2024-04-30/event=테스트/part-000.parquet")).num_rows(),
+            1
+        );
+
+        drop(handler);
+        fs::remove_dir_all(&dir).expect("Failed to remove directory");
+    }
+
+    #[test]
+    fn test_new_removes_unfinished_files() {
+        // Arrange
+        let config = config("parquet-unfinished", 8192, 0);
+        let dir = PathBuf::from(&config.dir);
+        let partition = dir.join("candle/date=2024-04-30/event=테스트");
+        fs::create_dir_all(&partition).expect("Failed to create directory");
+        fs::write(partition.join("part-000.inprogress"), b"PAR1").expect("Failed to write file");
+        fs::write(partition.join("part-001.parquet"), b"PAR1").expect("Failed to write file");
+
+        // Act
+        let handler = ParquetHandler::new(config).expect("Failed to create handler");
+        drop(handler);
+
+        // Assert
+        assert_eq!(
+            files(&dir),
+            vec!["candle/date=2024-04-30/event=테스트/part-001.parquet"]
+        );
+
+        fs::remove_dir_all(&dir).expect("Failed to remove directory");
+    }
+}
@@ -0,0 +1,57 @@
+use crate::config::parse_or;
+use envconfig::Envconfig;
+use serde::Deserialize;
+
+use std::env;
+use std::error::Error;
+use std::time::Duration;
+
+/// Directory used when `PARQUET_DIR` is not set.
+pub const DEFAULT_DIR: &str = "archive";
+
+#[derive(Debug, Clone, Deserialize, Envconfig)]
+pub struct Config {
+    /// Directory the partitions are written under, as
+    /// `<kind>/date=<date>/event=<event>/part-<sequence>.parquet`.
+    pub dir: String,
+    /// Rows collected per partition before they are written as one Arrow
+    /// batch.
+    pub batch_rows: usize,
+    /// Bytes after which a file is closed and the next one started, `0` for
+    /// one file per partition and day.
+    pub max_bytes: usize,
+    /// Milliseconds after which a file is closed and the next one started,
+    /// so its rows are readable without waiting for the day to end, `0` to
+    /// keep it open.
+    pub max_file_age_ms: u64,
+}
+
+impl Config {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            dir: env::var("PARQUET_DIR").unwrap_or_else(|_| DEFAULT_DIR.to_string()),
+            batch_rows: parse_or(env::var("PARQUET_BATCH_ROWS").ok(), 8192)?,
+            max_bytes: parse_or(env::var("PARQUET_MAX_BYTES").ok(), 128 * 1024 * 1024)?,
+            max_file_age_ms: parse_or(env::var("PARQUET_MAX_FILE_AGE_MS").ok(), 3_600_000)?,
+        })
+    }
+
+    pub fn init() -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            dir: option_env!("PARQUET_DIR")
+                .unwrap_or(DEFAULT_DIR)
+                .to_string(),
+            batch_rows: parse_or(option_env!("PARQUET_BATCH_ROWS"), 8192)?,
+            max_bytes: parse_or(option_env!("PARQUET_MAX_BYTES"), 128 * 1024 * 1024)?,
+            max_file_age_ms: parse_or(option_env!("PARQUET_MAX_FILE_AGE_MS"), 3_600_000)?,
+        })
+    }
+
+    #[must_use]
+    pub const fn max_file_age(&self) -> Option<Duration> {
+        match self.max_file_age_ms {
+            0 => None,
+            ms => Some(Duration::from_millis(ms)),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Arrow schemas of the archived records and their conversion into batches.
+
+use crate::model::{candle::Candle, indicator::Indicator};
+use arrow_array::{
+    ArrayRef, Float64Array, Int64Array, RecordBatch, StringArray, TimestampNanosecondArray,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef, TimeUnit};
+
+use std::sync::Arc;
+
+/// A record archived into its own set of Parquet files.
+pub trait Record: Sized {
+    /// Directory the files of the record are written under.
+    const KIND: &'static str;
+
+    fn schema() -> SchemaRef;
+
+    /// Nanoseconds since the Unix epoch.
+    fn timestamp(&self) -> u128;
+
+    fn event(&self) -> &str;
+
+    /// Builds a batch of `records` in the order given.
+    fn batch(records: &[Self]) -> Result<RecordBatch, ArrowError>;
+}
+
+/// `time` as nanoseconds in UTC, which Parquet readers load as a timestamp.
+fn time_field() -> Field {
+    Field::new(
+        "time",
+        DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())),
+        false,
+    )
+}
+
+fn times<R: Record>(records: &[R]) -> Result<ArrayRef, ArrowError> {
+    let times = records
+        .iter()
+        .map(|record| {
+            i64::try_from(record.timestamp()).map_err(|_| {
+                ArrowError::InvalidArgumentError(format!(
+                    "Timestamp out of range: {}",
+                    record.timestamp()
+                ))
+            })
+        })
+        .collect::<Result<Vec<i64>, ArrowError>>()?;
+    Ok(Arc::new(
+        TimestampNanosecondArray::from(times).with_timezone("UTC"),
+    ))
+}
+
+impl Record for Candle {
+    const KIND: &'static str = "candle";
+
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            time_field(),
+            Field::new("event", DataType::Utf8, false),
+            Field::new("open", DataType::Float64, false),
+            Field::new("high", DataType::Float64, false),
+            Field::new("low", DataType::Float64, false),
+            Field::new("close", DataType::Float64, false),
+        ]))
+    }
+
+    fn timestamp(&self) -> u128 {
+        self.timestamp
+    }
+
+    fn event(&self) -> &str {
+        &self.event
+    }
+
+    fn batch(candles: &[Self]) -> Result<RecordBatch, ArrowError> {
+        let prices = |price: fn(&Self) -> f64| -> ArrayRef {
+            Arc::new(candles.iter().map(price).collect::<Float64Array>())
+        };
+        RecordBatch::try_new(
+            Self::schema(),
+            vec![
+                times(candles)?,
+                Arc::new(StringArray::from_iter_values(
+                    candles.iter().map(|candle| &candle.event),
+                )),
+                prices(|candle| candle.open),
+                prices(|candle| candle.high),
+                prices(|candle| candle.low),
+                prices(|candle| candle.close),
+            ],
+        )
+    }
+}
+
+impl Record for Indicator {
+    const KIND: &'static str = "indicator";
+
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            time_field(),
+            Field::new("event", DataType::Utf8, false),
+            Field::new("property", DataType::Utf8, false),
+            Field::new("value", DataType::Int64, false),
+        ]))
+    }
+
+    fn timestamp(&self) -> u128 {
+        self.timestamp
+    }
+
+    fn event(&self) -> &str {
+        &self.event
+    }
+
+    fn batch(indicators: &[Self]) -> Result<RecordBatch, ArrowError> {
+        RecordBatch::try_new(
+            Self::schema(),
+            vec![
+                times(indicators)?,
+                Arc::new(StringArray::from_iter_values(
+                    indicators.iter().map(|indicator| &indicator.event),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    indicators.iter().map(|indicator| &indicator.property),
+                )),
+                Arc::new(
+                    indicators
+                        .iter()
+                        .map(|indicator| indicator.value)
+                        .collect::<Int64Array>(),
+                ),
+            ],
+        )
+    }
+}
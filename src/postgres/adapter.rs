@@ -0,0 +1,605 @@
+use crate::model::{candle::Candle, indicator::Indicator};
+use crate::postgres::config::Config;
+use crate::postgres::migration::migrate;
+use crate::text::reader::Handler;
+use chrono::{DateTime, Utc};
+use postgres::types::ToSql;
+use postgres::{Client, GenericClient, NoTls};
+
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+/// Bind parameters `PostgreSQL` accepts in one statement.
+const MAX_PARAMETERS: usize = 65_535;
+
+#[derive(Debug)]
+enum Record {
+    Candle(Candle),
+    Indicator(Indicator),
+}
+
+/// A [`Handler`] writing candles and indicators into the `candle` and
+/// `indicator` tables, creating them on startup.
+///
+/// Records are upserted in batches on a writer thread, keyed on the event
+/// (and property) and time, so replaying a file does not duplicate rows.
+/// A batch that fails is kept and retried after the reconnect backoff,
+/// unless `PostgreSQL` rejected some of its rows, in which case only those
+/// are dropped and the rest is written. Once the configured number of rows
+/// is waiting, handling fails until they are written, and records already
+/// on their way are dropped.
+pub struct PostgresHandler {
+    sender: Option<Sender<Record>>,
+    writer: Option<JoinHandle<()>>,
+    full: Arc<AtomicBool>,
+}
+
+impl PostgresHandler {
+    /// Connects, applies the migrations and starts the writer thread.
+    pub fn new(config: Config) -> Result<Self, io::Error> {
+        let mut client = connect(&config)?;
+        migrate(&mut client, config.timescale)
+            .map_err(|e| io::Error::other(format!("Failed to migrate: {e}")))?;
+
+        let (sender, receiver) = mpsc::channel();
+        let full = Arc::new(AtomicBool::new(false));
+        let mut writer = Writer::new(Some(client), config, Arc::clone(&full));
+        let writer = thread::spawn(move || writer.run(&receiver));
+
+        Ok(Self {
+            sender: Some(sender),
+            writer: Some(writer),
+            full,
+        })
+    }
+
+    fn send(&self, record: Record) -> Result<(), io::Error> {
+        if self.full.load(Ordering::Relaxed) {
+            return Err(io::Error::other(
+                "PostgreSQL buffer is full, waiting for rows to be written",
+            ));
+        }
+        self.sender
+            .as_ref()
+            .and_then(|sender| sender.send(record).ok())
+            .ok_or_else(|| io::Error::other("PostgreSQL writer stopped"))
+    }
+}
+
+impl Handler for PostgresHandler {
+    fn handle_candle(&self, candle: Candle) -> Result<(), io::Error> {
+        self.send(Record::Candle(candle))
+    }
+
+    fn handle_indicator(&self, indicator: Indicator) -> Result<(), io::Error> {
+        self.send(Record::Indicator(indicator))
+    }
+}
+
+impl Drop for PostgresHandler {
+    /// Writes the rows still queued before returning.
+    fn drop(&mut self) {
+        self.sender = None;
+        if let Some(writer) = self.writer.take() {
+            if writer.join().is_err() {
+                println!("PostgreSQL writer panicked");
+            }
+        }
+    }
+}
+
+fn connect(config: &Config) -> Result<Client, io::Error> {
+    Client::connect(&config.url, NoTls)
+        .map_err(|e| io::Error::other(format!("Failed to connect to PostgreSQL: {e}")))
+}
+
+/// Timestamps after 2262 do not fit into nanoseconds and are clamped; the
+/// HTS file cannot contain them.
+fn time(timestamp: u128) -> DateTime<Utc> {
+    DateTime::from_timestamp_nanos(i64::try_from(timestamp).unwrap_or(i64::MAX))
+}
+
+/// The microseconds `TIMESTAMPTZ` keeps of `timestamp`, so that records
+/// stored as the same row share a key.
+const fn micros(timestamp: u128) -> u128 {
+    timestamp / 1_000
+}
+
+/// Whether `PostgreSQL` refused the rows themselves, so writing them again
+/// fails the same way. Connection failures, lack of resources, shutdowns
+/// and transaction conflicts are retried.
+fn is_permanent(e: &postgres::Error) -> bool {
+    e.code()
+        .is_some_and(|code| !matches!(code.code().get(..2), Some("08" | "40" | "53" | "57")))
+}
+
+/// Writes `rows` with `write`, splitting them in halves on a permanent
+/// error until the rows `PostgreSQL` rejects are isolated and dropped.
+/// Returns the number of dropped rows, or the first other error.
+fn isolate<T, E: std::fmt::Display>(
+    rows: &[T],
+    write: &mut impl FnMut(&[T]) -> Result<(), E>,
+    permanent: &impl Fn(&E) -> bool,
+) -> Result<usize, E> {
+    if rows.is_empty() {
+        return Ok(0);
+    }
+    match write(rows) {
+        Ok(()) => Ok(0),
+        Err(e) if permanent(&e) && rows.len() > 1 => {
+            let (first, second) = rows.split_at(rows.len() / 2);
+            Ok(isolate(first, write, permanent)? + isolate(second, write, permanent)?)
+        }
+        Err(e) if permanent(&e) => {
+            println!("Dropped a row PostgreSQL rejected: {e}");
+            Ok(1)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// `INSERT … ON CONFLICT … DO UPDATE` for `rows` rows, updating the columns
+/// not in `key`.
+fn upsert(table: &str, columns: &[&str], key: &[&str], rows: usize) -> String {
+    let values: Vec<String> = (0..rows)
+        .map(|row| {
+            let parameters: Vec<String> = (1..=columns.len())
+                .map(|column| format!("${}", row * columns.len() + column))
+                .collect();
+            format!("({})", parameters.join(", "))
+        })
+        .collect();
+    let updates: Vec<String> = columns
+        .iter()
+        .filter(|column| !key.contains(column))
+        .map(|column| format!("{column} = EXCLUDED.{column}"))
+        .collect();
+
+    format!(
+        "INSERT INTO {table} ({}) VALUES {} ON CONFLICT ({}) DO UPDATE SET {}",
+        columns.join(", "),
+        values.join(", "),
+        key.join(", "),
+        updates.join(", ")
+    )
+}
+
+const CANDLE_COLUMNS: &[&str] = &["event", "time", "open", "high", "low", "close"];
+const INDICATOR_COLUMNS: &[&str] = &["event", "property", "time", "value"];
+
+fn upsert_candles(
+    client: &mut impl GenericClient,
+    candles: &[&Candle],
+    batch_size: usize,
+) -> Result<(), postgres::Error> {
+    let times: Vec<DateTime<Utc>> = candles
+        .iter()
+        .map(|candle| time(candle.timestamp))
+        .collect();
+
+    let rows = batch_size.clamp(1, MAX_PARAMETERS / CANDLE_COLUMNS.len());
+    for (candles, times) in candles.chunks(rows).zip(times.chunks(rows)) {
+        let mut parameters: Vec<&(dyn ToSql + Sync)> = Vec::new();
+        for (candle, time) in candles.iter().zip(times) {
+            parameters.extend([
+                &candle.event as &(dyn ToSql + Sync),
+                time,
+                &candle.open,
+                &candle.high,
+                &candle.low,
+                &candle.close,
+            ]);
+        }
+        let statement = upsert("candle", CANDLE_COLUMNS, &["event", "time"], candles.len());
+        client.execute(&statement, &parameters)?;
+    }
+    Ok(())
+}
+
+fn upsert_indicators(
+    client: &mut impl GenericClient,
+    indicators: &[&Indicator],
+    batch_size: usize,
+) -> Result<(), postgres::Error> {
+    let times: Vec<DateTime<Utc>> = indicators
+        .iter()
+        .map(|indicator| time(indicator.timestamp))
+        .collect();
+
+    let rows = batch_size.clamp(1, MAX_PARAMETERS / INDICATOR_COLUMNS.len());
+    for (indicators, times) in indicators.chunks(rows).zip(times.chunks(rows)) {
+        let mut parameters: Vec<&(dyn ToSql + Sync)> = Vec::new();
+        for (indicator, time) in indicators.iter().zip(times) {
+            parameters.extend([
+                &indicator.event as &(dyn ToSql + Sync),
+                &indicator.property,
+                time,
+                &indicator.value,
+            ]);
+        }
+        let statement = upsert(
+            "indicator",
+            INDICATOR_COLUMNS,
+            &["event", "property", "time"],
+            indicators.len(),
+        );
+        client.execute(&statement, &parameters)?;
+    }
+    Ok(())
+}
+
+/// Upserts `candles` and `indicators` in one transaction.
+fn upsert_rows(
+    client: &mut Client,
+    candles: &[&Candle],
+    indicators: &[&Indicator],
+    batch_size: usize,
+) -> Result<(), postgres::Error> {
+    let mut transaction = client.transaction()?;
+    upsert_candles(&mut transaction, candles, batch_size)?;
+    upsert_indicators(&mut transaction, indicators, batch_size)?;
+    transaction.commit()
+}
+
+/// Rows waiting to be written, by key so that a record repeated within a
+/// batch is written once, with its latest values. Keys hold the time in
+/// microseconds, as stored.
+struct Writer {
+    client: Option<Client>,
+    config: Config,
+    candles: BTreeMap<(String, u128), Candle>,
+    indicators: BTreeMap<(String, String, u128), Indicator>,
+    /// Set after a failure, so reconnects are attempted once per backoff.
+    retry_at: Option<Instant>,
+    /// Set while `max_buffered_rows` rows are waiting.
+    full: Arc<AtomicBool>,
+    /// Records dropped since the buffer filled up.
+    dropped: usize,
+}
+
+impl Writer {
+    const fn new(client: Option<Client>, config: Config, full: Arc<AtomicBool>) -> Self {
+        Self {
+            client,
+            config,
+            candles: BTreeMap::new(),
+            indicators: BTreeMap::new(),
+            retry_at: None,
+            full,
+            dropped: 0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.candles.len() + self.indicators.len()
+    }
+
+    fn push(&mut self, record: Record) {
+        if self.len() >= self.config.max_buffered_rows {
+            self.dropped += 1;
+            return;
+        }
+        match record {
+            Record::Candle(candle) => {
+                self.candles
+                    .insert((candle.event.clone(), micros(candle.timestamp)), candle);
+            }
+            Record::Indicator(indicator) => {
+                self.indicators.insert(
+                    (
+                        indicator.event.clone(),
+                        indicator.property.clone(),
+                        micros(indicator.timestamp),
+                    ),
+                    indicator,
+                );
+            }
+        }
+        if self.len() >= self.config.max_buffered_rows {
+            self.full.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Writes the buffered rows in one transaction. When `PostgreSQL`
+    /// rejects some of them, candles and indicators are written again in
+    /// halves so that only the rejected rows are dropped.
+    fn write(&mut self) -> Result<(), postgres::Error> {
+        let client = match &mut self.client {
+            Some(client) if !client.is_closed() => client,
+            _ => self
+                .client
+                .insert(Client::connect(&self.config.url, NoTls)?),
+        };
+
+        let batch_size = self.config.batch_size;
+        let candles: Vec<&Candle> = self.candles.values().collect();
+        let indicators: Vec<&Indicator> = self.indicators.values().collect();
+        match upsert_rows(client, &candles, &indicators, batch_size) {
+            Err(e) if is_permanent(&e) => {
+                let dropped = isolate(
+                    &candles,
+                    &mut |candles| upsert_rows(client, candles, &[], batch_size),
+                    &is_permanent,
+                )? + isolate(
+                    &indicators,
+                    &mut |indicators| upsert_rows(client, &[], indicators, batch_size),
+                    &is_permanent,
+                )?;
+                println!("Dropped {dropped} rows PostgreSQL rejected: {e}");
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.candles.clear();
+        self.indicators.clear();
+        self.retry_at = None;
+        self.full.store(false, Ordering::Relaxed);
+        if self.dropped > 0 {
+            println!(
+                "Dropped {} records while the PostgreSQL buffer was full",
+                self.dropped
+            );
+            self.dropped = 0;
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.len() == 0 || self.retry_at.is_some_and(|at| Instant::now() < at) {
+            return;
+        }
+        match self.write() {
+            Ok(()) => self.clear(),
+            Err(e) if is_permanent(&e) => {
+                println!("Dropped {} rows PostgreSQL rejected: {e}", self.len());
+                self.clear();
+            }
+            Err(e) => {
+                println!(
+                    "Failed to write {} rows to PostgreSQL, retrying: {e}",
+                    self.len()
+                );
+                self.client = None;
+                self.retry_at = Some(Instant::now() + self.config.reconnect_backoff());
+            }
+        }
+    }
+
+    /// Collects records into batches of up to `batch_size` rows, writing a
+    /// batch when it is full or the flush interval passed, until the
+    /// handler is dropped.
+    fn run(&mut self, receiver: &mpsc::Receiver<Record>) {
+        let mut deadline = Instant::now() + self.config.flush_interval();
+        loop {
+            match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(record) => {
+                    self.push(record);
+                    if self.len() < self.config.batch_size {
+                        continue;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.retry_at = None;
+                    self.flush();
+                    if self.len() + self.dropped > 0 {
+                        println!(
+                            "Dropped {} rows not written to PostgreSQL",
+                            self.len() + self.dropped
+                        );
+                    }
+                    return;
+                }
+            }
+            self.flush();
+            deadline = Instant::now() + self.config.flush_interval();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::candle::tests::candle;
+    use std::net::TcpListener;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn test_upsert_statement() {
+        // Act
+        let statement = upsert(
+            "indicator",
+            INDICATOR_COLUMNS,
+            &["event", "property", "time"],
+            2,
+        );
+
+        // Assert
+        assert_eq!(
+            statement,
+            "INSERT INTO indicator (event, property, time, value) \
+             VALUES ($1, $2, $3, $4), ($5, $6, $7, $8) \
+             ON CONFLICT (event, property, time) DO UPDATE SET value = EXCLUDED.value"
+        );
+    }
+
+    #[test]
+    fn test_isolate_drops_only_the_rejected_row() {
+        // Arrange
+        let rows: Vec<u32> = (0..10).collect();
+        let mut written = Vec::new();
+        let mut write = |rows: &[u32]| {
+            if rows.contains(&7) {
+                return Err("invalid input syntax".to_string());
+            }
+            written.extend_from_slice(rows);
+            Ok(())
+        };
+
+        // Act
+        let dropped = isolate(&rows, &mut write, &|_| true);
+
+        // Assert
+        assert_eq!(dropped, Ok(1));
+        written.sort_unstable();
+        assert_eq!(written, vec![0, 1, 2, 3, 4, 5, 6, 8, 9]);
+    }
+
+    #[test]
+    fn test_isolate_stops_on_other_errors() {
+        // Arrange
+        let rows: Vec<u32> = (0..10).collect();
+        let mut write = |_: &[u32]| Err("connection closed".to_string());
+
+        // Act
+        let dropped = isolate(&rows, &mut write, &|_| false);
+
+        // Assert
+        assert_eq!(dropped, Err("connection closed".to_string()));
+    }
+
+    #[test]
+    fn test_records_in_the_same_microsecond_share_a_row() {
+        // Arrange
+        let mut writer = Writer::new(
+            None,
+            Config {
+                url: String::new(),
+                timescale: false,
+                batch_size: 500,
+                flush_interval_ms: 1000,
+                reconnect_backoff_ms: 1000,
+                max_buffered_rows: 100,
+            },
+            Arc::new(AtomicBool::new(false)),
+        );
+
+        // Act
+        for timestamp in [
+            1_714_450_980_000_000_100,
+            1_714_450_980_000_000_900,
+            1_714_450_980_000_001_000,
+        ] {
+            writer.push(Record::Candle(candle("BTCUSDT", timestamp, 150.0)));
+        }
+
+        // Assert
+        assert_eq!(writer.len(), 2);
+    }
+
+    #[test]
+    fn test_failed_writes_back_off_and_fill_the_buffer() {
+        // Arrange
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind");
+        let url = format!(
+            "postgresql://postgres@{}/hts",
+            listener.local_addr().expect("No address")
+        );
+        let attempts = Arc::new(AtomicUsize::new(0));
+        {
+            let attempts = Arc::clone(&attempts);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    attempts.fetch_add(1, Ordering::SeqCst);
+                    drop(stream);
+                }
+            });
+        }
+        let full = Arc::new(AtomicBool::new(false));
+        let mut writer = Writer::new(
+            None,
+            Config {
+                url,
+                timescale: false,
+                batch_size: 1,
+                flush_interval_ms: 60_000,
+                reconnect_backoff_ms: 60_000,
+                max_buffered_rows: 2,
+            },
+            Arc::clone(&full),
+        );
+
+        // Act
+        for timestamp in 0..4 {
+            writer.push(Record::Candle(candle("BTCUSDT", timestamp * 1_000, 150.0)));
+            writer.flush();
+        }
+
+        // Assert
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        assert_eq!(writer.len(), 2);
+        assert_eq!(writer.dropped, 2);
+        assert!(full.load(Ordering::Relaxed));
+    }
+
+    #[ignore]
+    #[test]
+    fn test_write_candles_and_indicators() {
+        // Arrange
+        let config = Config::new().expect("Failed to create config");
+        let event = format!("test-{}", std::process::id());
+        let candle = Candle {
+            timestamp: 1_714_450_980_000_000_000,
+            event: event.clone(),
+            open: 100.0,
+            high: 200.0,
+            low: 50.0,
+            close: 150.0,
+        };
+        let indicator = Indicator {
+            timestamp: 1_714_450_980_000_000_000,
+            event: event.clone(),
+            property: "rsi".to_string(),
+            value: 70,
+        };
+        let handler = PostgresHandler::new(config.clone()).expect("Failed to create handler");
+
+        // Act
+        handler
+            .handle_candle(candle.clone())
+            .expect("Failed to handle candle");
+        handler
+            .handle_candle(Candle {
+                close: 175.0,
+                ..candle
+            })
+            .expect("Failed to handle candle");
+        handler
+            .handle_indicator(indicator)
+            .expect("Failed to handle indicator");
+        drop(handler);
+
+        // Assert
+        let mut client = connect(&config).expect("Failed to connect");
+        let candles = client
+            .query("SELECT time, close FROM candle WHERE event = $1", &[&event])
+            .expect("Failed to query candles");
+        let values: Vec<i64> = client
+            .query("SELECT value FROM indicator WHERE event = $1", &[&event])
+            .expect("Failed to query indicators")
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        client
+            .execute("DELETE FROM candle WHERE event = $1", &[&event])
+            .expect("Failed to clean up candles");
+        client
+            .execute("DELETE FROM indicator WHERE event = $1", &[&event])
+            .expect("Failed to clean up indicators");
+
+        assert_eq!(candles.len(), 1);
+        assert_eq!(
+            candles[0].get::<_, DateTime<Utc>>(0).timestamp(),
+            1_714_450_980
+        );
+        assert!((candles[0].get::<_, f64>(1) - 175.0).abs() < f64::EPSILON);
+        assert_eq!(values, vec![70]);
+    }
+}
@@ -0,0 +1,75 @@
+use crate::config::parse_or;
+use envconfig::Envconfig;
+use serde::Deserialize;
+
+use std::env;
+use std::error::Error;
+use std::time::Duration;
+
+/// Connection string used when `POSTGRES_URL` is not set.
+pub const DEFAULT_URL: &str = "postgresql://postgres@localhost/hts";
+
+#[derive(Debug, Clone, Deserialize, Envconfig)]
+pub struct Config {
+    /// A `postgresql://` URL or a `host=… user=…` connection string.
+    pub url: String,
+    /// Turn the tables into `TimescaleDB` hypertables partitioned by time.
+    pub timescale: bool,
+    /// Rows written per statement.
+    pub batch_size: usize,
+    /// Time after which rows are written even if the batch is not full.
+    pub flush_interval_ms: u64,
+    /// Time to wait before reconnecting after a write failed.
+    pub reconnect_backoff_ms: u64,
+    /// Rows kept while writes fail, after which handling fails until they
+    /// are written.
+    pub max_buffered_rows: usize,
+}
+
+impl Config {
+    fn validate(self) -> Result<Self, Box<dyn Error>> {
+        if self.flush_interval_ms == 0 {
+            return Err("Expected a flush interval above 0 ms".into());
+        }
+        if self.max_buffered_rows == 0 {
+            return Err("Expected a maximum of buffered rows above 0".into());
+        }
+        Ok(self)
+    }
+
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Self {
+            url: env::var("POSTGRES_URL").unwrap_or_else(|_| DEFAULT_URL.to_string()),
+            timescale: parse_or(env::var("POSTGRES_TIMESCALE").ok(), false)?,
+            batch_size: parse_or(env::var("POSTGRES_BATCH_SIZE").ok(), 500)?,
+            flush_interval_ms: parse_or(env::var("POSTGRES_FLUSH_INTERVAL_MS").ok(), 1000)?,
+            reconnect_backoff_ms: parse_or(env::var("POSTGRES_RECONNECT_BACKOFF_MS").ok(), 1000)?,
+            max_buffered_rows: parse_or(env::var("POSTGRES_MAX_BUFFERED_ROWS").ok(), 100_000)?,
+        }
+        .validate()
+    }
+
+    pub fn init() -> Result<Self, Box<dyn Error>> {
+        Self {
+            url: option_env!("POSTGRES_URL")
+                .unwrap_or(DEFAULT_URL)
+                .to_string(),
+            timescale: parse_or(option_env!("POSTGRES_TIMESCALE"), false)?,
+            batch_size: parse_or(option_env!("POSTGRES_BATCH_SIZE"), 500)?,
+            flush_interval_ms: parse_or(option_env!("POSTGRES_FLUSH_INTERVAL_MS"), 1000)?,
+            reconnect_backoff_ms: parse_or(option_env!("POSTGRES_RECONNECT_BACKOFF_MS"), 1000)?,
+            max_buffered_rows: parse_or(option_env!("POSTGRES_MAX_BUFFERED_ROWS"), 100_000)?,
+        }
+        .validate()
+    }
+
+    #[must_use]
+    pub const fn flush_interval(&self) -> Duration {
+        Duration::from_millis(self.flush_interval_ms)
+    }
+
+    #[must_use]
+    pub const fn reconnect_backoff(&self) -> Duration {
+        Duration::from_millis(self.reconnect_backoff_ms)
+    }
+}
@@ -0,0 +1,88 @@
+//! Versioned schema migrations, recorded in `schema_migrations` so every
+//! migration is applied once per database.
+
+use postgres::Client;
+
+/// Migrations in the order they are applied. Versions are never reused.
+const MIGRATIONS: &[(i32, &str)] = &[(1, include_str!("migrations/0001_create_tables.sql"))];
+
+/// Tables partitioned by time when `TimescaleDB` is enabled.
+const HYPERTABLES: &[&str] = &["candle", "indicator"];
+
+/// Applies the migrations not applied yet and, if `timescale` is set, turns
+/// the tables into hypertables. Concurrent runs wait for each other.
+pub fn migrate(client: &mut Client, timescale: bool) -> Result<(), postgres::Error> {
+    client.batch_execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        )",
+    )?;
+
+    let mut transaction = client.transaction()?;
+    transaction.batch_execute("LOCK TABLE schema_migrations IN EXCLUSIVE MODE")?;
+    for (version, sql) in MIGRATIONS {
+        let applied = transaction
+            .query_opt(
+                "SELECT 1 FROM schema_migrations WHERE version = $1",
+                &[version],
+            )?
+            .is_some();
+        if applied {
+            continue;
+        }
+
+        println!("Applying migration {version}");
+        transaction.batch_execute(sql)?;
+        transaction.execute(
+            "INSERT INTO schema_migrations (version) VALUES ($1)",
+            &[version],
+        )?;
+    }
+    transaction.commit()?;
+
+    if timescale {
+        client.batch_execute("CREATE EXTENSION IF NOT EXISTS timescaledb")?;
+        for table in HYPERTABLES {
+            client.execute(
+                "SELECT create_hypertable($1::text::regclass, 'time', \
+                 if_not_exists => TRUE, migrate_data => TRUE)",
+                &[table],
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::postgres::config::Config;
+    use postgres::NoTls;
+
+    #[ignore]
+    #[test]
+    fn test_migrate_twice() {
+        // Arrange
+        let config = Config::new().expect("Failed to create config");
+        let mut client = Client::connect(&config.url, NoTls).expect("Failed to connect");
+
+        // Act
+        let first = migrate(&mut client, false);
+        let second = migrate(&mut client, false);
+
+        // Assert
+        assert!(first.is_ok());
+        assert!(second.is_ok());
+
+        let versions: i64 = client
+            .query_one("SELECT count(*) FROM schema_migrations", &[])
+            .expect("Failed to count migrations")
+            .get(0);
+        assert_eq!(
+            versions,
+            i64::try_from(MIGRATIONS.len()).unwrap_or_default()
+        );
+    }
+}
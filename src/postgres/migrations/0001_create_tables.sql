@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS candle (
+    event TEXT NOT NULL,
+    time TIMESTAMPTZ NOT NULL,
+    open DOUBLE PRECISION NOT NULL,
+    high DOUBLE PRECISION NOT NULL,
+    low DOUBLE PRECISION NOT NULL,
+    close DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (event, time)
+);
+
+CREATE TABLE IF NOT EXISTS indicator (
+    event TEXT NOT NULL,
+    property TEXT NOT NULL,
+    time TIMESTAMPTZ NOT NULL,
+    value BIGINT NOT NULL,
+    PRIMARY KEY (event, property, time)
+);
@@ -0,0 +1,354 @@
+use crate::influx::line::Point;
+use crate::influx::precision::Precision;
+use crate::influx::schema::Schema;
+use crate::model::{candle::Candle, indicator::Indicator};
+use crate::questdb::config::Config;
+use crate::text::reader::Handler;
+
+use std::io::{self, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Time connecting and every write may take before the connection is
+/// considered down.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A [`Handler`] streaming the line protocol of the `influx` module to
+/// `QuestDB` over a persistent ILP TCP connection.
+///
+/// Lines are buffered on a writer thread and sent when the buffer is full or
+/// the flush interval passed. While the connection is down they stay
+/// buffered and are sent once it is re-established; once the configured
+/// maximum is buffered, handling fails until they are sent, and lines
+/// already on their way are dropped. Whatever is buffered when the handler
+/// is dropped is sent before it returns.
+pub struct QuestDbHandler {
+    schema: Schema,
+    sender: Option<Sender<String>>,
+    writer: Option<JoinHandle<()>>,
+    full: Arc<AtomicBool>,
+}
+
+impl QuestDbHandler {
+    #[must_use]
+    pub fn new(config: Config, schema: Schema) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let full = Arc::new(AtomicBool::new(false));
+        let mut writer = Writer::new(config, Arc::clone(&full));
+        let writer = thread::spawn(move || writer.run(&receiver));
+
+        Self {
+            schema,
+            sender: Some(sender),
+            writer: Some(writer),
+            full,
+        }
+    }
+
+    fn send(&self, point: &Point) -> Result<(), io::Error> {
+        if self.full.load(Ordering::Relaxed) {
+            return Err(io::Error::other(
+                "QuestDB buffer is full, waiting for the connection",
+            ));
+        }
+        // ILP over TCP has no precision parameter; timestamps are nanoseconds.
+        let line = point.render(Precision::Nanoseconds);
+        self.sender
+            .as_ref()
+            .and_then(|sender| sender.send(line).ok())
+            .ok_or_else(|| io::Error::other("QuestDB writer stopped"))
+    }
+}
+
+impl Handler for QuestDbHandler {
+    fn handle_candle(&self, candle: Candle) -> Result<(), io::Error> {
+        self.send(&self.schema.candle_point(&candle))
+    }
+
+    fn handle_indicator(&self, indicator: Indicator) -> Result<(), io::Error> {
+        self.send(&self.schema.indicator_point(&indicator))
+    }
+}
+
+impl Drop for QuestDbHandler {
+    /// Sends the buffered lines before returning.
+    fn drop(&mut self) {
+        self.sender = None;
+        if let Some(writer) = self.writer.take() {
+            if writer.join().is_err() {
+                println!("QuestDB writer panicked");
+            }
+        }
+    }
+}
+
+/// Connects to the first address `addr` resolves to that accepts within
+/// [`TIMEOUT`].
+fn connect(addr: &str) -> Result<TcpStream, io::Error> {
+    let mut last = io::Error::other(format!("{addr} resolves to no address"));
+    for addr in addr.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, TIMEOUT) {
+            Ok(stream) => {
+                stream.set_nodelay(true)?;
+                stream.set_write_timeout(Some(TIMEOUT))?;
+                return Ok(stream);
+            }
+            Err(e) => last = e,
+        }
+    }
+    Err(last)
+}
+
+/// Bytes of the lines in `sent` that were sent in full. A line cut off by a
+/// failed write is sent again whole, since the server discards the part it
+/// received with the connection.
+fn whole_lines(sent: &[u8]) -> usize {
+    sent.iter()
+        .rposition(|&byte| byte == b'\n')
+        .map_or(0, |end| end + 1)
+}
+
+struct Writer {
+    config: Config,
+    stream: Option<TcpStream>,
+    buffer: Vec<u8>,
+    /// Set after a failure, so reconnects are attempted once per backoff.
+    retry_at: Option<Instant>,
+    /// Set while `max_buffer_bytes` are buffered.
+    full: Arc<AtomicBool>,
+    /// Lines dropped since the buffer filled up.
+    dropped: usize,
+}
+
+impl Writer {
+    const fn new(config: Config, full: Arc<AtomicBool>) -> Self {
+        Self {
+            config,
+            stream: None,
+            buffer: Vec::new(),
+            retry_at: None,
+            full,
+            dropped: 0,
+        }
+    }
+
+    fn push(&mut self, line: &str) {
+        if self.buffer.len() >= self.config.max_buffer_bytes {
+            self.dropped += 1;
+            return;
+        }
+        self.buffer.extend_from_slice(line.as_bytes());
+        self.buffer.push(b'\n');
+        if self.buffer.len() >= self.config.max_buffer_bytes {
+            self.full.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Sends the buffer, removing the lines sent even if a later write fails
+    /// so they are not sent twice.
+    fn write(&mut self) -> Result<(), io::Error> {
+        let stream = match &mut self.stream {
+            Some(stream) => stream,
+            None => {
+                let stream = connect(&self.config.addr)?;
+                println!("Connected to QuestDB at {}", self.config.addr);
+                self.stream.insert(stream)
+            }
+        };
+
+        let mut sent = 0;
+        let result = loop {
+            if sent == self.buffer.len() {
+                break stream.flush();
+            }
+            match stream.write(&self.buffer[sent..]) {
+                Ok(0) => break Err(io::ErrorKind::WriteZero.into()),
+                Ok(written) => sent += written,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => break Err(e),
+            }
+        };
+        self.buffer.drain(..whole_lines(&self.buffer[..sent]));
+        result
+    }
+
+    fn flush(&mut self) {
+        if self.buffer.is_empty() || self.retry_at.is_some_and(|at| Instant::now() < at) {
+            return;
+        }
+        match self.write() {
+            Ok(()) => {
+                self.retry_at = None;
+                self.full.store(false, Ordering::Relaxed);
+                if self.dropped > 0 {
+                    println!(
+                        "Dropped {} lines while the QuestDB buffer was full",
+                        self.dropped
+                    );
+                    self.dropped = 0;
+                }
+            }
+            Err(e) => {
+                println!(
+                    "Failed to send {} bytes to QuestDB, retrying: {e}",
+                    self.buffer.len()
+                );
+                self.stream = None;
+                self.retry_at = Some(Instant::now() + self.config.reconnect_backoff());
+            }
+        }
+    }
+
+    /// Buffers lines until the buffer is full or the flush interval passed,
+    /// until the handler is dropped.
+    fn run(&mut self, receiver: &Receiver<String>) {
+        let mut deadline = Instant::now() + self.config.flush_interval();
+        loop {
+            match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(line) => {
+                    self.push(&line);
+                    if self.buffer.len() < self.config.buffer_bytes {
+                        continue;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.retry_at = None;
+                    self.flush();
+                    if !self.buffer.is_empty() || self.dropped > 0 {
+                        println!(
+                            "Dropped {} bytes and {} lines not sent to QuestDB",
+                            self.buffer.len(),
+                            self.dropped
+                        );
+                    }
+                    return;
+                }
+            }
+            self.flush();
+            deadline = Instant::now() + self.config.flush_interval();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::candle::tests::candle;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    fn config(addr: String) -> Config {
+        Config {
+            addr,
+            buffer_bytes: 64 * 1024,
+            flush_interval_ms: 50,
+            reconnect_backoff_ms: 50,
+            max_buffer_bytes: 1024 * 1024,
+        }
+    }
+
+    fn received(listener: &TcpListener) -> String {
+        let (mut stream, _) = listener.accept().expect("Failed to accept");
+        let mut received = String::new();
+        stream
+            .read_to_string(&mut received)
+            .expect("Failed to read");
+        received
+    }
+
+    #[test]
+    fn test_stream_flushes_on_shutdown() {
+        // Arrange
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind");
+        let mut config = config(listener.local_addr().expect("No address").to_string());
+        config.flush_interval_ms = 60_000;
+        let handler = QuestDbHandler::new(config, Schema::default());
+
+        // Act
+        handler
+            .handle_candle(candle("BTCUSDT", 1_000_000_000, 150.0))
+            .expect("Failed to handle candle");
+        handler
+            .handle_indicator(Indicator {
+                timestamp: 2_000_000_000,
+                event: "BTCUSDT".to_string(),
+                property: "rsi".to_string(),
+                value: 70,
+            })
+            .expect("Failed to handle indicator");
+        drop(handler);
+
+        // Assert
+        assert_eq!(
+            received(&listener),
+            "candle,event=BTCUSDT open=100,high=200,low=50,close=150 1000000000\n\
+             indicator,event=BTCUSDT,property=rsi value=70i 2000000000\n"
+        );
+    }
+
+    #[test]
+    fn test_stream_buffers_until_connected() {
+        // Arrange
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("Failed to find a free port");
+        let handler = QuestDbHandler::new(config(addr.to_string()), Schema::default());
+
+        // Act
+        handler
+            .handle_candle(candle("BTCUSDT", 1_000_000_000, 150.0))
+            .expect("Failed to handle candle");
+        thread::sleep(Duration::from_millis(200));
+        let listener = TcpListener::bind(addr).expect("Failed to bind");
+        handler
+            .handle_candle(candle("BTCUSDT", 2_000_000_000, 150.0))
+            .expect("Failed to handle candle");
+        drop(handler);
+
+        // Assert
+        assert_eq!(
+            received(&listener),
+            "candle,event=BTCUSDT open=100,high=200,low=50,close=150 1000000000\n\
+             candle,event=BTCUSDT open=100,high=200,low=50,close=150 2000000000\n"
+        );
+    }
+
+    #[test]
+    fn test_whole_lines_of_partial_write() {
+        // Act & Assert
+        assert_eq!(whole_lines(b""), 0);
+        assert_eq!(whole_lines(b"a 1\nb"), 4);
+        assert_eq!(whole_lines(b"a 1\nb 2\n"), 8);
+        assert_eq!(whole_lines(b"a"), 0);
+    }
+
+    #[test]
+    fn test_buffer_fills_up_while_disconnected() {
+        // Arrange
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("Failed to find a free port");
+        let mut config = config(addr.to_string());
+        config.reconnect_backoff_ms = 60_000;
+        config.max_buffer_bytes = 8;
+        let full = Arc::new(AtomicBool::new(false));
+        let mut writer = Writer::new(config, Arc::clone(&full));
+
+        // Act
+        for line in ["a 1", "b 2", "c 3"] {
+            writer.push(line);
+            writer.flush();
+        }
+
+        // Assert
+        assert_eq!(writer.buffer, b"a 1\nb 2\n");
+        assert_eq!(writer.dropped, 1);
+        assert!(full.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,76 @@
+use crate::config::parse_or;
+use envconfig::Envconfig;
+use serde::Deserialize;
+
+use std::env;
+use std::error::Error;
+use std::time::Duration;
+
+/// Address used when `QUESTDB_ADDR` is not set, QuestDB's ILP port.
+pub const DEFAULT_ADDR: &str = "localhost:9009";
+
+#[derive(Debug, Clone, Deserialize, Envconfig)]
+pub struct Config {
+    /// `host:port` of the ILP TCP endpoint.
+    pub addr: String,
+    /// Buffered bytes after which lines are sent without waiting for the
+    /// flush interval.
+    pub buffer_bytes: usize,
+    /// Time after which buffered lines are sent.
+    pub flush_interval_ms: u64,
+    /// Time to wait before reconnecting after the connection failed.
+    pub reconnect_backoff_ms: u64,
+    /// Bytes kept while the connection is down, after which handling fails
+    /// until they are sent.
+    pub max_buffer_bytes: usize,
+}
+
+impl Config {
+    fn validate(self) -> Result<Self, Box<dyn Error>> {
+        if self.flush_interval_ms == 0 {
+            return Err("Expected a flush interval above 0 ms".into());
+        }
+        if self.max_buffer_bytes < self.buffer_bytes {
+            return Err(format!(
+                "Expected a maximum buffer of at least {} bytes, found {}",
+                self.buffer_bytes, self.max_buffer_bytes
+            )
+            .into());
+        }
+        Ok(self)
+    }
+
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Self {
+            addr: env::var("QUESTDB_ADDR").unwrap_or_else(|_| DEFAULT_ADDR.to_string()),
+            buffer_bytes: parse_or(env::var("QUESTDB_BUFFER_BYTES").ok(), 64 * 1024)?,
+            flush_interval_ms: parse_or(env::var("QUESTDB_FLUSH_INTERVAL_MS").ok(), 1000)?,
+            reconnect_backoff_ms: parse_or(env::var("QUESTDB_RECONNECT_BACKOFF_MS").ok(), 1000)?,
+            max_buffer_bytes: parse_or(env::var("QUESTDB_MAX_BUFFER_BYTES").ok(), 64 << 20)?,
+        }
+        .validate()
+    }
+
+    pub fn init() -> Result<Self, Box<dyn Error>> {
+        Self {
+            addr: option_env!("QUESTDB_ADDR")
+                .unwrap_or(DEFAULT_ADDR)
+                .to_string(),
+            buffer_bytes: parse_or(option_env!("QUESTDB_BUFFER_BYTES"), 64 * 1024)?,
+            flush_interval_ms: parse_or(option_env!("QUESTDB_FLUSH_INTERVAL_MS"), 1000)?,
+            reconnect_backoff_ms: parse_or(option_env!("QUESTDB_RECONNECT_BACKOFF_MS"), 1000)?,
+            max_buffer_bytes: parse_or(option_env!("QUESTDB_MAX_BUFFER_BYTES"), 64 << 20)?,
+        }
+        .validate()
+    }
+
+    #[must_use]
+    pub const fn flush_interval(&self) -> Duration {
+        Duration::from_millis(self.flush_interval_ms)
+    }
+
+    #[must_use]
+    pub const fn reconnect_backoff(&self) -> Duration {
+        Duration::from_millis(self.reconnect_backoff_ms)
+    }
+}
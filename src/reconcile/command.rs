@@ -0,0 +1,126 @@
+use crate::influx::client::Client;
+use crate::model::candle::Candle;
+use crate::reconcile::diff::{diff_event, Report};
+use crate::text::parser::parse_candle;
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+/// Parses the candles of a text file, grouped by event. Lines that are not
+/// candles are ignored.
+pub fn read_candles(path: &str) -> Result<BTreeMap<String, Vec<Candle>>, io::Error> {
+    let reader = BufReader::new(File::open(path)?);
+
+    let mut candles: BTreeMap<String, Vec<Candle>> = BTreeMap::new();
+    for line in reader.lines() {
+        if let Ok(candle) = parse_candle(line?) {
+            candles
+                .entry(candle.event.clone())
+                .or_default()
+                .push(candle);
+        }
+    }
+
+    Ok(candles)
+}
+
+/// Compares the candles of the file at `path` with the ones stored in
+/// `InfluxDB` over the same time range of every event. With `backfill`, the
+/// missing candles are written afterwards.
+pub async fn reconcile(
+    client: &Client,
+    path: &str,
+    backfill: bool,
+) -> Result<Report, Box<dyn Error>> {
+    let mut report = Report::default();
+
+    for (event, candles) in read_candles(path)? {
+        let start = candles
+            .iter()
+            .map(|c| c.timestamp)
+            .min()
+            .unwrap_or_default();
+        let stop = candles
+            .iter()
+            .map(|c| c.timestamp)
+            .max()
+            .unwrap_or_default()
+            + 1;
+
+        let stored = client.query_candles(&event, start, stop).await?;
+        report.events.insert(event, diff_event(&candles, &stored));
+    }
+
+    if backfill {
+        client.insert_candles(report.missing()).await?;
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+#[cfg(not(target_os = "windows"))]
+mod tests {
+    use super::*;
+    use crate::influx::api::ApiVersion;
+    use crate::influx::fake::{config, FakeServer};
+
+    use scopeguard::defer;
+    use std::env;
+    use std::io::Write;
+
+    #[tokio::test]
+    async fn test_reconcile_backfills_missing_candles() {
+        // Arrange
+        let path = env::temp_dir().join(format!(
+            "hts-connector-reconcile-{}.txt",
+            std::process::id()
+        ));
+        let mut file = File::create(&path).expect("Failed to create file");
+        writeln!(
+            file,
+            "2024-04-30 13:21:00  테스트 368.85 368.9 368.75 368.7"
+        )
+        .expect("Failed to write to file");
+        writeln!(file, "2024-05-02 11:00:00  옵션 풋외국인 -13.000000")
+            .expect("Failed to write to file");
+        writeln!(file, "2024-04-30 13:22:00  테스트 368.8 368.8 368.7 368.65")
+            .expect("Failed to write to file");
+
+        defer! {
+            std::fs::remove_file(&path).expect("Failed to remove file");
+        }
+
+        let server = FakeServer::start().expect("Failed to start server");
+        let config = config(server.url(), ApiVersion::V2);
+        let client = Client::new(config).await.expect("Failed to create client");
+        server.respond_with(
+            200,
+            r#"{"results":[{"statement_id":0,"series":[{"name":"candle",
+            "tags":{"event":"테스트"},"columns":["time","open","high","low","close"],
+            "values":[["2024-04-30T04:21:00Z",368.85,368.9,368.7,368.75]]}]}]}"#,
+        );
+
+        // Act
+        let report = reconcile(&client, &path.to_string_lossy(), true)
+            .await
+            .expect("Failed to reconcile");
+
+        // Assert
+        let event = &report.events["테스트"];
+        assert_eq!(event.matched, 1);
+        assert_eq!(event.missing.len(), 1);
+        assert!(event.extra.is_empty());
+        assert!(event.mismatched.is_empty());
+
+        let requests = server.requests();
+        let write = requests.last().expect("No request received");
+        assert_eq!(write.path, "/api/v2/write");
+        assert_eq!(
+            String::from_utf8_lossy(&write.body),
+            "candle,event=테스트 open=368.8,high=368.8,low=368.65,close=368.7 1714450920"
+        );
+    }
+}
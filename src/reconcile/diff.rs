@@ -0,0 +1,144 @@
+use crate::model::candle::Candle;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Differences between the candles of one event in the file and in `InfluxDB`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct EventReport {
+    /// Candles in the file that are not stored.
+    pub missing: Vec<Candle>,
+    /// Candles stored within the range of the file that are not in the file.
+    pub extra: Vec<Candle>,
+    /// Candles stored with the same timestamp but different values, as
+    /// `(file, stored)` pairs.
+    pub mismatched: Vec<(Candle, Candle)>,
+    pub matched: usize,
+}
+
+impl EventReport {
+    #[must_use]
+    pub const fn is_consistent(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+/// Reconciliation result per event, ordered by event name.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Report {
+    pub events: BTreeMap<String, EventReport>,
+}
+
+impl Report {
+    #[must_use]
+    pub fn is_consistent(&self) -> bool {
+        self.events.values().all(EventReport::is_consistent)
+    }
+
+    /// All missing candles, in event order.
+    #[must_use]
+    pub fn missing(&self) -> Vec<Candle> {
+        self.events
+            .values()
+            .flat_map(|event| event.missing.iter().cloned())
+            .collect()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (event, report) in &self.events {
+            writeln!(
+                f,
+                "{event}: {} matched, {} missing, {} extra, {} mismatched",
+                report.matched,
+                report.missing.len(),
+                report.extra.len(),
+                report.mismatched.len()
+            )?;
+            for candle in &report.missing {
+                writeln!(f, "  missing    {candle:?}")?;
+            }
+            for candle in &report.extra {
+                writeln!(f, "  extra      {candle:?}")?;
+            }
+            for (file, stored) in &report.mismatched {
+                writeln!(f, "  mismatched {file:?} != {stored:?}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compares the candles of one event, matching them by timestamp. When the
+/// file has several candles with the same timestamp the last one wins, as it
+/// would when written to `InfluxDB`.
+#[must_use]
+pub fn diff_event(file: &[Candle], stored: &[Candle]) -> EventReport {
+    let file: BTreeMap<u128, &Candle> = file.iter().map(|c| (c.timestamp, c)).collect();
+    let mut stored: HashMap<u128, &Candle> = stored.iter().map(|c| (c.timestamp, c)).collect();
+
+    let mut report = EventReport::default();
+    for (timestamp, candle) in file {
+        match stored.remove(&timestamp) {
+            None => report.missing.push(candle.clone()),
+            Some(other) if other == candle => report.matched += 1,
+            Some(other) => report.mismatched.push((candle.clone(), other.clone())),
+        }
+    }
+
+    let mut extra: Vec<Candle> = stored.into_values().cloned().collect();
+    extra.sort_by_key(|candle| candle.timestamp);
+    report.extra = extra;
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::candle::tests::candle;
+
+    #[test]
+    fn test_diff_event() {
+        // Arrange
+        let file = vec![
+            candle("테스트", 1, 1.0),
+            candle("테스트", 2, 2.0),
+            candle("테스트", 3, 3.0),
+        ];
+        let stored = vec![
+            candle("테스트", 1, 1.0),
+            candle("테스트", 3, 3.5),
+            candle("테스트", 4, 4.0),
+        ];
+
+        // Act
+        let report = diff_event(&file, &stored);
+
+        // Assert
+        assert_eq!(
+            report,
+            EventReport {
+                missing: vec![candle("테스트", 2, 2.0)],
+                extra: vec![candle("테스트", 4, 4.0)],
+                mismatched: vec![(candle("테스트", 3, 3.0), candle("테스트", 3, 3.5))],
+                matched: 1,
+            }
+        );
+        assert!(!report.is_consistent());
+    }
+
+    #[test]
+    fn test_diff_event_consistent() {
+        // Arrange
+        let file = vec![candle("테스트", 1, 1.0), candle("테스트", 2, 2.0)];
+
+        // Act
+        let report = diff_event(&file, &file);
+
+        // Assert
+        assert!(report.is_consistent());
+        assert_eq!(report.matched, 2);
+    }
+}
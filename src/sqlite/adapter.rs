@@ -0,0 +1,85 @@
+use crate::model::{candle::Candle, indicator::Indicator};
+use crate::sqlite::config::Config;
+use crate::sqlite::store::Store;
+use crate::text::reader::Handler;
+
+use std::io;
+use std::sync::{Mutex, MutexGuard};
+
+/// A [`Handler`] upserting every record into an embedded SQLite [`Store`],
+/// for setups without a database server.
+pub struct SqliteHandler {
+    store: Mutex<Store>,
+}
+
+impl SqliteHandler {
+    /// Opens the database, creating its tables if needed.
+    pub fn new(config: &Config) -> Result<Self, io::Error> {
+        let store = Store::open(&config.path)
+            .map_err(|e| io::Error::other(format!("Failed to open {}: {e}", config.path)))?;
+        Ok(Self {
+            store: Mutex::new(store),
+        })
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Store>, io::Error> {
+        self.store
+            .lock()
+            .map_err(|e| io::Error::other(format!("Failed to lock store: {e}")))
+    }
+}
+
+impl Handler for SqliteHandler {
+    fn handle_candle(&self, candle: Candle) -> Result<(), io::Error> {
+        self.lock()?
+            .insert_candle(&candle)
+            .map_err(io::Error::other)
+    }
+
+    fn handle_indicator(&self, indicator: Indicator) -> Result<(), io::Error> {
+        self.lock()?
+            .insert_indicator(&indicator)
+            .map_err(io::Error::other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn test_records_are_queryable_from_another_connection() {
+        // Arrange
+        let dir = env::temp_dir().join(format!("hts-connector-sqlite-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).expect("Failed to create directory");
+        let config = Config {
+            path: dir.join("hts.db").to_string_lossy().to_string(),
+        };
+        let handler = SqliteHandler::new(&config).expect("Failed to create handler");
+        let candle = Candle {
+            timestamp: 1_714_450_860_000_000_000,
+            event: "테스트".to_string(),
+            open: 368.85,
+            high: 368.9,
+            low: 368.7,
+            close: 368.75,
+        };
+
+        // Act
+        handler
+            .handle_candle(candle.clone())
+            .expect("Failed to handle candle");
+        let stored = Store::open(&config.path)
+            .and_then(|store| store.query_candles("테스트", 0, u128::MAX))
+            .expect("Failed to query candles");
+
+        // Assert
+        assert_eq!(stored, vec![candle]);
+
+        drop(handler);
+        fs::remove_dir_all(&dir).expect("Failed to remove directory");
+    }
+}
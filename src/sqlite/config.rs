@@ -0,0 +1,37 @@
+use envconfig::Envconfig;
+use serde::Deserialize;
+
+use std::env;
+
+/// Database file used when `SQLITE_PATH` is not set.
+pub const DEFAULT_PATH: &str = "hts.db";
+
+#[derive(Debug, Clone, Deserialize, Envconfig)]
+pub struct Config {
+    /// Database file, created with its tables if it does not exist.
+    pub path: String,
+}
+
+impl Config {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            path: env::var("SQLITE_PATH").unwrap_or_else(|_| DEFAULT_PATH.to_string()),
+        }
+    }
+
+    #[must_use]
+    pub fn init() -> Self {
+        Self {
+            path: option_env!("SQLITE_PATH")
+                .unwrap_or(DEFAULT_PATH)
+                .to_string(),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
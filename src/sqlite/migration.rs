@@ -0,0 +1,47 @@
+//! Versioned schema migrations, recorded in the `user_version` pragma so
+//! every migration is applied once per database file.
+
+use rusqlite::Connection;
+
+/// Migrations in the order they are applied; a migration's version is its
+/// position plus one.
+const MIGRATIONS: &[&str] = &[include_str!("migrations/0001_create_tables.sql")];
+
+/// Applies the migrations not applied yet, each in its own transaction.
+pub fn migrate(connection: &mut Connection) -> Result<(), rusqlite::Error> {
+    let applied: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    for (version, sql) in MIGRATIONS.iter().enumerate().skip(applied) {
+        println!("Applying migration {}", version + 1);
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(sql)?;
+        transaction.pragma_update(None, "user_version", version + 1)?;
+        transaction.commit()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_twice() {
+        // Arrange
+        let mut connection = Connection::open_in_memory().expect("Failed to open database");
+
+        // Act
+        let first = migrate(&mut connection);
+        let second = migrate(&mut connection);
+
+        // Assert
+        assert!(first.is_ok());
+        assert!(second.is_ok());
+
+        let version: usize = connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .expect("Failed to read version");
+        assert_eq!(version, MIGRATIONS.len());
+    }
+}
@@ -0,0 +1,22 @@
+-- Times are nanoseconds since the Unix epoch.
+CREATE TABLE candle (
+    event TEXT NOT NULL,
+    time INTEGER NOT NULL,
+    open REAL NOT NULL,
+    high REAL NOT NULL,
+    low REAL NOT NULL,
+    close REAL NOT NULL,
+    PRIMARY KEY (event, time)
+) WITHOUT ROWID;
+
+CREATE INDEX candle_time ON candle (time);
+
+CREATE TABLE indicator (
+    event TEXT NOT NULL,
+    property TEXT NOT NULL,
+    time INTEGER NOT NULL,
+    value INTEGER NOT NULL,
+    PRIMARY KEY (event, property, time)
+) WITHOUT ROWID;
+
+CREATE INDEX indicator_time ON indicator (time);
@@ -0,0 +1,250 @@
+use crate::model::{candle::Candle, indicator::Indicator};
+use crate::sqlite::migration::migrate;
+use crate::text::watermark::Watermarks;
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, Row};
+
+use std::path::Path;
+use std::time::Duration;
+
+/// Time a statement waits for another connection to release the database.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn to_sql(timestamp: u128) -> Result<i64, rusqlite::Error> {
+    i64::try_from(timestamp).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+/// A query bound; bounds past what is stored are clamped.
+fn bound(timestamp: u128) -> i64 {
+    i64::try_from(timestamp).unwrap_or(i64::MAX)
+}
+
+fn from_sql(row: &Row, index: usize) -> Result<u128, rusqlite::Error> {
+    let time: i64 = row.get(index)?;
+    u128::try_from(time)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Integer, Box::new(e)))
+}
+
+fn to_candle(row: &Row) -> Result<Candle, rusqlite::Error> {
+    Ok(Candle {
+        event: row.get(0)?,
+        timestamp: from_sql(row, 1)?,
+        open: row.get(2)?,
+        high: row.get(3)?,
+        low: row.get(4)?,
+        close: row.get(5)?,
+    })
+}
+
+fn to_indicator(row: &Row) -> Result<Indicator, rusqlite::Error> {
+    Ok(Indicator {
+        event: row.get(0)?,
+        property: row.get(1)?,
+        timestamp: from_sql(row, 2)?,
+        value: row.get(3)?,
+    })
+}
+
+/// Candles and indicators in an embedded SQLite database, keyed on the
+/// event (and property) and time so that storing a record again replaces
+/// it.
+///
+/// The database is in WAL mode, so other processes can open the same file
+/// and query it while the connector writes.
+pub struct Store {
+    connection: Connection,
+}
+
+impl Store {
+    /// Opens the database at `path`, creating it and applying the
+    /// migrations if needed. `:memory:` opens a private in-memory database.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, rusqlite::Error> {
+        let mut connection = Connection::open(path)?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        connection.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+        connection.pragma_update(None, "synchronous", "NORMAL")?;
+        migrate(&mut connection)?;
+        Ok(Self { connection })
+    }
+
+    pub fn insert_candle(&self, candle: &Candle) -> Result<(), rusqlite::Error> {
+        self.connection
+            .prepare_cached(
+                "INSERT INTO candle (event, time, open, high, low, close) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6) \
+                 ON CONFLICT (event, time) DO UPDATE SET open = excluded.open, \
+                 high = excluded.high, low = excluded.low, close = excluded.close",
+            )?
+            .execute(params![
+                candle.event,
+                to_sql(candle.timestamp)?,
+                candle.open,
+                candle.high,
+                candle.low,
+                candle.close,
+            ])?;
+        Ok(())
+    }
+
+    pub fn insert_indicator(&self, indicator: &Indicator) -> Result<(), rusqlite::Error> {
+        self.connection
+            .prepare_cached(
+                "INSERT INTO indicator (event, property, time, value) VALUES (?1, ?2, ?3, ?4) \
+                 ON CONFLICT (event, property, time) DO UPDATE SET value = excluded.value",
+            )?
+            .execute(params![
+                indicator.event,
+                indicator.property,
+                to_sql(indicator.timestamp)?,
+                indicator.value,
+            ])?;
+        Ok(())
+    }
+
+    /// Candles of `event` with `start <= timestamp < stop`, in nanoseconds,
+    /// ordered by time.
+    pub fn query_candles(
+        &self,
+        event: &str,
+        start: u128,
+        stop: u128,
+    ) -> Result<Vec<Candle>, rusqlite::Error> {
+        self.connection
+            .prepare_cached(
+                "SELECT event, time, open, high, low, close FROM candle \
+                 WHERE event = ?1 AND time >= ?2 AND time < ?3 ORDER BY time",
+            )?
+            .query_map(params![event, bound(start), bound(stop)], to_candle)?
+            .collect()
+    }
+
+    /// The most recent candle of every event, ordered by time.
+    pub fn latest_candles(&self) -> Result<Vec<Candle>, rusqlite::Error> {
+        self.connection
+            .prepare_cached(
+                "SELECT event, time, open, high, low, close FROM candle \
+                 JOIN (SELECT event, max(time) AS time FROM candle GROUP BY event) \
+                 USING (event, time) ORDER BY time",
+            )?
+            .query_map([], to_candle)?
+            .collect()
+    }
+
+    /// Values of the `property` indicator of `event` with
+    /// `start <= timestamp < stop`, in nanoseconds, ordered by time.
+    pub fn query_indicators(
+        &self,
+        event: &str,
+        property: &str,
+        start: u128,
+        stop: u128,
+    ) -> Result<Vec<Indicator>, rusqlite::Error> {
+        self.connection
+            .prepare_cached(
+                "SELECT event, property, time, value FROM indicator \
+                 WHERE event = ?1 AND property = ?2 AND time >= ?3 AND time < ?4 \
+                 ORDER BY time",
+            )?
+            .query_map(
+                params![event, property, bound(start), bound(stop)],
+                to_indicator,
+            )?
+            .collect()
+    }
+
+    /// The most recent value of every indicator series, ordered by time.
+    pub fn latest_indicators(&self) -> Result<Vec<Indicator>, rusqlite::Error> {
+        self.connection
+            .prepare_cached(
+                "SELECT event, property, time, value FROM indicator \
+                 JOIN (SELECT event, property, max(time) AS time FROM indicator \
+                 GROUP BY event, property) USING (event, property, time) ORDER BY time",
+            )?
+            .query_map([], to_indicator)?
+            .collect()
+    }
+
+    /// The latest stored timestamp of every candle and indicator series.
+    pub fn watermarks(&self) -> Result<Watermarks, rusqlite::Error> {
+        let mut watermarks = Watermarks::new();
+        for candle in self.latest_candles()? {
+            watermarks.observe_candle(&candle);
+        }
+        for indicator in self.latest_indicators()? {
+            watermarks.observe_indicator(&indicator);
+        }
+        Ok(watermarks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::candle::tests::candle;
+
+    #[test]
+    fn test_upsert_and_query_candles() {
+        // Arrange
+        let store = Store::open(":memory:").expect("Failed to open store");
+
+        // Act
+        for candle in [
+            candle("A", 1_000, 1.0),
+            candle("A", 2_000, 2.0),
+            candle("A", 2_000, 2.5),
+            candle("A", 3_000, 3.0),
+            candle("B", 2_000, 5.0),
+        ] {
+            store
+                .insert_candle(&candle)
+                .expect("Failed to insert candle");
+        }
+        let range = store
+            .query_candles("A", 1_500, 3_000)
+            .expect("Failed to query candles");
+        let latest = store.latest_candles().expect("Failed to query candles");
+
+        // Assert
+        assert_eq!(range, vec![candle("A", 2_000, 2.5)]);
+        assert_eq!(
+            latest,
+            vec![candle("B", 2_000, 5.0), candle("A", 3_000, 3.0)]
+        );
+    }
+
+    #[test]
+    fn test_upsert_and_query_indicators() {
+        // Arrange
+        let store = Store::open(":memory:").expect("Failed to open store");
+        let indicator = |property: &str, timestamp: u128, value: i64| Indicator {
+            timestamp,
+            event: "A".to_string(),
+            property: property.to_string(),
+            value,
+        };
+
+        // Act
+        for indicator in [
+            indicator("rsi", 1_000, 70),
+            indicator("rsi", 1_000, 75),
+            indicator("rsi", 2_000, 80),
+            indicator("macd", 1_500, -3),
+        ] {
+            store
+                .insert_indicator(&indicator)
+                .expect("Failed to insert indicator");
+        }
+        let range = store
+            .query_indicators("A", "rsi", 0, u128::MAX)
+            .expect("Failed to query indicators");
+        let watermarks = store.watermarks().expect("Failed to query watermarks");
+
+        // Assert
+        assert_eq!(
+            range,
+            vec![indicator("rsi", 1_000, 75), indicator("rsi", 2_000, 80)]
+        );
+        assert!(watermarks.contains_indicator(&indicator("macd", 1_500, -3)));
+        assert!(!watermarks.contains_indicator(&indicator("rsi", 2_001, 0)));
+    }
+}
@@ -1,3 +1,5 @@
+use crate::config::parse_or;
+use crate::text::encoding::SourceEncoding;
 use envconfig::Envconfig;
 use serde::Deserialize;
 use std::error::Error;
@@ -7,6 +9,9 @@ use std::env;
 #[derive(Debug, Deserialize, Envconfig)]
 pub struct Config {
     pub path: String,
+    /// Encoding of the file, such as `cp949` for the raw HTS export.
+    #[envconfig(from = "TEXT_FILE_ENCODING", default = "utf-8")]
+    pub encoding: SourceEncoding,
 }
 
 impl Config {
@@ -19,12 +24,14 @@ impl Config {
     pub fn new() -> Result<Self, Box<dyn Error>> {
         Ok(Self {
             path: Self::retrieve_env_var("TEXT_FILE_PATH")?,
+            encoding: parse_or(env::var("TEXT_FILE_ENCODING").ok(), SourceEncoding::default())?,
         })
     }
 
     pub fn init() -> Result<Self, Box<dyn Error>> {
         Ok(Self {
             path: env!("TEXT_FILE_PATH").to_string(),
+            encoding: parse_or(option_env!("TEXT_FILE_ENCODING"), SourceEncoding::default())?,
         })
     }
 }
@@ -0,0 +1,77 @@
+use encoding_rs::{Encoding, EUC_KR, UTF_8};
+use serde::{Deserialize, Deserializer};
+
+use std::borrow::Cow;
+use std::error::Error;
+use std::str::FromStr;
+
+/// Character encoding of the text file, UTF-8 unless configured otherwise.
+///
+/// The HTS exports CP949, which is read with the WHATWG `EUC-KR` decoder,
+/// a superset of CP949. Bytes that are invalid in the encoding are replaced
+/// rather than failing the line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceEncoding(&'static Encoding);
+
+impl SourceEncoding {
+    #[must_use]
+    pub fn decode(self, bytes: &[u8]) -> Cow<'_, str> {
+        self.0.decode_without_bom_handling(bytes).0
+    }
+}
+
+impl Default for SourceEncoding {
+    fn default() -> Self {
+        Self(UTF_8)
+    }
+}
+
+impl FromStr for SourceEncoding {
+    type Err = Box<dyn Error>;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        if input.eq_ignore_ascii_case("cp949") {
+            return Ok(Self(EUC_KR));
+        }
+        Encoding::for_label(input.trim().as_bytes())
+            .map(Self)
+            .ok_or_else(|| {
+                format!("Expected an encoding such as utf-8 or cp949, found {input}").into()
+            })
+    }
+}
+
+impl<'de> Deserialize<'de> for SourceEncoding {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_cp949() {
+        // Arrange
+        let encoding: SourceEncoding = "cp949".parse().expect("Failed to parse encoding");
+        let bytes = [0xC5, 0xD7, 0xBD, 0xBA, 0xC6, 0xAE];
+
+        // Act
+        let text = encoding.decode(&bytes);
+
+        // Assert
+        assert_eq!(text, "테스트");
+    }
+
+    #[test]
+    fn test_from_str_rejects_unknown_encoding() {
+        // Act
+        let encoding = "klingon".parse::<SourceEncoding>();
+
+        // Assert
+        assert!(encoding.is_err());
+    }
+}
@@ -0,0 +1,75 @@
+use crate::model::{candle::Candle, indicator::Indicator};
+use crate::text::reader::Handler;
+
+use std::io;
+
+/// A [`Handler`] passing every record to several handlers in order.
+///
+/// Handling stops at the first handler that fails, so a record the reader
+/// retries is not handed twice to the handlers after it. Put the handler
+/// whose failures should be retried first.
+pub struct FanOut {
+    handlers: Vec<Box<dyn Handler>>,
+}
+
+impl FanOut {
+    #[must_use]
+    pub fn new(handlers: Vec<Box<dyn Handler>>) -> Self {
+        Self { handlers }
+    }
+}
+
+impl Handler for FanOut {
+    fn handle_candle(&self, candle: Candle) -> Result<(), io::Error> {
+        self.handlers
+            .iter()
+            .try_for_each(|handler| handler.handle_candle(candle.clone()))
+    }
+
+    fn handle_indicator(&self, indicator: Indicator) -> Result<(), io::Error> {
+        self.handlers
+            .iter()
+            .try_for_each(|handler| handler.handle_indicator(indicator.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::reader::MockHandler;
+
+    #[test]
+    fn test_fan_out_stops_at_failure() {
+        // Arrange
+        let mut first = MockHandler::new();
+        first.expect_handle_candle().times(1).returning(|_| Ok(()));
+        first
+            .expect_handle_indicator()
+            .times(1)
+            .returning(|_| Err(io::Error::other("connection refused")));
+        let mut second = MockHandler::new();
+        second.expect_handle_candle().times(1).returning(|_| Ok(()));
+        second.expect_handle_indicator().times(0);
+        let fan_out = FanOut::new(vec![Box::new(first), Box::new(second)]);
+
+        // Act
+        let candle = fan_out.handle_candle(Candle {
+            timestamp: 1_714_450_860_000_000_000,
+            event: "테스트".to_string(),
+            open: 368.85,
+            high: 368.9,
+            low: 368.7,
+            close: 368.75,
+        });
+        let indicator = fan_out.handle_indicator(Indicator {
+            timestamp: 1_714_450_860_000_000_000,
+            event: "테스트".to_string(),
+            property: "rsi".to_string(),
+            value: 70,
+        });
+
+        // Assert
+        assert!(candle.is_ok());
+        assert!(indicator.is_err());
+    }
+}
@@ -1,3 +1,6 @@
 pub mod config;
+pub mod encoding;
+pub mod fanout;
 pub mod parser;
 pub mod reader;
+pub mod watermark;
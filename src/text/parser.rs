@@ -5,6 +5,22 @@ use std::error::Error;
 
 use crate::model::{candle::Candle, indicator::Indicator};
 
+/// Converts a Seoul local date and time, with optional fractional seconds,
+/// into nanoseconds since the Unix epoch.
+fn parse_timestamp(date: &str, time: &str) -> Result<u128, Box<dyn Error>> {
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")?;
+    let time = NaiveTime::parse_from_str(time, "%H:%M:%S%.f")?;
+    let datetime: NaiveDateTime = NaiveDateTime::new(date, time);
+
+    let nanos = datetime
+        .and_local_timezone(Seoul)
+        .single()
+        .and_then(|datetime| datetime.timestamp_nanos_opt())
+        .ok_or_else(|| format!("Timestamp out of range: {datetime}"))?;
+
+    u128::try_from(nanos).map_err(|_| format!("Timestamp before epoch: {datetime}").into())
+}
+
 fn parse_f64_to_i64(input: f64) -> Result<i64, Box<dyn Error>> {
     if input.fract() != 0.0 {
         return Err(format!("Expected integer, found {}", input).into());
@@ -20,12 +36,8 @@ pub fn parse_candle(input: String) -> Result<Candle, Box<dyn Error>> {
         return Err(format!("Expected 7 parts, found {}", parts.len()).into());
     }
 
-    let date = NaiveDate::parse_from_str(&parts[0], "%Y-%m-%d").expect("Failed to parse date");
-    let time = NaiveTime::parse_from_str(&parts[1], "%H:%M:%S").expect("Failed to parse time");
-    let datetime: NaiveDateTime = NaiveDateTime::new(date, time);
-
     let candle = Candle {
-        timestamp: datetime.and_local_timezone(Seoul).unwrap().timestamp() as u128,
+        timestamp: parse_timestamp(&parts[0], &parts[1])?,
         event: parts[2].clone(),
         open: parts[3].parse().expect("Failed to parse open"),
         high: parts[4].parse().expect("Failed to parse high"),
@@ -43,14 +55,10 @@ pub fn parse_indicator(input: String) -> Result<Indicator, Box<dyn Error>> {
         return Err(format!("Expected 5 parts, found {}", parts.len()).into());
     }
 
-    let date = NaiveDate::parse_from_str(&parts[0], "%Y-%m-%d")?;
-    let time = NaiveTime::parse_from_str(&parts[1], "%H:%M:%S")?;
-    let datetime: NaiveDateTime = NaiveDateTime::new(date, time);
-
     let float_value: f64 = parts[4].parse()?;
 
     let indicator = Indicator {
-        timestamp: datetime.and_local_timezone(Seoul).unwrap().timestamp() as u128,
+        timestamp: parse_timestamp(&parts[0], &parts[1])?,
         event: parts[2].clone(),
         property: parts[3].clone(),
         value: parse_f64_to_i64(float_value)?,
@@ -60,6 +68,7 @@ pub fn parse_indicator(input: String) -> Result<Indicator, Box<dyn Error>> {
 }
 
 #[cfg(test)]
+#[allow(clippy::unreadable_literal)]
 mod tests {
     use super::*;
 
@@ -93,7 +102,7 @@ mod tests {
         // Arrange
         let input = "2021-01-01 00:00:00 BTCUSDT 100.0 200.0 150.0 50.0".to_string();
         let expect = Candle {
-            timestamp: 1609426800,
+            timestamp: 1_609_426_800_000_000_000,
             event: "BTCUSDT".to_string(),
             open: 100.0,
             high: 200.0,
@@ -109,6 +118,21 @@ mod tests {
         assert_eq!(result.expect("Failed to parse candle"), expect);
     }
 
+    #[test]
+    fn test_parse_candle_subsecond_success() {
+        // Arrange
+        let input = "2021-01-01 00:00:00.250 BTCUSDT 100.0 200.0 150.0 50.0".to_string();
+
+        // Act
+        let result = parse_candle(input);
+
+        // Assert
+        assert_eq!(
+            result.expect("Failed to parse candle").timestamp,
+            1_609_426_800_250_000_000
+        );
+    }
+
     #[test]
     fn test_parse_candle_fail() {
         // Arrange
@@ -126,7 +150,7 @@ mod tests {
         // Arrange
         let input = "2021-01-01 00:00:00 이벤트 속성 -70.0".to_string();
         let expect = Indicator {
-            timestamp: 1609426800,
+            timestamp: 1_609_426_800_000_000_000,
             event: "이벤트".to_string(),
             property: "속성".to_string(),
             value: -70,
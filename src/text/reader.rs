@@ -1,11 +1,15 @@
 use std::fs::{File, OpenOptions};
 use std::io::{self, BufRead, BufReader, Seek};
+use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant};
 
+use crate::influx::health::Health;
 use crate::model::{candle::Candle, indicator::Indicator};
 use crate::text::config::Config;
+use crate::text::encoding::SourceEncoding;
 use crate::text::parser::{parse_candle, parse_indicator};
+use crate::text::watermark::Watermarks;
 
 use mockall::automock;
 
@@ -15,9 +19,15 @@ pub trait Handler: Send + Sync {
     fn handle_indicator(&self, indicator: Indicator) -> Result<(), io::Error>;
 }
 
+const HEALTH_POLL: Duration = Duration::from_millis(500);
+
 pub struct Reader {
     path: String,
+    encoding: SourceEncoding,
     handler: Box<dyn Handler>,
+    watermarks: Watermarks,
+    health: Option<Arc<Health>>,
+    echo: bool,
 }
 
 impl Reader {
@@ -28,10 +38,60 @@ impl Reader {
         };
         Ok(Self {
             path: config.path,
+            encoding: config.encoding,
             handler,
+            watermarks: Watermarks::new(),
+            health: None,
+            echo: true,
         })
     }
 
+    /// Skips candles and indicators at or before the given watermarks, so that
+    /// lines already stored by a previous run are not handled again.
+    #[must_use]
+    pub fn with_watermarks(mut self, watermarks: Watermarks) -> Self {
+        self.watermarks = watermarks;
+        self
+    }
+
+    /// Pauses while the connection is down instead of failing lines, retrying
+    /// a line that failed because the connection went down. Something must
+    /// mark the connection up again, such as the health monitor.
+    #[must_use]
+    pub fn with_health(mut self, health: Arc<Health>) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    /// Stops printing the records read, skipped or failing to parse, so that
+    /// stdout is left to a handler writing its output there.
+    #[must_use]
+    pub const fn quiet(mut self) -> Self {
+        self.echo = false;
+        self
+    }
+
+    fn handle<T: Clone>(
+        &self,
+        record: T,
+        handle: impl Fn(T) -> Result<(), io::Error>,
+    ) -> Result<(), io::Error> {
+        let Some(health) = &self.health else {
+            return handle(record);
+        };
+
+        loop {
+            if !health.is_up() {
+                println!("Waiting for InfluxDB to come back up");
+                health.wait_until_up(HEALTH_POLL);
+            }
+            match handle(record.clone()) {
+                Err(_) if !health.is_up() => continue,
+                result => return result,
+            }
+        }
+    }
+
     #[allow(clippy::unreadable_literal)]
     #[allow(clippy::cast_sign_loss)]
     pub fn read_and_follow(&self, duration: Duration) -> Result<(), io::Error> {
@@ -44,27 +104,45 @@ impl Reader {
             let mut eof_reached = false;
 
             while let Some(line) = {
-                let mut buffer = String::new();
-                match reader.read_line(&mut buffer) {
+                let mut buffer = Vec::new();
+                match reader.read_until(b'\n', &mut buffer) {
                     Ok(0) => {
                         eof_reached = true;
                         None
                     }
-                    Ok(_) => Some(buffer),
+                    Ok(_) => Some(self.encoding.decode(&buffer).into_owned()),
                     Err(e) => return Err(e),
                 }
             } {
                 match (parse_candle(line.clone()), parse_indicator(line.clone())) {
+                    (Ok(candle), _) if self.watermarks.contains_candle(&candle) => {
+                        if self.echo {
+                            println!("Skipping stored candle: {:?}", candle);
+                        }
+                    }
+                    (_, Ok(indicator)) if self.watermarks.contains_indicator(&indicator) => {
+                        if self.echo {
+                            println!("Skipping stored indicator: {:?}", indicator);
+                        }
+                    }
                     (Ok(candle), _) => {
-                        println!("{:?}", candle);
-                        self.handler.handle_candle(candle)?;
+                        if self.echo {
+                            println!("{:?}", candle);
+                        }
+                        self.handle(candle, |candle| self.handler.handle_candle(candle))?;
                     }
                     (_, Ok(indicator)) => {
-                        println!("{:?}", indicator);
-                        self.handler.handle_indicator(indicator)?;
+                        if self.echo {
+                            println!("{:?}", indicator);
+                        }
+                        self.handle(indicator, |indicator| {
+                            self.handler.handle_indicator(indicator)
+                        })?;
                     }
                     (Err(_), Err(_)) => {
-                        println!("Failed to parse line: {}", line.trim_end());
+                        if self.echo {
+                            println!("Failed to parse line: {}", line.trim_end());
+                        }
                     }
                 }
             }
@@ -139,6 +217,40 @@ mod tests {
         std::fs::remove_file(TEXT_FILE_PATH).expect("Failed to remove file");
     }
 
+    #[test]
+    fn test_read_cp949_file() {
+        // Arrange
+        let path = env::temp_dir().join(format!("hts-connector-cp949-{}.txt", std::process::id()));
+        let (line, _, _) = encoding_rs::EUC_KR
+            .encode("2024-04-30 13:21:00  테스트 368.850000 368.900000 368.750000 368.700000\n");
+        std::fs::write(&path, line).expect("Failed to write file");
+
+        defer! {
+            std::fs::remove_file(&path).expect("Failed to remove file");
+        }
+
+        let mut mock_handler = MockHandler::new();
+        mock_handler
+            .expect_handle_candle()
+            .withf(|candle| candle.event == "테스트")
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let config = Config {
+            path: path.to_string_lossy().to_string(),
+            encoding: "cp949".parse().expect("Failed to parse encoding"),
+        };
+        let reader = Reader::new(config, Box::new(mock_handler))
+            .expect("Failed to create reader")
+            .quiet();
+
+        // Act
+        let result = reader.read_and_follow(Duration::ZERO);
+
+        // Assert
+        assert!(result.is_ok());
+    }
+
     #[ignore]
     #[test]
     #[allow(clippy::unreadable_literal)]
@@ -168,7 +280,7 @@ mod tests {
 
         let candles: [Candle; 3] = [
             Candle {
-                timestamp: 1714450860,
+                timestamp: 1_714_450_860_000_000_000,
                 event: "테스트".to_string(),
                 open: 368.850000,
                 high: 368.900000,
@@ -176,7 +288,7 @@ mod tests {
                 low: 368.700000,
             },
             Candle {
-                timestamp: 1714450920,
+                timestamp: 1_714_450_920_000_000_000,
                 event: "테스트".to_string(),
                 open: 368.800000,
                 high: 368.800000,
@@ -184,7 +296,7 @@ mod tests {
                 low: 368.650000,
             },
             Candle {
-                timestamp: 1714450980,
+                timestamp: 1_714_450_980_000_000_000,
                 event: "테스트".to_string(),
                 open: 368.750000,
                 high: 368.850000,
@@ -249,13 +361,13 @@ mod tests {
 
         let indicators: [Indicator; 2] = [
             Indicator {
-                timestamp: 1714615200,
+                timestamp: 1_714_615_200_000_000_000,
                 event: "옵션".to_string(),
                 property: "풋외국인".to_string(),
                 value: -13,
             },
             Indicator {
-                timestamp: 1714615260,
+                timestamp: 1_714_615_260_000_000_000,
                 event: "옵션".to_string(),
                 property: "풋외국인".to_string(),
                 value: -14,
@@ -316,7 +428,7 @@ mod tests {
 
         let candles: [Candle; 2] = [
             Candle {
-                timestamp: 1714450860,
+                timestamp: 1_714_450_860_000_000_000,
                 event: "테스트".to_string(),
                 open: 368.850000,
                 high: 368.900000,
@@ -324,7 +436,7 @@ mod tests {
                 low: 368.700000,
             },
             Candle {
-                timestamp: 1714450920,
+                timestamp: 1_714_450_920_000_000_000,
                 event: "테스트".to_string(),
                 open: 368.800000,
                 high: 368.800000,
@@ -335,13 +447,13 @@ mod tests {
 
         let indicators: [Indicator; 2] = [
             Indicator {
-                timestamp: 1714615200,
+                timestamp: 1_714_615_200_000_000_000,
                 event: "옵션".to_string(),
                 property: "풋외국인".to_string(),
                 value: -13,
             },
             Indicator {
-                timestamp: 1714615260,
+                timestamp: 1_714_615_260_000_000_000,
                 event: "옵션".to_string(),
                 property: "풋외국인".to_string(),
                 value: -14,
@@ -380,4 +492,118 @@ mod tests {
         // Assert
         assert!(result.is_ok());
     }
+
+    #[test]
+    #[allow(clippy::unreadable_literal)]
+    fn test_read_skips_lines_at_or_before_watermarks() {
+        // Arrange
+        let path = env::temp_dir().join(format!("hts-connector-resume-{}.txt", std::process::id()));
+        let datas: [&str; 4] = [
+            "2024-04-30 13:21:00  테스트 368.850000 368.900000 368.750000 368.700000",
+            "2024-05-02 11:00:00  옵션 풋외국인 -13.000000",
+            "2024-04-30 13:22:00  테스트 368.800000 368.800000 368.700000 368.650000",
+            "2024-05-02 11:01:00  옵션 풋외국인 -14.000000",
+        ];
+        let mut file = File::create(&path).expect("Failed to create file");
+        for data in &datas {
+            writeln!(file, "{data}").expect("Failed to write to file");
+        }
+
+        defer! {
+            std::fs::remove_file(&path).expect("Failed to remove file");
+        }
+
+        let stored_candle = Candle {
+            timestamp: 1_714_450_860_000_000_000,
+            event: "테스트".to_string(),
+            open: 368.850000,
+            high: 368.900000,
+            close: 368.750000,
+            low: 368.700000,
+        };
+        let stored_indicator = Indicator {
+            timestamp: 1_714_615_200_000_000_000,
+            event: "옵션".to_string(),
+            property: "풋외국인".to_string(),
+            value: -13,
+        };
+        let mut watermarks = Watermarks::new();
+        watermarks.observe_candle(&stored_candle);
+        watermarks.observe_indicator(&stored_indicator);
+
+        let mut mock_handler = MockHandler::new();
+        mock_handler
+            .expect_handle_candle()
+            .withf(|candle| candle.timestamp == 1_714_450_920_000_000_000)
+            .times(1)
+            .returning(|_| Ok(()));
+        mock_handler
+            .expect_handle_indicator()
+            .withf(|indicator| indicator.timestamp == 1_714_615_260_000_000_000)
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let config = Config {
+            path: path.to_string_lossy().to_string(),
+            encoding: SourceEncoding::default(),
+        };
+        let reader = Reader::new(config, Box::new(mock_handler))
+            .expect("Failed to create reader")
+            .with_watermarks(watermarks);
+
+        // Act
+        let result = reader.read_and_follow(Duration::ZERO);
+
+        // Assert
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_read_retries_line_after_connection_comes_back() {
+        // Arrange
+        let path = env::temp_dir().join(format!("hts-connector-health-{}.txt", std::process::id()));
+        let mut file = File::create(&path).expect("Failed to create file");
+        writeln!(file, "2024-05-02 11:00:00  옵션 풋외국인 -13.000000")
+            .expect("Failed to write to file");
+
+        defer! {
+            std::fs::remove_file(&path).expect("Failed to remove file");
+        }
+
+        let health = Arc::new(Health::default());
+        let failing = Arc::clone(&health);
+        let recovering = Arc::clone(&health);
+
+        let mut mock_handler = MockHandler::new();
+        mock_handler
+            .expect_handle_indicator()
+            .times(1)
+            .returning(move |_| {
+                failing.record_failure();
+                let health = Arc::clone(&recovering);
+                thread::spawn(move || {
+                    thread::sleep(Duration::from_millis(100));
+                    health.record_success();
+                });
+                Err(io::Error::other("connection refused"))
+            });
+        mock_handler
+            .expect_handle_indicator()
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let config = Config {
+            path: path.to_string_lossy().to_string(),
+            encoding: SourceEncoding::default(),
+        };
+        let reader = Reader::new(config, Box::new(mock_handler))
+            .expect("Failed to create reader")
+            .with_health(health);
+
+        // Act
+        let result = reader.read_and_follow(Duration::ZERO);
+
+        // Assert
+        assert!(result.is_ok());
+    }
 }
@@ -0,0 +1,180 @@
+use crate::model::{candle::Candle, indicator::Indicator};
+
+use std::collections::HashMap;
+
+/// Latest timestamp already stored for every series, used to skip lines that
+/// were written by a previous run.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Watermarks {
+    candles: HashMap<String, u128>,
+    indicators: HashMap<(String, String), u128>,
+}
+
+impl Watermarks {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Raises the watermark of the candle series to the candle's timestamp.
+    pub fn observe_candle(&mut self, candle: &Candle) {
+        let latest = self.candles.entry(candle.event.clone()).or_default();
+        *latest = (*latest).max(candle.timestamp);
+    }
+
+    /// Raises the watermark of the indicator series to the indicator's
+    /// timestamp.
+    pub fn observe_indicator(&mut self, indicator: &Indicator) {
+        let latest = self
+            .indicators
+            .entry((indicator.event.clone(), indicator.property.clone()))
+            .or_default();
+        *latest = (*latest).max(indicator.timestamp);
+    }
+
+    /// Whether a candle at or before the watermark of its event was stored.
+    #[must_use]
+    pub fn contains_candle(&self, candle: &Candle) -> bool {
+        self.candles
+            .get(&candle.event)
+            .is_some_and(|latest| candle.timestamp <= *latest)
+    }
+
+    /// Whether an indicator at or before the watermark of its event and
+    /// property was stored.
+    #[must_use]
+    pub fn contains_indicator(&self, indicator: &Indicator) -> bool {
+        self.indicators
+            .get(&(indicator.event.clone(), indicator.property.clone()))
+            .is_some_and(|latest| indicator.timestamp <= *latest)
+    }
+
+    /// The watermarks stored in both `self` and `other`, keeping the earlier
+    /// one of every series. A series missing from either is dropped, so
+    /// none of its lines are skipped.
+    #[must_use]
+    pub fn min(self, other: &Self) -> Self {
+        Self {
+            candles: self
+                .candles
+                .into_iter()
+                .filter_map(|(event, latest)| {
+                    let other = other.candles.get(&event)?;
+                    Some((event, latest.min(*other)))
+                })
+                .collect(),
+            indicators: self
+                .indicators
+                .into_iter()
+                .filter_map(|(series, latest)| {
+                    let other = other.indicators.get(&series)?;
+                    Some((series, latest.min(*other)))
+                })
+                .collect(),
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.candles.is_empty() && self.indicators.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contains_candle_at_or_before_watermark() {
+        // Arrange
+        let stored = Candle {
+            timestamp: 1_714_450_920_000_000_000,
+            event: "테스트".to_string(),
+            open: 368.8,
+            high: 368.8,
+            low: 368.65,
+            close: 368.7,
+        };
+        let mut watermarks = Watermarks::new();
+        watermarks.observe_candle(&stored);
+
+        let earlier = Candle {
+            timestamp: 1_714_450_860_000_000_000,
+            ..stored.clone()
+        };
+        let later = Candle {
+            timestamp: 1_714_450_980_000_000_000,
+            ..stored.clone()
+        };
+        let other = Candle {
+            event: "다른".to_string(),
+            ..earlier
+        };
+
+        // Act & Assert
+        assert!(watermarks.contains_candle(&earlier));
+        assert!(watermarks.contains_candle(&stored));
+        assert!(!watermarks.contains_candle(&later));
+        assert!(!watermarks.contains_candle(&other));
+    }
+
+    #[test]
+    fn test_contains_indicator_per_property() {
+        // Arrange
+        let stored = Indicator {
+            timestamp: 1_714_615_260_000_000_000,
+            event: "옵션".to_string(),
+            property: "풋외국인".to_string(),
+            value: -14,
+        };
+        let mut watermarks = Watermarks::new();
+        watermarks.observe_indicator(&stored);
+
+        let earlier = Indicator {
+            timestamp: 1_714_615_200_000_000_000,
+            ..stored.clone()
+        };
+        let other = Indicator {
+            property: "콜외국인".to_string(),
+            ..earlier.clone()
+        };
+
+        // Act & Assert
+        assert!(watermarks.contains_indicator(&earlier));
+        assert!(!watermarks.contains_indicator(&other));
+    }
+
+    #[test]
+    fn test_min_keeps_earlier_watermark_of_shared_series() {
+        // Arrange
+        let candle = Candle {
+            timestamp: 1_714_450_920_000_000_000,
+            event: "테스트".to_string(),
+            open: 368.8,
+            high: 368.8,
+            low: 368.65,
+            close: 368.7,
+        };
+        let behind = Candle {
+            timestamp: 1_714_450_860_000_000_000,
+            ..candle.clone()
+        };
+        let other = Candle {
+            event: "다른".to_string(),
+            ..candle
+        };
+        let mut primary = Watermarks::new();
+        primary.observe_candle(&candle);
+        primary.observe_candle(&other);
+        let mut research = Watermarks::new();
+        research.observe_candle(&behind);
+
+        // Act
+        let watermarks = primary.min(&research);
+
+        // Assert
+        assert!(watermarks.contains_candle(&behind));
+        assert!(!watermarks.contains_candle(&candle));
+        assert!(!watermarks.contains_candle(&other));
+    }
+}
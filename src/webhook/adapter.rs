@@ -0,0 +1,408 @@
+use crate::model::{candle::Candle, indicator::Indicator};
+use crate::text::reader::Handler;
+use crate::webhook::config::Config;
+use crate::webhook::payload::Record;
+use crate::webhook::signature::{sign, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use reqwest::header::{CONTENT_TYPE, RETRY_AFTER};
+use reqwest::StatusCode;
+use tokio::runtime::{Builder, Runtime};
+
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// A [`Handler`] POSTing batches of records as JSON to a webhook, rendered
+/// with the configured [`crate::webhook::payload::Template`] and signed as
+/// described in [`crate::webhook::signature`].
+///
+/// Records are batched on a writer thread and sent when the batch is full
+/// or the flush interval passed. Requests failing with a transient error are
+/// retried with a doubling backoff, after which the batch is dropped; once
+/// the queue is full, records are dropped until the webhook catches up.
+/// Whatever is batched when the handler is dropped is sent before it
+/// returns.
+pub struct WebhookHandler {
+    sender: Option<SyncSender<String>>,
+    writer: Option<JoinHandle<()>>,
+    /// Records dropped since the queue filled up.
+    dropped: AtomicU64,
+}
+
+impl WebhookHandler {
+    pub fn new(config: Config) -> Result<Self, io::Error> {
+        let http = reqwest::Client::builder()
+            .timeout(config.timeout())
+            .build()
+            .map_err(io::Error::other)?;
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        let (sender, receiver) = mpsc::sync_channel(config.capacity);
+        let writer = thread::Builder::new()
+            .name("webhook".to_string())
+            .spawn(move || {
+                Writer {
+                    config,
+                    http,
+                    runtime,
+                    batch: Vec::new(),
+                }
+                .run(&receiver);
+            })?;
+
+        Ok(Self {
+            sender: Some(sender),
+            writer: Some(writer),
+            dropped: AtomicU64::new(0),
+        })
+    }
+
+    fn send(&self, record: &Record) -> Result<(), io::Error> {
+        let json = serde_json::to_string(record)?;
+        let Some(sender) = &self.sender else {
+            return Err(io::Error::other("Webhook writer stopped"));
+        };
+        match sender.try_send(json) {
+            Ok(()) => {
+                let dropped = self.dropped.swap(0, Ordering::Relaxed);
+                if dropped > 0 {
+                    println!("Dropped {dropped} records while the webhook queue was full");
+                }
+                Ok(())
+            }
+            Err(TrySendError::Full(_)) => {
+                if self.dropped.fetch_add(1, Ordering::Relaxed) == 0 {
+                    println!("Webhook queue is full, dropping records until it catches up");
+                }
+                Ok(())
+            }
+            Err(TrySendError::Disconnected(_)) => Err(io::Error::other("Webhook writer stopped")),
+        }
+    }
+}
+
+impl Handler for WebhookHandler {
+    fn handle_candle(&self, candle: Candle) -> Result<(), io::Error> {
+        self.send(&Record::Candle(&candle))
+    }
+
+    fn handle_indicator(&self, indicator: Indicator) -> Result<(), io::Error> {
+        self.send(&Record::Indicator(&indicator))
+    }
+}
+
+impl Drop for WebhookHandler {
+    /// Sends the batched records before returning.
+    fn drop(&mut self) {
+        self.sender = None;
+        if let Some(writer) = self.writer.take() {
+            if writer.join().is_err() {
+                println!("Webhook writer panicked");
+            }
+        }
+    }
+}
+
+/// Why a request failed, deciding whether it is retried.
+#[derive(Debug)]
+enum Failure {
+    /// The webhook could not be reached, timed out or is overloaded (408,
+    /// 429, 5xx). Retried, after `retry_after` if the webhook said so.
+    Transient {
+        reason: String,
+        retry_after: Option<Duration>,
+    },
+    /// The webhook refused the request.
+    Rejected { reason: String },
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Transient { reason, .. } => write!(f, "Transient failure: {reason}"),
+            Self::Rejected { reason } => write!(f, "Request rejected: {reason}"),
+        }
+    }
+}
+
+struct Writer {
+    config: Config,
+    http: reqwest::Client,
+    runtime: Runtime,
+    batch: Vec<String>,
+}
+
+impl Writer {
+    async fn post(&self, body: &str) -> Result<(), Failure> {
+        let mut request = self
+            .http
+            .post(&self.config.url)
+            .header(CONTENT_TYPE, "application/json");
+        if let Some(secret) = &self.config.secret {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs());
+            request = request
+                .header(TIMESTAMP_HEADER, timestamp.to_string())
+                .header(
+                    SIGNATURE_HEADER,
+                    sign(secret.as_bytes(), timestamp, body.as_bytes()),
+                );
+        }
+        let response =
+            request
+                .body(body.to_string())
+                .send()
+                .await
+                .map_err(|e| Failure::Transient {
+                    reason: e.to_string(),
+                    retry_after: None,
+                })?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+            .map(Duration::from_secs);
+        let reason = format!(
+            "{status}: {}",
+            response.text().await.unwrap_or_default().trim()
+        );
+        if status == StatusCode::REQUEST_TIMEOUT
+            || status == StatusCode::TOO_MANY_REQUESTS
+            || status.is_server_error()
+        {
+            Err(Failure::Transient {
+                reason,
+                retry_after,
+            })
+        } else {
+            Err(Failure::Rejected { reason })
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.batch.is_empty() {
+            return;
+        }
+        let body = self.config.template.render(&self.batch);
+        let mut attempt = 0;
+        loop {
+            match self.runtime.block_on(self.post(&body)) {
+                Ok(()) => break,
+                Err(Failure::Transient {
+                    reason,
+                    retry_after,
+                }) if attempt < self.config.max_retries => {
+                    let delay = self.config.backoff(attempt, retry_after);
+                    println!("Webhook request failed, retrying in {delay:?}: {reason}");
+                    thread::sleep(delay);
+                    attempt += 1;
+                }
+                Err(e) => {
+                    println!(
+                        "Dropped {} records not delivered to webhook: {e}",
+                        self.batch.len()
+                    );
+                    break;
+                }
+            }
+        }
+        self.batch.clear();
+    }
+
+    /// Batches records until the batch is full or the flush interval
+    /// passed, until the handler is dropped.
+    fn run(&mut self, receiver: &Receiver<String>) {
+        let mut deadline = Instant::now() + self.config.flush_interval();
+        loop {
+            match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(record) => {
+                    self.batch.push(record);
+                    if self.batch.len() < self.config.batch_size {
+                        continue;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.flush();
+                    return;
+                }
+            }
+            self.flush();
+            deadline = Instant::now() + self.config.flush_interval();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::candle::tests::candle;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    /// A request received by [`serve`], with its lowercase header names.
+    struct Received {
+        headers: Vec<(String, String)>,
+        body: String,
+    }
+
+    impl Received {
+        fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        }
+    }
+
+    /// Answers one request per connection with each of `statuses` in turn.
+    fn serve(listener: &TcpListener, statuses: &[u16]) -> Vec<Received> {
+        statuses
+            .iter()
+            .map(|status| {
+                let (stream, _) = listener.accept().expect("Failed to accept");
+                let mut reader = BufReader::new(stream);
+                let mut headers = Vec::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).expect("Failed to read");
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((key, value)) = line.split_once(':') {
+                        headers.push((key.to_lowercase(), value.trim().to_string()));
+                    }
+                }
+                let received = Received {
+                    headers,
+                    body: String::new(),
+                };
+                let length: usize = received
+                    .header("content-length")
+                    .and_then(|length| length.parse().ok())
+                    .unwrap_or_default();
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).expect("Failed to read body");
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {status} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                )
+                .expect("Failed to write");
+                Received {
+                    body: String::from_utf8(body).expect("Body is not UTF-8"),
+                    ..received
+                }
+            })
+            .collect()
+    }
+
+    fn config(url: String) -> Config {
+        Config {
+            url,
+            secret: None,
+            template: r#"{"count":{{count}},"records":{{records}}}"#
+                .parse()
+                .expect("Failed to parse template"),
+            batch_size: 2,
+            flush_interval_ms: 60_000,
+            timeout_ms: 5_000,
+            max_retries: 3,
+            retry_backoff_ms: 10,
+            max_backoff_ms: 1_000,
+            capacity: 16,
+        }
+    }
+
+    #[test]
+    fn test_batches_are_signed_and_retried() {
+        // Arrange
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind");
+        let url = format!("http://{}/hook", listener.local_addr().expect("No address"));
+        let server = thread::spawn(move || serve(&listener, &[503, 200]));
+        let handler = WebhookHandler::new(Config {
+            secret: Some("secret".to_string()),
+            ..config(url)
+        })
+        .expect("Failed to create handler");
+
+        // Act
+        handler
+            .handle_candle(candle("BTCUSDT", 1_714_450_980_000_000_000, 150.0))
+            .expect("Failed to handle candle");
+        handler
+            .handle_indicator(Indicator {
+                timestamp: 1_714_450_980_000_000_000,
+                event: "BTCUSDT".to_string(),
+                property: "rsi".to_string(),
+                value: 70,
+            })
+            .expect("Failed to handle indicator");
+        drop(handler);
+        let received = server.join().expect("Server panicked");
+
+        // Assert
+        assert_eq!(received.len(), 2);
+        let request = &received[1];
+        assert_eq!(received[0].body, request.body);
+        assert_eq!(
+            request.body,
+            r#"{"count":2,"records":[{"type":"candle","event":"BTCUSDT","timestamp":1714450980000000000,"open":100.0,"high":200.0,"low":50.0,"close":150.0},{"type":"indicator","timestamp":1714450980000000000,"event":"BTCUSDT","property":"rsi","value":70}]}"#
+        );
+        let timestamp: u64 = request
+            .header("x-hts-timestamp")
+            .and_then(|timestamp| timestamp.parse().ok())
+            .expect("No timestamp header");
+        assert_eq!(
+            request.header("x-hts-signature"),
+            Some(sign(b"secret", timestamp, request.body.as_bytes()).as_str())
+        );
+    }
+
+    #[test]
+    fn test_retry_after_is_capped() {
+        // Arrange
+        let config = config("http://127.0.0.1/hook".to_string());
+
+        // Act
+        let delay = config.backoff(0, Some(Duration::from_secs(86_400)));
+
+        // Assert
+        assert_eq!(delay, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_drops_records_while_webhook_is_stuck() {
+        // Arrange
+        // Connections are accepted by the OS but never answered.
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind");
+        let url = format!("http://{}/hook", listener.local_addr().expect("No address"));
+        let handler = WebhookHandler::new(Config {
+            batch_size: 1,
+            timeout_ms: 200,
+            max_retries: 0,
+            capacity: 1,
+            ..config(url)
+        })
+        .expect("Failed to create handler");
+
+        // Act
+        let results: Vec<Result<(), io::Error>> = (0..10)
+            .map(|timestamp| handler.handle_candle(candle("BTCUSDT", timestamp, 150.0)))
+            .collect();
+        let dropped = handler.dropped.load(Ordering::Relaxed);
+        drop(handler);
+        drop(listener);
+
+        // Assert
+        assert!(results.iter().all(Result::is_ok));
+        assert!(dropped > 0);
+    }
+}
@@ -0,0 +1,103 @@
+use crate::config::parse_or;
+use crate::webhook::payload::Template;
+use envconfig::Envconfig;
+use serde::Deserialize;
+
+use std::env;
+use std::error::Error;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Deserialize, Envconfig)]
+pub struct Config {
+    /// URL the batches are POSTed to.
+    pub url: String,
+    /// Key of the HMAC signature of every request; requests are unsigned
+    /// without it.
+    pub secret: Option<String>,
+    pub template: Template,
+    /// Records after which a batch is sent without waiting for the flush
+    /// interval.
+    pub batch_size: usize,
+    /// Time after which a partial batch is sent.
+    pub flush_interval_ms: u64,
+    /// Time a request may take before it is retried.
+    pub timeout_ms: u64,
+    /// Retries of a request failing with a transient error before the batch
+    /// is dropped.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on every retry.
+    pub retry_backoff_ms: u64,
+    /// Longest delay before a retry, also capping the webhook's
+    /// `Retry-After`.
+    pub max_backoff_ms: u64,
+    /// Records queued for the writer at most, after which records are
+    /// dropped until the webhook catches up.
+    pub capacity: usize,
+}
+
+impl Config {
+    fn validate(self) -> Result<Self, Box<dyn Error>> {
+        if self.flush_interval_ms == 0 {
+            return Err("Expected a flush interval above 0 ms".into());
+        }
+        if self.capacity == 0 {
+            return Err("Expected a capacity above 0".into());
+        }
+        Ok(self)
+    }
+
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Self {
+            url: env::var("WEBHOOK_URL").map_err(|e| format!("WEBHOOK_URL: {e}"))?,
+            secret: env::var("WEBHOOK_SECRET").ok(),
+            template: parse_or(env::var("WEBHOOK_TEMPLATE").ok(), Template::default())?,
+            batch_size: parse_or(env::var("WEBHOOK_BATCH_SIZE").ok(), 100)?,
+            flush_interval_ms: parse_or(env::var("WEBHOOK_FLUSH_INTERVAL_MS").ok(), 1000)?,
+            timeout_ms: parse_or(env::var("WEBHOOK_TIMEOUT_MS").ok(), 10_000)?,
+            max_retries: parse_or(env::var("WEBHOOK_MAX_RETRIES").ok(), 5)?,
+            retry_backoff_ms: parse_or(env::var("WEBHOOK_RETRY_BACKOFF_MS").ok(), 500)?,
+            max_backoff_ms: parse_or(env::var("WEBHOOK_MAX_BACKOFF_MS").ok(), 30_000)?,
+            capacity: parse_or(env::var("WEBHOOK_CAPACITY").ok(), 10_000)?,
+        }
+        .validate()
+    }
+
+    pub fn init() -> Result<Self, Box<dyn Error>> {
+        Self {
+            url: option_env!("WEBHOOK_URL")
+                .ok_or("WEBHOOK_URL is not set")?
+                .to_string(),
+            secret: option_env!("WEBHOOK_SECRET").map(str::to_string),
+            template: parse_or(option_env!("WEBHOOK_TEMPLATE"), Template::default())?,
+            batch_size: parse_or(option_env!("WEBHOOK_BATCH_SIZE"), 100)?,
+            flush_interval_ms: parse_or(option_env!("WEBHOOK_FLUSH_INTERVAL_MS"), 1000)?,
+            timeout_ms: parse_or(option_env!("WEBHOOK_TIMEOUT_MS"), 10_000)?,
+            max_retries: parse_or(option_env!("WEBHOOK_MAX_RETRIES"), 5)?,
+            retry_backoff_ms: parse_or(option_env!("WEBHOOK_RETRY_BACKOFF_MS"), 500)?,
+            max_backoff_ms: parse_or(option_env!("WEBHOOK_MAX_BACKOFF_MS"), 30_000)?,
+            capacity: parse_or(option_env!("WEBHOOK_CAPACITY"), 10_000)?,
+        }
+        .validate()
+    }
+
+    #[must_use]
+    pub const fn flush_interval(&self) -> Duration {
+        Duration::from_millis(self.flush_interval_ms)
+    }
+
+    #[must_use]
+    pub const fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+
+    /// Delay before the retry following `attempt` failed attempts, or after
+    /// the `retry_after` the webhook asked for, at most `max_backoff_ms`.
+    #[must_use]
+    pub fn backoff(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        retry_after
+            .unwrap_or_else(|| {
+                Duration::from_millis(self.retry_backoff_ms.saturating_mul(1 << attempt.min(16)))
+            })
+            .min(Duration::from_millis(self.max_backoff_ms))
+    }
+}
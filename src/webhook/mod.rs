@@ -0,0 +1,4 @@
+pub mod adapter;
+pub mod config;
+pub mod payload;
+pub mod signature;
@@ -0,0 +1,149 @@
+//! Bodies of the requests sent to the webhook.
+//!
+//! A batch is rendered into a template in which `{{records}}` stands for
+//! the JSON array of its records and `{{count}}` for their number. Records
+//! are tagged with their kind:
+//!
+//! ```text
+//! {"type":"candle","event":"BTCUSDT","timestamp":1714450980000000000,"open":100.0,...}
+//! {"type":"indicator","timestamp":1714450980000000000,"event":"BTCUSDT","property":"rsi","value":70}
+//! ```
+//!
+//! The default template, `{"records":{{records}}}`, wraps them in an object.
+
+use crate::model::{candle::Candle, indicator::Indicator};
+use serde::{Deserialize, Serialize};
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A record as sent to the webhook.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Record<'a> {
+    Candle(&'a Candle),
+    Indicator(&'a Indicator),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Records,
+    Count,
+}
+
+/// A payload template, parsed from its string form.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// The body of a batch of records, each rendered as JSON already.
+    #[must_use]
+    pub fn render(&self, records: &[String]) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(literal) => literal.clone(),
+                Part::Records => format!("[{}]", records.join(",")),
+                Part::Count => records.len().to_string(),
+            })
+            .collect()
+    }
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        Self {
+            parts: vec![
+                Part::Literal(r#"{"records":"#.to_string()),
+                Part::Records,
+                Part::Literal("}".to_string()),
+            ],
+        }
+    }
+}
+
+impl FromStr for Template {
+    type Err = Box<dyn Error>;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut rest = input;
+        while let Some(start) = rest.find("{{") {
+            let end = rest[start..]
+                .find("}}")
+                .map(|end| start + end)
+                .ok_or_else(|| format!("Unclosed placeholder in payload template {input}"))?;
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_string()));
+            }
+            parts.push(match rest[start + 2..end].trim() {
+                "records" => Part::Records,
+                "count" => Part::Count,
+                name => return Err(format!("Unknown payload placeholder {{{{{name}}}}}").into()),
+            });
+            rest = &rest[end + 2..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+        Ok(Self { parts })
+    }
+}
+
+impl TryFrom<String> for Template {
+    type Error = Box<dyn Error>;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        input.parse()
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => write!(f, "{literal}")?,
+                Part::Records => write!(f, "{{{{records}}}}")?,
+                Part::Count => write!(f, "{{{{count}}}}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_render() {
+        // Arrange
+        let template: Template = r#"{"source":"hts","count":{{count}},"data":{{ records }}}"#
+            .parse()
+            .expect("Failed to parse template");
+        let records = vec![r#"{"a":1}"#.to_string(), r#"{"b":2}"#.to_string()];
+
+        // Act
+        let body = template.render(&records);
+
+        // Assert
+        assert_eq!(
+            body,
+            r#"{"source":"hts","count":2,"data":[{"a":1},{"b":2}]}"#
+        );
+        assert_eq!(
+            Template::default().render(&records),
+            r#"{"records":[{"a":1},{"b":2}]}"#
+        );
+        assert_eq!(
+            Template::default().to_string(),
+            r#"{"records":{{records}}}"#
+        );
+        assert!("{{record}}".parse::<Template>().is_err());
+    }
+}
@@ -0,0 +1,281 @@
+use crate::model::{candle::Candle, indicator::Indicator};
+use crate::text::reader::Handler;
+use crate::websocket::config::Config;
+use crate::websocket::message::{Filter, Request, Update};
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::{Builder, Runtime};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_tungstenite::tungstenite::handshake::server::{Request as HttpRequest, Response};
+use tokio_tungstenite::tungstenite::{self, Message};
+
+use std::collections::BTreeMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// A record rendered once for every subscriber.
+#[derive(Debug)]
+struct Rendered {
+    event: String,
+    json: String,
+}
+
+/// The latest record of every candle and indicator series, sent to new
+/// subscribers.
+#[derive(Default)]
+struct Latest {
+    candles: BTreeMap<String, Arc<Rendered>>,
+    indicators: BTreeMap<(String, String), Arc<Rendered>>,
+}
+
+struct State {
+    sender: broadcast::Sender<Arc<Rendered>>,
+    latest: Mutex<Latest>,
+}
+
+impl State {
+    fn snapshot(&self, filter: &Filter) -> Vec<Arc<Rendered>> {
+        let latest = self.latest.lock().unwrap_or_else(PoisonError::into_inner);
+        latest
+            .candles
+            .values()
+            .chain(latest.indicators.values())
+            .filter(|rendered| filter.matches(&rendered.event))
+            .cloned()
+            .collect()
+    }
+}
+
+async fn send(
+    sink: &mut (impl SinkExt<Message, Error = tungstenite::Error> + Unpin),
+    updates: impl IntoIterator<Item = Arc<Rendered>>,
+) -> Result<(), tungstenite::Error> {
+    for update in updates {
+        sink.send(Message::Text(update.json.clone())).await?;
+    }
+    Ok(())
+}
+
+/// Serves one subscriber: the snapshot of its events, then their updates,
+/// until it disconnects.
+// The handshake callback's error type is set by `tungstenite`.
+#[allow(clippy::result_large_err)]
+async fn subscriber(stream: TcpStream, state: Arc<State>) -> Result<(), tungstenite::Error> {
+    let mut filter = Filter::All;
+    let socket = tokio_tungstenite::accept_hdr_async(stream, |request: &HttpRequest, response| {
+        filter = Filter::from_query(request.uri().query());
+        Ok::<Response, _>(response)
+    })
+    .await?;
+    let (mut sink, mut messages) = socket.split();
+
+    // Subscribing before taking the snapshot may repeat a record, but never
+    // misses one.
+    let mut updates = state.sender.subscribe();
+    send(&mut sink, state.snapshot(&filter)).await?;
+
+    loop {
+        tokio::select! {
+            update = updates.recv() => match update {
+                Ok(update) if filter.matches(&update.event) => send(&mut sink, [update]).await?,
+                Ok(_) => (),
+                Err(RecvError::Lagged(missed)) => println!("WebSocket subscriber missed {missed} updates"),
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            message = messages.next() => match message {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<Request>(&text) {
+                    Ok(request) => {
+                        let added = filter.apply(request);
+                        send(&mut sink, state.snapshot(&Filter::Events(added))).await?;
+                    }
+                    Err(e) => println!("Ignoring WebSocket message {text}: {e}"),
+                },
+                Some(Ok(Message::Close(_))) | None => return Ok(()),
+                Some(Ok(_)) => (),
+                Some(Err(e)) => return Err(e),
+            },
+        }
+    }
+}
+
+async fn serve(listener: TcpListener, state: Arc<State>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                let state = Arc::clone(&state);
+                tokio::spawn(async move {
+                    if let Err(e) = subscriber(stream, state).await {
+                        println!("WebSocket subscriber {peer} failed: {e}");
+                    }
+                });
+            }
+            Err(e) => println!("Failed to accept WebSocket connection: {e}"),
+        }
+    }
+}
+
+/// A [`Handler`] broadcasting every record as JSON to the subscribers of a
+/// WebSocket server, as described in [`crate::websocket::message`].
+///
+/// A subscriber first receives the latest record of every series it is
+/// subscribed to. One that falls behind by more than the configured capacity
+/// misses updates instead of slowing the reader down.
+pub struct WebSocketHandler {
+    state: Arc<State>,
+    local_addr: SocketAddr,
+    /// Runs the server until the handler is dropped.
+    _runtime: Runtime,
+}
+
+impl WebSocketHandler {
+    /// Starts listening on the configured address.
+    pub fn new(config: &Config) -> Result<Self, io::Error> {
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("websocket")
+            .enable_all()
+            .build()?;
+        let listener = runtime.block_on(TcpListener::bind(&config.addr))?;
+        let local_addr = listener.local_addr()?;
+        println!("Broadcasting to WebSocket subscribers on {local_addr}");
+
+        let (sender, _) = broadcast::channel(config.capacity.max(1));
+        let state = Arc::new(State {
+            sender,
+            latest: Mutex::new(Latest::default()),
+        });
+        runtime.spawn(serve(listener, Arc::clone(&state)));
+
+        Ok(Self {
+            state,
+            local_addr,
+            _runtime: runtime,
+        })
+    }
+
+    /// The address the server listens on, with the port picked if the
+    /// configured one was `0`.
+    #[must_use]
+    pub const fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    fn render(event: &str, update: &Update) -> Result<Arc<Rendered>, io::Error> {
+        Ok(Arc::new(Rendered {
+            event: event.to_string(),
+            json: serde_json::to_string(update)?,
+        }))
+    }
+
+    fn broadcast(&self, rendered: Arc<Rendered>) {
+        // Fails only without subscribers.
+        let _ = self.state.sender.send(rendered);
+    }
+
+    fn latest(&self) -> MutexGuard<'_, Latest> {
+        self.state
+            .latest
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Handler for WebSocketHandler {
+    fn handle_candle(&self, candle: Candle) -> Result<(), io::Error> {
+        let rendered = Self::render(&candle.event, &Update::Candle(&candle))?;
+        self.latest()
+            .candles
+            .insert(candle.event, Arc::clone(&rendered));
+        self.broadcast(rendered);
+        Ok(())
+    }
+
+    fn handle_indicator(&self, indicator: Indicator) -> Result<(), io::Error> {
+        let rendered = Self::render(&indicator.event, &Update::Indicator(&indicator))?;
+        self.latest()
+            .indicators
+            .insert((indicator.event, indicator.property), Arc::clone(&rendered));
+        self.broadcast(rendered);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::candle::tests::candle;
+    use serde_json::{json, Value};
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    async fn next(
+        socket: &mut (impl StreamExt<Item = tungstenite::Result<Message>> + Unpin),
+    ) -> Value {
+        let message = timeout(Duration::from_secs(5), socket.next())
+            .await
+            .expect("No message received")
+            .expect("Connection closed")
+            .expect("Failed to receive");
+        serde_json::from_str(message.to_text().expect("Not a text message")).expect("Not JSON")
+    }
+
+    #[test]
+    fn test_subscriber_receives_snapshot_and_filtered_updates() {
+        // Arrange
+        let handler = WebSocketHandler::new(&Config {
+            addr: "127.0.0.1:0".to_string(),
+            capacity: 16,
+        })
+        .expect("Failed to start server");
+        handler
+            .handle_candle(candle("A", 1_714_450_980_000_000_000, 150.0))
+            .expect("Failed to handle candle");
+        handler
+            .handle_candle(candle("B", 1_714_450_980_000_000_000, 150.0))
+            .expect("Failed to handle candle");
+        let url = format!("ws://{}/?events=A", handler.local_addr());
+        let runtime = Runtime::new().expect("Failed to create runtime");
+
+        // Act
+        let received: Vec<Value> = runtime.block_on(async {
+            let (mut socket, _) = tokio_tungstenite::connect_async(url)
+                .await
+                .expect("Failed to connect");
+            let mut received = vec![next(&mut socket).await];
+            handler
+                .handle_candle(candle("B", 1_714_450_980_000_000_000, 175.0))
+                .expect("Failed to handle candle");
+            handler
+                .handle_candle(candle("A", 1_714_450_980_000_000_000, 175.0))
+                .expect("Failed to handle candle");
+            received.push(next(&mut socket).await);
+            received
+        });
+
+        // Assert
+        assert_eq!(
+            received,
+            vec![
+                json!({
+                    "type": "candle",
+                    "event": "A",
+                    "timestamp": 1_714_450_980_000_000_000_u64,
+                    "open": 100.0,
+                    "high": 200.0,
+                    "low": 50.0,
+                    "close": 150.0,
+                }),
+                json!({
+                    "type": "candle",
+                    "event": "A",
+                    "timestamp": 1_714_450_980_000_000_000_u64,
+                    "open": 100.0,
+                    "high": 200.0,
+                    "low": 50.0,
+                    "close": 175.0,
+                }),
+            ]
+        );
+    }
+}
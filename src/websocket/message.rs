@@ -0,0 +1,158 @@
+//! Messages exchanged with WebSocket subscribers.
+//!
+//! The server sends every record as a JSON text message tagged with its
+//! kind:
+//!
+//! ```text
+//! {"type":"candle","event":"BTCUSDT","timestamp":1714450980000000000,"open":100.0,...}
+//! {"type":"indicator","timestamp":1714450980000000000,"event":"BTCUSDT","property":"rsi","value":70}
+//! ```
+//!
+//! A subscriber receives every event unless it connects with
+//! `?events=<event>,...` or sends `{"subscribe":["<event>",...]}`; it can
+//! drop events again with `{"unsubscribe":["<event>",...]}`.
+
+use crate::model::{candle::Candle, indicator::Indicator};
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeSet;
+
+/// A record as sent to subscribers.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Update<'a> {
+    Candle(&'a Candle),
+    Indicator(&'a Indicator),
+}
+
+/// A message from a subscriber changing the events it receives.
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Request {
+    Subscribe(Vec<String>),
+    Unsubscribe(Vec<String>),
+}
+
+/// The events a subscriber receives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    All,
+    Events(BTreeSet<String>),
+}
+
+fn decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| input.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (escaped, bytes[index]) {
+            (Some(byte), _) => {
+                output.push(byte);
+                index += 3;
+            }
+            (None, b'+') => {
+                output.push(b' ');
+                index += 1;
+            }
+            (None, byte) => {
+                output.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&output).into_owned()
+}
+
+impl Filter {
+    /// The filter of `events=<event>,...` in a request's query string, with
+    /// the events percent-decoded.
+    #[must_use]
+    pub fn from_query(query: Option<&str>) -> Self {
+        query
+            .into_iter()
+            .flat_map(|query| query.split('&'))
+            .find_map(|pair| pair.strip_prefix("events="))
+            .map_or(Self::All, |events| {
+                Self::Events(
+                    events
+                        .split(',')
+                        .map(decode)
+                        .filter(|event| !event.is_empty())
+                        .collect(),
+                )
+            })
+    }
+
+    #[must_use]
+    pub fn matches(&self, event: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Events(events) => events.contains(event),
+        }
+    }
+
+    /// Applies `request`, returning the events it added. Unsubscribing
+    /// without a filter has no effect.
+    pub fn apply(&mut self, request: Request) -> BTreeSet<String> {
+        match request {
+            Request::Subscribe(events) => {
+                let Self::Events(current) = self else {
+                    let events: BTreeSet<String> = events.into_iter().collect();
+                    *self = Self::Events(events.clone());
+                    return events;
+                };
+                events
+                    .into_iter()
+                    .filter(|event| current.insert(event.clone()))
+                    .collect()
+            }
+            Request::Unsubscribe(events) => {
+                if let Self::Events(current) = self {
+                    for event in &events {
+                        current.remove(event);
+                    }
+                }
+                BTreeSet::new()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_from_query() {
+        // Act
+        let filter = Filter::from_query(Some("token=x&events=BTCUSDT,%ED%85%8C%EC%8A%A4%ED%8A%B8"));
+
+        // Assert
+        assert!(filter.matches("BTCUSDT"));
+        assert!(filter.matches("테스트"));
+        assert!(!filter.matches("ETHUSDT"));
+        assert_eq!(Filter::from_query(None), Filter::All);
+    }
+
+    #[test]
+    fn test_filter_apply() {
+        // Arrange
+        let mut filter = Filter::All;
+
+        // Act
+        let first = filter.apply(Request::Subscribe(vec!["A".to_string(), "B".to_string()]));
+        let second = filter.apply(Request::Subscribe(vec!["B".to_string(), "C".to_string()]));
+        filter.apply(Request::Unsubscribe(vec!["A".to_string()]));
+
+        // Assert
+        assert_eq!(first.len(), 2);
+        assert_eq!(second, BTreeSet::from(["C".to_string()]));
+        assert!(!filter.matches("A"));
+        assert!(filter.matches("B"));
+        assert!(filter.matches("C"));
+    }
+}